@@ -0,0 +1,51 @@
+//! Throughput of the conversion of large generated suites.
+//!
+//! Some generated test files have 100k tests and more, the benchmarks
+//! parse and serialize files of that size.
+
+use std::{fmt::Write as _, path::PathBuf};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use louis_migrate_yaml::{
+    parse::{parse_source, ParseOptions},
+    yaml_events::YamlCursor,
+};
+
+/// A legacy file with `tests` tests, every tenth of them expected to fail.
+fn generated(tests: usize) -> String {
+    let mut source = String::from("display: unicode.dis\ntable: [en-us-g2.ctb]\ntests:\n");
+    for i in 0..tests {
+        write!(source, "  - [word{}, ⠺⠕⠗⠙{}", i, i).unwrap();
+        if i % 10 == 0 {
+            source.push_str(", {xfail: true}");
+        }
+        source.push_str("]\n");
+    }
+    source
+}
+
+fn convert(c: &mut Criterion) {
+    let path = PathBuf::from("generated.yaml");
+    let mut group = c.benchmark_group("convert");
+    group.sample_size(10);
+    for tests in [10_000, 100_000] {
+        let source = generated(tests);
+        group.throughput(Throughput::Elements(tests as u64));
+        group.bench_with_input(BenchmarkId::new("events", tests), &source, |b, source| {
+            b.iter(|| YamlCursor::new(source).unwrap().count())
+        });
+        group.bench_with_input(BenchmarkId::new("parse", tests), &source, |b, source| {
+            b.iter(|| parse_source(&path, source, &ParseOptions::default()).unwrap())
+        });
+        let (test_suites, _) = parse_source(&path, &source, &ParseOptions::default()).unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("serialize", tests),
+            &test_suites,
+            |b, test_suites| b.iter(|| serde_yaml::to_string(test_suites).unwrap()),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, convert);
+criterion_main!(benches);
@@ -0,0 +1,33 @@
+//! Walk arbitrary input with the yaml cursor, which must fail with an error
+//! rather than panic or loop forever. Run with `cargo fuzz run cursor`.
+
+#![no_main]
+
+use anyhow::Result;
+use libfuzzer_sys::fuzz_target;
+use louis_migrate_yaml::yaml_events::{Event, YamlCursor};
+
+/// Consume all events of `source` node by node, the way the parser of the
+/// test files does.
+fn walk(source: &str) -> Result<()> {
+    let mut cursor = YamlCursor::new(source)?;
+    cursor.read_stream_start()?;
+    while let Some(event) = cursor.next_event()? {
+        match event {
+            Event::StreamEnd => break,
+            Event::DocumentStart | Event::DocumentEnd => (),
+            first => {
+                cursor.skip_subtree(&first)?;
+                cursor.path();
+            }
+        }
+    }
+    Ok(())
+}
+
+fuzz_target!(|data: &[u8]| {
+    // decoding is done before parsing and is not what is fuzzed here
+    if let Ok(source) = std::str::from_utf8(data) {
+        let _ = walk(source);
+    }
+});
@@ -0,0 +1,28 @@
+//! Parse arbitrary bytes as a test file, from decoding them to reading
+//! every suite, which must fail with an error rather than panic or loop
+//! forever. Run with `cargo fuzz run parser`.
+
+#![no_main]
+
+use std::path::Path;
+
+use libfuzzer_sys::fuzz_target;
+use louis_migrate_yaml::{
+    parse::{parse_source, ParseOptions, TestSuiteIter},
+    transcode,
+};
+
+fuzz_target!(|data: &[u8]| {
+    // suite by suite, as a test runner reads a file
+    if let Ok(suites) = TestSuiteIter::new(data) {
+        for suite in suites {
+            if suite.is_err() {
+                break;
+            }
+        }
+    }
+    // and whole, as the conversion reads it
+    if let Ok((source, _)) = transcode::decode(data) {
+        let _ = parse_source(Path::new("fuzz.yaml"), &source, &ParseOptions::default());
+    }
+});
@@ -0,0 +1,230 @@
+//! Helpers dealing with the braille side of the tests.
+
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use louis_migrate_yaml::normalized::{TestMode, TestSuite};
+
+use crate::diagnostics::Position;
+
+/// Return true if `c` is in the Unicode braille patterns block (U+2800–U+28FF).
+pub fn is_braille_pattern(c: char) -> bool {
+    ('\u{2800}'..='\u{28FF}').contains(&c)
+}
+
+/// Convert one cell in liblouis dot notation (e.g. `1245`, or `0` for a
+/// blank cell) to the corresponding Unicode braille pattern. An empty
+/// cell, as in `1-` or `1--2`, is not dot notation.
+fn dots_to_cell(dots: &str) -> Option<char> {
+    match dots {
+        "" => return None,
+        "0" => return Some('\u{2800}'),
+        _ => {}
+    }
+    let mut bits = 0;
+    let mut previous = 0;
+    for c in dots.chars() {
+        let dot = c.to_digit(10).filter(|dot| (1..=8).contains(dot))?;
+        // dots are listed in ascending order without repetition
+        if dot <= previous {
+            return None;
+        }
+        previous = dot;
+        bits |= 1 << (dot - 1);
+    }
+    char::from_u32(0x2800 + bits)
+}
+
+/// Convert a Unicode braille pattern into liblouis dot notation.
+pub fn cell_to_dots(cell: char) -> Option<String> {
+    if !is_braille_pattern(cell) {
+        return None;
+    }
+    let bits = cell as u32 - 0x2800;
+    if bits == 0 {
+        return Some("0".to_string());
+    }
+    Some(
+        (1..=8)
+            .filter(|dot| bits & (1 << (dot - 1)) != 0)
+            .map(|dot| char::from_digit(dot, 10).unwrap())
+            .collect(),
+    )
+}
+
+/// Convert a string in liblouis dot notation (cells separated by `-`,
+/// words separated by spaces) to Unicode braille. Returns `None` if the
+/// string is not in dot notation. A lone cell such as `123` is
+/// indistinguishable from a number, so at least one `-` is required.
+pub fn dots_to_unicode(dots: &str) -> Option<String> {
+    if !dots.contains('-') {
+        return None;
+    }
+    let mut braille = String::new();
+    for (i, word) in dots.split(' ').enumerate() {
+        if i > 0 {
+            braille.push(' ');
+        }
+        for cell in word.split('-') {
+            braille.push(dots_to_cell(cell)?);
+        }
+    }
+    Some(braille)
+}
+
+/// Convert a string of Unicode braille patterns to liblouis dot notation.
+/// Returns `None` if the string contains anything but braille patterns and
+/// spaces.
+pub fn unicode_to_dots(braille: &str) -> Option<String> {
+    let words = braille
+        .split(' ')
+        .map(|word| {
+            word.chars()
+                .map(cell_to_dots)
+                .collect::<Option<Vec<_>>>()
+                .map(|cells| cells.join("-"))
+        })
+        .collect::<Option<Vec<_>>>()?;
+    Some(words.join(" "))
+}
+
+/// Rewrite the expected output of all forward translation tests using
+/// `convert`, leaving values it rejects untouched. Returns the number of
+/// tests that were changed.
+pub fn convert_expected(
+    test_suites: &mut [TestSuite],
+    convert: impl Fn(&str) -> Option<String>,
+) -> usize {
+    let mut changed = 0;
+    for suite in test_suites
+        .iter_mut()
+        .filter(|suite| matches!(suite.mode, TestMode::Forward | TestMode::BothDirections))
+    {
+        for test in suite.tests.iter_mut() {
+            if let Some(expected) = convert(&test.expected) {
+                if expected != test.expected {
+                    test.expected = expected;
+                    changed += 1;
+                }
+            }
+        }
+    }
+    changed
+}
+
+/// The display tables of liblouis that render braille as Unicode braille
+/// patterns
+const UCS_DISPLAY_TABLES: &[&str] = &[
+    "unicode.dis",
+    "unicode-without-blank.dis",
+    "braille-patterns.cti",
+];
+
+/// The display tables of liblouis that render braille as ASCII
+const ASCII_DISPLAY_TABLES: &[&str] = &["en-us-brf.dis", "text_nabcc.dis"];
+
+/// Whether the display table renders braille as Unicode braille patterns,
+/// `None` if the table is not one of the known ones. Suites without a
+/// display table are assumed to use one.
+fn is_ucs_display(display_table: Option<&Path>) -> Option<bool> {
+    let Some(path) = display_table else {
+        return Some(true);
+    };
+    let name = path.file_name()?.to_str()?;
+    if UCS_DISPLAY_TABLES.contains(&name) {
+        Some(true)
+    } else if ASCII_DISPLAY_TABLES.contains(&name) {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// An expected string that does not look like Unicode braille.
+#[derive(Debug)]
+pub struct SuspiciousExpected {
+    /// 1-based index of the suite in the file
+    pub suite: usize,
+    /// 1-based index of the test in the suite
+    pub test: usize,
+    pub expected: String,
+    /// Where the test starts in the legacy file, if it is known
+    pub position: Option<Position>,
+}
+
+impl fmt::Display for SuspiciousExpected {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "suite {}, test {}: expected {:?} is not Unicode braille",
+            self.suite, self.test, self.expected
+        )
+    }
+}
+
+/// A display table that is not known to render braille either as Unicode
+/// braille or as ASCII.
+#[derive(Debug)]
+pub struct UnknownDisplayTable {
+    /// 1-based index of the suite in the file
+    pub suite: usize,
+    pub display_table: PathBuf,
+    /// Where the suite starts in the legacy file, if it is known
+    pub position: Option<Position>,
+}
+
+impl fmt::Display for UnknownDisplayTable {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "suite {}: display table {} is not known to render Unicode braille, \
+             its expected output is not checked",
+            self.suite,
+            self.display_table.display()
+        )
+    }
+}
+
+/// Check that the expected output of all forward translation tests consists
+/// only of Unicode braille patterns and whitespace. Suites with an ASCII
+/// display table are skipped, as their output is legitimately ASCII, and
+/// so are suites with a display table that is not known, which are
+/// returned as well.
+pub fn validate(test_suites: &[TestSuite]) -> (Vec<SuspiciousExpected>, Vec<UnknownDisplayTable>) {
+    let mut suspicious = Vec::new();
+    let mut unknown = Vec::new();
+    for (i, suite) in test_suites.iter().enumerate() {
+        if !matches!(suite.mode, TestMode::Forward | TestMode::BothDirections) {
+            continue;
+        }
+        match is_ucs_display(suite.display_table.as_deref()) {
+            Some(true) => (),
+            Some(false) => continue,
+            None => {
+                unknown.push(UnknownDisplayTable {
+                    suite: i + 1,
+                    display_table: suite.display_table.clone().unwrap_or_default(),
+                    position: suite.source.map(Position::from),
+                });
+                continue;
+            }
+        }
+        for (j, test) in suite.tests.iter().enumerate() {
+            if !test
+                .expected
+                .chars()
+                .all(|c| is_braille_pattern(c) || c.is_whitespace())
+            {
+                suspicious.push(SuspiciousExpected {
+                    suite: i + 1,
+                    test: j + 1,
+                    expected: test.expected.clone(),
+                    position: test.source.map(Position::from),
+                });
+            }
+        }
+    }
+    (suspicious, unknown)
+}
@@ -0,0 +1,181 @@
+//! Building normalized suites in code.
+//!
+//! For tools that generate tests rather than convert them, e.g. from a
+//! word list and the output of liblouis:
+//!
+//! ```ignore
+//! let suite = TestSuite::builder()
+//!     .table("de-g2.ctb")
+//!     .forward()
+//!     .test("Haus", "⠓⠁⠥⠎")
+//!     .build()?;
+//! ```
+//!
+//! The suite is checked as a whole by [`TestSuiteBuilder::build`], so the
+//! settings may be given in any order.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{
+    normalized::{Mode, Table, Test, TestMode, TestSuite},
+    parse,
+};
+
+impl TestSuite {
+    /// A builder for a suite, see [`crate::builder`].
+    pub fn builder() -> TestSuiteBuilder {
+        TestSuiteBuilder::default()
+    }
+}
+
+/// A suite under construction, see [`TestSuite::builder`]
+#[derive(Debug, Default)]
+pub struct TestSuiteBuilder {
+    name: Option<String>,
+    comments: Vec<String>,
+    tags: BTreeSet<String>,
+    display_table: Option<PathBuf>,
+    table: Option<Table>,
+    mode: TestMode,
+    translation_modes: BTreeSet<Mode>,
+    variables: BTreeMap<String, serde_yaml::Value>,
+    tests: Vec<Test>,
+}
+
+impl TestSuiteBuilder {
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.name = Some(name.into());
+        self
+    }
+
+    pub fn comment(mut self, comment: impl Into<String>) -> Self {
+        self.comments.push(comment.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.insert(tag.into());
+        self
+    }
+
+    pub fn display_table(mut self, display_table: impl Into<PathBuf>) -> Self {
+        self.display_table = Some(display_table.into());
+        self
+    }
+
+    /// Translate with the single table file `table`.
+    pub fn table(self, table: impl Into<PathBuf>) -> Self {
+        self.table_as(Table::Single(table.into()))
+    }
+
+    /// Translate with the list of table files `tables`.
+    pub fn tables<P: Into<PathBuf>>(self, tables: impl IntoIterator<Item = P>) -> Self {
+        self.table_as(Table::List(tables.into_iter().map(Into::into).collect()))
+    }
+
+    /// Translate with any form of [`Table`], e.g. a metadata query.
+    pub fn table_as(mut self, table: Table) -> Self {
+        self.table = Some(table);
+        self
+    }
+
+    pub fn mode(mut self, mode: TestMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn forward(self) -> Self {
+        self.mode(TestMode::Forward)
+    }
+
+    pub fn backward(self) -> Self {
+        self.mode(TestMode::Backward)
+    }
+
+    pub fn both_directions(self) -> Self {
+        self.mode(TestMode::BothDirections)
+    }
+
+    pub fn display(self) -> Self {
+        self.mode(TestMode::Display)
+    }
+
+    pub fn hyphenate(self) -> Self {
+        self.mode(TestMode::Hyphenate)
+    }
+
+    pub fn hyphenate_braille(self) -> Self {
+        self.mode(TestMode::HyphenateBraille)
+    }
+
+    /// Run all tests of the suite with the translation mode `mode`.
+    pub fn translation_mode(mut self, mode: Mode) -> Self {
+        self.translation_modes.insert(mode);
+        self
+    }
+
+    pub fn variable(mut self, key: impl Into<String>, value: impl Into<serde_yaml::Value>) -> Self {
+        self.variables.insert(key.into(), value.into());
+        self
+    }
+
+    /// Add a test of `input` with the expected output `expected`, in the
+    /// sense of the test mode of the suite.
+    pub fn test(self, input: impl Into<String>, expected: impl Into<String>) -> Self {
+        self.test_with(Test {
+            input: input.into(),
+            expected: expected.into(),
+            ..Default::default()
+        })
+    }
+
+    /// Add `test` with all its options, e.g. an xfail or positions.
+    pub fn test_with(mut self, test: Test) -> Self {
+        self.tests.push(test);
+        self
+    }
+
+    /// The suite, if it has a table and tests that fit its test mode.
+    pub fn build(self) -> Result<TestSuite> {
+        let table = self
+            .table
+            .ok_or_else(|| anyhow!("the suite has no table"))?;
+        if self.tests.is_empty() {
+            bail!("the suite has no tests");
+        }
+        let mut ids = BTreeSet::new();
+        for (i, test) in self.tests.iter().enumerate() {
+            if let Some(problem) = parse::check_test_mode(test, &self.mode) {
+                bail!("test {}: {}", i + 1, problem);
+            }
+            if test.expected_backward.is_some() && self.mode != TestMode::BothDirections {
+                bail!(
+                    "test {}: expected_backward is only used by bothDirections suites",
+                    i + 1
+                );
+            }
+            if let Some(id) = &test.id {
+                if !ids.insert(id) {
+                    bail!("test {}: duplicate id {:?}", i + 1, id);
+                }
+            }
+        }
+        Ok(TestSuite {
+            name: self.name,
+            comments: self.comments,
+            tags: self.tags,
+            display_table: self.display_table,
+            table,
+            mode: self.mode,
+            translation_modes: self.translation_modes,
+            variables: self.variables,
+            source: None,
+            tests: self.tests,
+        })
+    }
+}
@@ -0,0 +1,84 @@
+//! A cache of conversion results, keyed by the content of the input.
+//!
+//! Converting the whole corpus again after changing a single file is
+//! wasteful, so the hash of every conversion is kept in a small JSON file
+//! next to the hash of the input it was made from. The cache is only valid
+//! for the executable that wrote it, a rebuilt converter may convert
+//! differently and starts with an empty cache.
+
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// The cache file used unless another one is given
+pub const DEFAULT_PATH: &str = ".louis-migrate-cache.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CacheFile {
+    /// The SHA-256 of the executable that did the conversions
+    converter: String,
+    /// The hash of each conversion by the SHA-256 of its input
+    conversions: BTreeMap<String, String>,
+}
+
+pub struct Cache {
+    path: PathBuf,
+    file: CacheFile,
+    changed: bool,
+}
+
+/// The SHA-256 of `bytes`, hex encoded.
+pub fn hash(bytes: impl AsRef<[u8]>) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+impl Cache {
+    /// Load the cache at `path`. A missing or unreadable cache, or one
+    /// written by another executable, is treated as empty.
+    pub fn load(path: &Path) -> Result<Self> {
+        let converter = hash(fs::read(env::current_exe()?)?);
+        let file = fs::read_to_string(path)
+            .ok()
+            .and_then(|json| serde_json::from_str::<CacheFile>(&json).ok())
+            .filter(|file| file.converter == converter)
+            .unwrap_or_else(|| {
+                log::info!("{}: starting with an empty cache", path.display());
+                CacheFile {
+                    converter,
+                    ..Default::default()
+                }
+            });
+        Ok(Cache {
+            path: path.to_path_buf(),
+            file,
+            changed: false,
+        })
+    }
+
+    /// The hash of the conversion of the input with hash `input`, if it
+    /// has been converted before.
+    pub fn get(&self, input: &str) -> Option<&str> {
+        self.file.conversions.get(input).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, input: String, conversion: String) {
+        if self.get(&input) != Some(&conversion) {
+            self.file.conversions.insert(input, conversion);
+            self.changed = true;
+        }
+    }
+
+    /// Write the cache back if anything was added to it.
+    pub fn save(&self) -> Result<()> {
+        if self.changed {
+            fs::write(&self.path, serde_json::to_string_pretty(&self.file)? + "\n")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,175 @@
+//! Reading legacy files the way `lou_checkyaml`, the yaml test harness of
+//! liblouis, reads them.
+//!
+//! The conversion is lenient: it accepts things the harness does not and
+//! reads some values in their YAML 1.1 sense. To check that a conversion
+//! keeps the meaning of the tests, the legacy side has to be read as the
+//! harness reads it instead, which differs in that
+//!
+//! - an xfail value other than `false` or `off` marks the test as expected
+//!   to fail, including `no`, `0` and reasons such as `no reason`
+//! - there are no `defaults` and no keys other than `display`, `table`,
+//!   `flags`, `variables` and `tests`, whatever `--unknown-keys` says
+//! - a test is a sequence of input and expected output, optionally
+//!   followed by its options, never a mapping, and only the test modes of
+//!   [`DEFAULT_IDENTITY_TESTS`] allow leaving out the expected output
+//!
+//! Only what decides the outcome of a translation test is kept: the
+//! tables, the test mode, the translation modes and the xfail. The other
+//! options and the `variables` are skipped. `tests` blocks without tests
+//! make no suite, as in the conversion.
+
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::{
+    normalized::{DirectionXfail, Table, Test, TestSuite, Xfail},
+    parse::{key_alias, parse_flags, parse_modes, parse_table, Flags, DEFAULT_IDENTITY_TESTS},
+    transcode,
+    yaml_events::{Event, YamlCursor},
+};
+
+/// Read the legacy file at `path` into its suites.
+pub fn read_file(path: &Path) -> Result<Vec<TestSuite>> {
+    let (source, _) = transcode::read_to_string(path)?;
+    read_source(&source)
+}
+
+/// Read `source`, the contents of a legacy file, into its suites.
+pub fn read_source(source: &str) -> Result<Vec<TestSuite>> {
+    let mut iter = YamlCursor::new(source)?;
+    read_suites(&mut iter).map_err(|e| iter.locate(e))
+}
+
+fn read_suites(iter: &mut YamlCursor) -> Result<Vec<TestSuite>> {
+    iter.read_stream_start()?;
+    iter.read_document_start()?;
+    iter.read_mapping_start()?;
+    let mut suites = Vec::new();
+    let mut display_table = None;
+    let mut table: Option<Table> = None;
+    let mut flags = Flags::default();
+    while let Some(event) = iter.next_event()? {
+        match event {
+            Event::Scalar { value, .. } => match key_alias(&value).unwrap_or(&value) {
+                "display" => display_table = Some(iter.read_scalar()?.into()),
+                "table" => table = Some(parse_table(iter)?),
+                "flags" => flags = parse_flags(iter)?,
+                "variables" => iter.skip_value()?,
+                "tests" => {
+                    let table = table
+                        .clone()
+                        .ok_or_else(|| anyhow!("No table defined for tests"))?;
+                    let identity = DEFAULT_IDENTITY_TESTS.contains(&flags.test_mode);
+                    let tests = read_tests(iter, identity)?;
+                    if tests.is_empty() {
+                        continue;
+                    }
+                    suites.push(TestSuite {
+                        name: None,
+                        comments: Vec::new(),
+                        tags: Default::default(),
+                        display_table: display_table.clone(),
+                        table,
+                        mode: flags.test_mode.clone(),
+                        translation_modes: flags.modes.clone(),
+                        variables: Default::default(),
+                        source: None,
+                        tests,
+                    });
+                }
+                other => bail!(
+                    "Unexpected key {:?}, expected `display`, `table`, `flags`, `variables` \
+                     or `tests`",
+                    other
+                ),
+            },
+            Event::MappingEnd => break,
+            _ => bail!("Expected Scalar or MappingEnd, got {:?}", event),
+        }
+    }
+    iter.read_document_end()?;
+    iter.read_stream_end()?;
+    Ok(suites)
+}
+
+fn read_tests(iter: &mut YamlCursor, identity: bool) -> Result<Vec<Test>> {
+    iter.read_sequence_start()?;
+    let mut tests = Vec::new();
+    while let Some(event) = iter.next_event()? {
+        match event {
+            Event::SequenceStart => tests.push(read_test(iter, identity)?),
+            Event::SequenceEnd => break,
+            _ => bail!("Expected SequenceStart or SequenceEnd, got {:?}", event),
+        }
+    }
+    Ok(tests)
+}
+
+/// Read a test, i.e. everything after its SequenceStart. With `identity`
+/// the expected output may be left out, it is then the same as the input.
+fn read_test(iter: &mut YamlCursor, identity: bool) -> Result<Test> {
+    let input = iter.read_scalar()?;
+    let (expected, next) = match iter.next_event()? {
+        Some(Event::Scalar { value, .. }) => (value, iter.next_event()?),
+        next if identity => (input.clone(), next),
+        _ => bail!("Test {:?} without expected output", input),
+    };
+    let mut test = Test {
+        input,
+        expected,
+        ..Default::default()
+    };
+    match next {
+        Some(Event::SequenceEnd) => return Ok(test),
+        Some(Event::MappingStart) => (),
+        other => bail!("Expected SequenceEnd or MappingStart, got {:?}", other),
+    }
+    while let Some(event) = iter.next_event()? {
+        match event {
+            Event::Scalar { value, .. } => match value.as_str() {
+                "xfail" => test.xfail = read_xfail(iter)?,
+                "mode" => test.mode = parse_modes(iter)?,
+                _ => iter.skip_value()?,
+            },
+            Event::MappingEnd => break,
+            _ => bail!("Expected Scalar or MappingEnd, got {:?}", event),
+        }
+    }
+    iter.read_sequence_end()?;
+    Ok(test)
+}
+
+/// Whether the xfail value `value` marks a test as expected to fail.
+fn fails(value: &str) -> bool {
+    !matches!(value, "false" | "off")
+}
+
+/// Read an xfail value, a scalar or a mapping with a scalar for `forward`
+/// and `backward`. Reasons are read as true.
+fn read_xfail(iter: &mut YamlCursor) -> Result<Xfail> {
+    match iter.next_event()? {
+        Some(Event::Scalar { value, .. }) => Ok(Xfail::Scalar(fails(&value))),
+        Some(Event::MappingStart) => {
+            let mut forward = DirectionXfail::default();
+            let mut backward = DirectionXfail::default();
+            while let Some(event) = iter.next_event()? {
+                match event {
+                    Event::Scalar { value, .. } => {
+                        let xfail = DirectionXfail::Scalar(fails(&iter.read_scalar()?));
+                        match value.as_str() {
+                            "forward" => forward = xfail,
+                            "backward" => backward = xfail,
+                            other => bail!("Expected 'forward' or 'backward', got {:?}", other),
+                        }
+                    }
+                    Event::MappingEnd => break,
+                    _ => bail!("Expected Scalar or MappingEnd, got {:?}", event),
+                }
+            }
+            Ok(Xfail::Map { forward, backward })
+        }
+        other => bail!("Expected Scalar or MappingStart, got {:?}", other),
+    }
+}
@@ -0,0 +1,89 @@
+//! Capture of yaml comments.
+//!
+//! libyaml does not report comments in its event stream, so they are
+//! recovered with the line-based scan of the source in [`crate::scan`],
+//! which collects the full-line comments that precede the suites and
+//! tests. The results are then matched to the parsed model by position.
+
+use louis_migrate_yaml::normalized::TestSuite;
+
+use crate::scan::{self, Line};
+
+#[derive(Debug, Default)]
+struct ScannedSuite {
+    comments: Vec<String>,
+    tests: Vec<Vec<String>>,
+    /// Whether the `tests:` line has an empty value, see [`Line::Key`]
+    empty: bool,
+}
+
+fn scan(source: &str) -> Vec<ScannedSuite> {
+    let mut suites: Vec<ScannedSuite> = Vec::new();
+    let mut pending: Vec<String> = Vec::new();
+
+    for (_, line) in scan::scan(source) {
+        match line {
+            Line::Comment { text, in_test } => {
+                let current_test = suites
+                    .last_mut()
+                    .and_then(|suite| suite.tests.last_mut())
+                    .filter(|_| in_test);
+                match current_test {
+                    Some(comments) => comments.push(text.to_string()),
+                    None => pending.push(text.to_string()),
+                }
+            }
+            // comments before `table:` and `flags:` also belong to the
+            // suite that is started by the following `tests:`
+            Line::Key {
+                key: "tests",
+                empty,
+            } => suites.push(ScannedSuite {
+                comments: std::mem::take(&mut pending),
+                empty,
+                ..Default::default()
+            }),
+            Line::Key { .. } => (),
+            Line::Test => {
+                if let Some(suite) = suites.last_mut() {
+                    suite.tests.push(std::mem::take(&mut pending));
+                }
+            }
+        }
+    }
+    suites.retain(|suite| !(suite.empty && suite.tests.is_empty()));
+    suites
+}
+
+/// Attach the comments found in `source` to the suites and tests parsed
+/// from it. Returns a warning for every suite whose tests could not be
+/// matched up, e.g. because they are written in flow style.
+pub fn attach(test_suites: &mut [TestSuite], source: &str) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for (i, (suite, scanned)) in test_suites.iter_mut().zip(scan(source)).enumerate() {
+        suite.comments = scanned.comments;
+        if scanned.tests.len() != suite.tests.len() {
+            if scanned.tests.iter().any(|comments| !comments.is_empty()) {
+                warnings.push(format!(
+                    "suite {}: could not match comments to tests, they are dropped",
+                    i + 1
+                ));
+            }
+            continue;
+        }
+        for (test, comments) in suite.tests.iter_mut().zip(scanned.tests) {
+            test.comments = comments;
+        }
+    }
+    warnings
+}
+
+/// Remove the comments recorded by [`attach`].
+pub fn forget(test_suites: &mut [TestSuite]) {
+    for suite in test_suites {
+        suite.comments.clear();
+        for test in &mut suite.tests {
+            test.comments.clear();
+        }
+    }
+}
@@ -0,0 +1,78 @@
+//! Settings shared by many invocations, read from a TOML file.
+//!
+//! Batch migrations run the converter over and over with the same output
+//! directory, table settings and unknown-key policy. These can be given
+//! once in `louis-migrate.toml` in the working directory, or in the file
+//! passed with `--config`. The keys are named like the command line
+//! options, e.g.
+//!
+//! ```toml
+//! output-dir = "converted"
+//! tables-dir = "tables"
+//! map-table-prefix = ["../tables/=tables/"]
+//! unknown-keys = "preserve"
+//! ```
+//!
+//! An option given on the command line takes precedence over the file.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use clap::{parser::ValueSource, ArgMatches, ValueEnum};
+use louis_migrate_yaml::parse::UnknownKeys;
+use serde::Deserialize;
+
+use crate::Args;
+
+/// The config file read from the working directory unless `--config` is
+/// given
+pub const FILE_NAME: &str = "louis-migrate.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+struct Config {
+    output_dir: Option<PathBuf>,
+    tables_dir: Option<PathBuf>,
+    #[serde(default)]
+    map_table_prefix: Vec<String>,
+    unknown_keys: Option<String>,
+}
+
+fn load(path: &Path) -> Result<Config> {
+    let source = fs::read_to_string(path)?;
+    toml::from_str(&source).with_context(|| format!("invalid config file {}", path.display()))
+}
+
+/// Fill in the options of `args` that were not given on the command line,
+/// as recorded in `matches`, from the config file.
+pub fn apply(args: &mut Args, matches: &ArgMatches) -> Result<()> {
+    let path = match &args.config {
+        Some(path) => path.clone(),
+        None if Path::new(FILE_NAME).is_file() => PathBuf::from(FILE_NAME),
+        None => return Ok(()),
+    };
+    let config = load(&path)?;
+    log::info!("{}: using the settings of this file", path.display());
+    let given = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    if args.output.is_none() && !given("output_dir") {
+        args.output_dir = config.output_dir;
+    }
+    if let (Some(dir), false) = (config.tables_dir, given("tables_dir")) {
+        args.tables_dir = dir;
+    }
+    if !given("map_table_prefix") {
+        for mapping in &config.map_table_prefix {
+            args.map_table_prefix
+                .push(mapping.parse().map_err(|e: String| anyhow!(e))?);
+        }
+    }
+    if let (Some(policy), false) = (config.unknown_keys, given("unknown_keys") || args.strict) {
+        args.unknown_keys = UnknownKeys::from_str(&policy, false)
+            .map_err(|e| anyhow!("unknown-keys in {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
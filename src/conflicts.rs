@@ -0,0 +1,299 @@
+//! Detection and resolution of contradictory tests.
+//!
+//! Two tests of a suite contradict each other if they have the same input
+//! and modes but expect different output: liblouis cannot pass both. Such
+//! conflicts are reported as warnings, or with `--interactive` presented
+//! one by one with the lines they come from, asking which test to keep or
+//! to mark as expected to fail. The decisions are written into the output,
+//! as comments on the kept tests or as xfail reasons, and can be logged to
+//! a file as JSON lines.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs::OpenOptions,
+    io::{BufRead, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use louis_migrate_yaml::normalized::{Mode, SourcePosition, Test, TestSuite, Xfail};
+use serde::Serialize;
+
+use crate::progress::Progress;
+
+/// The most lines of a test shown when asking about it
+const MAX_SOURCE_LINES: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Decision {
+    KeepFirst,
+    KeepSecond,
+    KeepBoth,
+    XfailFirst,
+    XfailSecond,
+}
+
+impl Decision {
+    fn from_answer(answer: &str) -> Option<Self> {
+        match answer.trim().to_lowercase().as_str() {
+            "1" => Some(Decision::KeepFirst),
+            "2" => Some(Decision::KeepSecond),
+            "b" => Some(Decision::KeepBoth),
+            "x1" => Some(Decision::XfailFirst),
+            "x2" => Some(Decision::XfailSecond),
+            _ => None,
+        }
+    }
+}
+
+/// One side of a conflict, as recorded in the resolution log
+#[derive(Debug, Serialize)]
+pub struct Side {
+    pub expected: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+}
+
+impl Side {
+    fn new(test: &Test) -> Self {
+        Side {
+            expected: test.expected.clone(),
+            line: test.source.map(|source| source.line),
+        }
+    }
+}
+
+/// A conflict and how it was resolved
+#[derive(Debug, Serialize)]
+pub struct Resolution {
+    pub file: PathBuf,
+    /// The suite of the conflicting tests, counting from 1
+    pub suite: usize,
+    pub input: String,
+    pub first: Side,
+    pub second: Side,
+    pub decision: Decision,
+}
+
+/// The indices of the tests of `suite` with the same input and modes, for
+/// every input with more than one test, in the order of the tests.
+fn groups(suite: &TestSuite) -> Vec<Vec<usize>> {
+    let mut groups: HashMap<(&str, &BTreeSet<Mode>), Vec<usize>> =
+        HashMap::with_capacity(suite.tests.len());
+    for (i, test) in suite.tests.iter().enumerate() {
+        groups.entry((&test.input, &test.mode)).or_default().push(i);
+    }
+    let mut groups: Vec<_> = groups
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .collect();
+    groups.sort();
+    groups
+}
+
+/// `test`, the `number`-th of its suite, as referred to in messages.
+fn describe(test: &Test, number: usize) -> String {
+    match test.source {
+        Some(source) => format!("the test on line {}", source.line),
+        None => format!("test {}", number),
+    }
+}
+
+/// Warn about every test of `test_suites` whose expected output differs
+/// from that of an earlier test with the same input and modes.
+pub fn warn(yaml: &Path, test_suites: &[TestSuite], progress: &Progress) {
+    for (i, suite) in test_suites.iter().enumerate() {
+        for group in groups(suite) {
+            let first = &suite.tests[group[0]];
+            for &j in &group[1..] {
+                let test = &suite.tests[j];
+                if test.expected != first.expected {
+                    progress.warn_at(
+                        yaml,
+                        test.source.map(Into::into),
+                        "conflicting-tests",
+                        format!(
+                            "suite {}: input {:?} expects {:?}, but {} expects {:?} \
+                             (resolve with --interactive)",
+                            i + 1,
+                            test.input,
+                            test.expected,
+                            describe(first, group[0] + 1),
+                            first.expected
+                        ),
+                    );
+                }
+            }
+        }
+    }
+}
+
+/// The lines of `source` the test at `position` was written on: its first
+/// line and the lines indented deeper than its start.
+fn source_lines(source: &str, position: SourcePosition) -> Vec<(usize, &str)> {
+    source
+        .lines()
+        .enumerate()
+        .skip(position.line.saturating_sub(1))
+        .take_while(|&(i, line)| {
+            let indent = line.chars().take_while(|c| c.is_whitespace()).count();
+            i + 1 == position.line || (indent >= position.column && !line.trim().is_empty())
+        })
+        .take(MAX_SOURCE_LINES)
+        .map(|(i, line)| (i + 1, line))
+        .collect()
+}
+
+/// Show `test`, the `number`-th of its suite, by the lines of `source` it
+/// was written on if known, and as JSON otherwise.
+fn show(
+    prompt: &mut impl Write,
+    label: &str,
+    test: &Test,
+    number: usize,
+    source: Option<&str>,
+) -> Result<()> {
+    writeln!(prompt, "  {} ({}):", label, describe(test, number))?;
+    match (source, test.source) {
+        (Some(source), Some(position)) => {
+            for (line, text) in source_lines(source, position) {
+                writeln!(prompt, "  {:>5} | {}", line, text)?;
+            }
+        }
+        _ => writeln!(prompt, "        {}", serde_json::to_string(test)?)?,
+    }
+    Ok(())
+}
+
+/// Ask how to resolve the conflict between `first` and `second` until the
+/// answer is understood.
+fn ask(
+    answers: &mut impl BufRead,
+    prompt: &mut impl Write,
+    heading: &str,
+    (first, first_number): (&Test, usize),
+    (second, second_number): (&Test, usize),
+    source: Option<&str>,
+) -> Result<Decision> {
+    writeln!(prompt, "{}", heading)?;
+    show(prompt, "first", first, first_number, source)?;
+    show(prompt, "second", second, second_number, source)?;
+    loop {
+        write!(
+            prompt,
+            "keep [1] first, [2] second, [b]oth, or mark as xfail [x1] first, [x2] second? "
+        )?;
+        prompt.flush()?;
+        let mut answer = String::new();
+        if answers.read_line(&mut answer)? == 0 {
+            bail!("no answer to the conflict, input ended");
+        }
+        if let Some(decision) = Decision::from_answer(&answer) {
+            return Ok(decision);
+        }
+        writeln!(prompt, "unknown answer {:?}", answer.trim())?;
+    }
+}
+
+/// Ask about every conflict in `test_suites` and apply the decisions. A
+/// test that is kept over another takes the place of the test it conflicts
+/// with, later tests are compared with it. `source` is the legacy file the
+/// suites were parsed from, to show the tests in, if they were.
+pub fn resolve(
+    yaml: &Path,
+    source: Option<&str>,
+    test_suites: &mut [TestSuite],
+    answers: &mut impl BufRead,
+    prompt: &mut impl Write,
+) -> Result<Vec<Resolution>> {
+    let mut resolutions = Vec::new();
+    for (i, suite) in test_suites.iter_mut().enumerate() {
+        let mut removed = BTreeSet::new();
+        for group in groups(suite) {
+            let mut kept = group[0];
+            for &j in &group[1..] {
+                let (first, second) = (&suite.tests[kept], &suite.tests[j]);
+                if first.expected == second.expected {
+                    continue;
+                }
+                let heading = format!(
+                    "{}: conflicting tests in suite {} ({}) for input {:?}:",
+                    yaml.display(),
+                    i + 1,
+                    suite
+                        .mode
+                        .to_possible_value()
+                        .map(|value| value.get_name().to_string())
+                        .unwrap_or_default(),
+                    first.input
+                );
+                let decision = ask(
+                    answers,
+                    prompt,
+                    &heading,
+                    (first, kept + 1),
+                    (second, j + 1),
+                    source,
+                )?;
+                resolutions.push(Resolution {
+                    file: yaml.to_path_buf(),
+                    suite: i + 1,
+                    input: first.input.clone(),
+                    first: Side::new(first),
+                    second: Side::new(second),
+                    decision,
+                });
+                let first_name = describe(first, kept + 1);
+                let second_name = describe(second, j + 1);
+                let first_expected = first.expected.clone();
+                let second_expected = second.expected.clone();
+                match decision {
+                    Decision::KeepFirst => {
+                        suite.tests[kept].comments.push(format!(
+                            "kept over the conflicting expected output {:?} of {}",
+                            second_expected, second_name
+                        ));
+                        removed.insert(j);
+                    }
+                    Decision::KeepSecond => {
+                        suite.tests[j].comments.push(format!(
+                            "kept over the conflicting expected output {:?} of {}",
+                            first_expected, first_name
+                        ));
+                        removed.insert(kept);
+                        kept = j;
+                    }
+                    Decision::KeepBoth => {
+                        suite.tests[j]
+                            .comments
+                            .push(format!("conflicts with {}, both kept", first_name));
+                    }
+                    Decision::XfailFirst => {
+                        suite.tests[kept].xfail =
+                            Xfail::Reason(format!("contradicts {}", second_name));
+                    }
+                    Decision::XfailSecond => {
+                        suite.tests[j].xfail = Xfail::Reason(format!("contradicts {}", first_name));
+                    }
+                }
+            }
+        }
+        let mut index = 0;
+        suite.tests.retain(|_| {
+            index += 1;
+            !removed.contains(&(index - 1))
+        });
+    }
+    Ok(resolutions)
+}
+
+/// Append `resolutions` to the log at `path`, one JSON object per line.
+pub fn log(path: &Path, resolutions: &[Resolution]) -> Result<()> {
+    let mut log = OpenOptions::new().create(true).append(true).open(path)?;
+    for resolution in resolutions {
+        writeln!(log, "{}", serde_json::to_string(resolution)?)?;
+    }
+    Ok(())
+}
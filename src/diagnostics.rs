@@ -0,0 +1,212 @@
+//! Warnings and errors in a machine-readable form for editor integration.
+//!
+//! With `--diagnostics json` every warning and error of a run is collected
+//! and written as a JSON array of `{file, line, column, severity, code,
+//! message}` objects when the run ends: to stderr instead of the
+//! human-readable messages, or with `json=PATH` to a file in addition to
+//! them. `line` and `column` are null where the position is not known.
+
+use std::{
+    error::Error,
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+use anyhow::Result;
+use louis_migrate_yaml::normalized::SourcePosition;
+use serde::Serialize;
+
+use crate::snippet;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+/// A position in a source file. Lines and columns count from 1, columns
+/// in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl From<SourcePosition> for Position {
+    fn from(position: SourcePosition) -> Self {
+        Position {
+            line: position.line,
+            column: position.column,
+        }
+    }
+}
+
+impl From<Position> for SourcePosition {
+    fn from(position: Position) -> Self {
+        SourcePosition {
+            line: position.line,
+            column: position.column,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Diagnostic {
+    file: PathBuf,
+    line: Option<usize>,
+    column: Option<usize>,
+    severity: Severity,
+    code: &'static str,
+    message: String,
+}
+
+/// Where diagnostics are written, given on the command line as
+/// `json[=PATH]`. Without a path they are written to stderr.
+#[derive(Debug, Clone)]
+pub struct DiagnosticsSpec {
+    path: Option<PathBuf>,
+}
+
+impl FromStr for DiagnosticsSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = match s.split_once('=') {
+            Some((format, path)) => (format, Some(PathBuf::from(path))),
+            None => (s, None),
+        };
+        match format {
+            "json" => Ok(DiagnosticsSpec { path }),
+            other => Err(format!(
+                "unknown diagnostics format {:?}, expected json",
+                other
+            )),
+        }
+    }
+}
+
+static SPEC: OnceLock<DiagnosticsSpec> = OnceLock::new();
+static COLLECTED: Mutex<Vec<Diagnostic>> = Mutex::new(Vec::new());
+static WARNINGS: AtomicUsize = AtomicUsize::new(0);
+
+/// Start collecting diagnostics if `spec` is given.
+pub fn init(spec: Option<DiagnosticsSpec>) {
+    if let Some(spec) = spec {
+        SPEC.set(spec).expect("diagnostics are initialized once");
+    }
+}
+
+/// Whether warnings and errors are to be printed in human-readable form,
+/// i.e. they are not written to stderr as JSON.
+pub fn human() -> bool {
+    !matches!(SPEC.get(), Some(DiagnosticsSpec { path: None }))
+}
+
+/// Record a diagnostic about `file` without printing it.
+pub fn record(
+    file: &Path,
+    position: Option<Position>,
+    severity: Severity,
+    code: &'static str,
+    message: impl Display,
+) {
+    if severity == Severity::Warning {
+        WARNINGS.fetch_add(1, Ordering::Relaxed);
+    }
+    if SPEC.get().is_some() {
+        COLLECTED.lock().unwrap().push(Diagnostic {
+            file: file.to_path_buf(),
+            line: position.map(|position| position.line),
+            column: position.map(|position| position.column),
+            severity,
+            code,
+            message: message.to_string(),
+        });
+    }
+}
+
+/// The number of warnings recorded so far.
+pub fn warnings() -> usize {
+    WARNINGS.load(Ordering::Relaxed)
+}
+
+/// Record a warning about `file` and log it in human-readable form.
+pub fn warn(file: &Path, code: &'static str, message: impl Display) {
+    warn_at(file, None, code, message);
+}
+
+/// Record a warning about `file` at `position`, if it is known, and log it
+/// in human-readable form.
+pub fn warn_at(file: &Path, position: Option<Position>, code: &'static str, message: impl Display) {
+    if human() {
+        match position {
+            Some(Position { line, column }) => {
+                log::warn!("{}:{}:{}: warning: {}", file.display(), line, column, message)
+            }
+            None => log::warn!("{}: warning: {}", file.display(), message),
+        }
+    }
+    record(file, position, Severity::Warning, code, message);
+}
+
+/// Record an error about `file` and log it in human-readable form.
+pub fn error(file: &Path, code: &'static str, message: impl Display) {
+    if human() {
+        log::error!("{}: error: {}", file.display(), message);
+    }
+    record(file, None, Severity::Error, code, message);
+}
+
+/// Record an error at `position` in `source`, the contents of `file`, and
+/// print it as an annotated snippet.
+pub fn error_at(
+    file: &Path,
+    source: &str,
+    position: Position,
+    code: &'static str,
+    message: impl Display,
+) {
+    let message = message.to_string();
+    if human() {
+        eprint!("{}", snippet::render(file, source, position, &message));
+    }
+    record(file, Some(position), Severity::Error, code, message);
+}
+
+/// An error that has been printed already, as an annotated snippet, so
+/// that it is not printed again when the run ends. It keeps the causes of
+/// the error it wraps.
+#[derive(Debug)]
+pub struct Reported(pub anyhow::Error);
+
+impl Display for Reported {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        Display::fmt(&self.0, f)
+    }
+}
+
+impl Error for Reported {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.0.source()
+    }
+}
+
+/// Write the collected diagnostics.
+pub fn finish() -> Result<()> {
+    let Some(spec) = SPEC.get() else {
+        return Ok(());
+    };
+    let json = serde_json::to_string_pretty(&*COLLECTED.lock().unwrap())?;
+    match &spec.path {
+        Some(path) => fs::write(path, json + "\n")?,
+        None => eprintln!("{}", json),
+    }
+    Ok(())
+}
@@ -0,0 +1,176 @@
+//! Semantic comparison of two test files.
+//!
+//! Both files are read into the normalized model, so formatting, key order
+//! and comments do not matter. Suites are matched by position, tests
+//! within a suite by their input and modes. Tests of a legacy file are
+//! named with the line they start on, where it can be found.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value;
+
+use louis_migrate_yaml::{
+    normalized::{Test, TestSuite},
+    parse::ParseOptions,
+};
+
+use crate::{parse_file, source_map::SourceMap, validate::validate_file};
+
+#[derive(Args, Debug)]
+pub struct DiffArgs {
+    /// The original file, legacy or normalized
+    old: PathBuf,
+    /// The file to compare it with, legacy or normalized
+    new: PathBuf,
+}
+
+/// Read `path` as a normalized file if it is json or its (last) yaml
+/// document is a sequence, and as a legacy file otherwise.
+pub fn load_file(path: &Path) -> Result<Vec<TestSuite>> {
+    let source = fs::read_to_string(path)?;
+    let is_normalized = path.extension().is_some_and(|ext| ext == "json")
+        || matches!(
+            serde_yaml::Deserializer::from_str(&source)
+                .last()
+                .map(Value::deserialize),
+            Some(Ok(Value::Sequence(..)))
+        );
+    if is_normalized {
+        validate_file(path)
+    } else {
+        let mut test_suites = parse_file(path, &ParseOptions::default())?;
+        SourceMap::new(&source).locate(&mut test_suites);
+        Ok(test_suites)
+    }
+}
+
+/// The serialized fields of `value`, for a field by field comparison.
+fn fields<T: Serialize>(value: &T) -> Result<BTreeMap<String, Value>> {
+    Ok(serde_yaml::from_value(serde_yaml::to_value(value)?)?)
+}
+
+fn show(value: Option<&Value>) -> String {
+    match value {
+        Some(value) => serde_json::to_string(value).unwrap_or_default(),
+        None => "(none)".to_string(),
+    }
+}
+
+/// Describe the fields that differ between `old` and `new`, ignoring the
+/// fields in `ignore`.
+fn changed_fields(
+    old: &BTreeMap<String, Value>,
+    new: &BTreeMap<String, Value>,
+    ignore: &[&str],
+) -> Vec<String> {
+    let keys: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+    keys.into_iter()
+        .filter(|key| !ignore.contains(&key.as_str()))
+        .filter(|key| old.get(*key) != new.get(*key))
+        .map(|key| {
+            format!(
+                "{} changed: {} -> {}",
+                key,
+                show(old.get(key)),
+                show(new.get(key))
+            )
+        })
+        .collect()
+}
+
+/// Tests are matched by their input and modes. Tests with the same key are
+/// matched in order.
+fn test_key(test: &Test) -> String {
+    format!("{:?} {:?}", test.input, test.mode)
+}
+
+/// `test` as named in the differences, by its input and the line it
+/// starts on if known.
+fn test_name(test: &Test) -> String {
+    match test.source {
+        Some(source) => format!("test {:?} (line {})", test.input, source.line),
+        None => format!("test {:?}", test.input),
+    }
+}
+
+fn diff_tests(old: &[Test], new: &[Test], differences: &mut Vec<String>) -> Result<()> {
+    let mut unmatched: BTreeMap<String, Vec<&Test>> = BTreeMap::new();
+    for test in new.iter().rev() {
+        unmatched.entry(test_key(test)).or_default().push(test);
+    }
+    for old_test in old {
+        match unmatched
+            .get_mut(&test_key(old_test))
+            .and_then(|tests| tests.pop())
+        {
+            Some(new_test) => {
+                let ignore = ["comments", "id", "source"];
+                for change in changed_fields(&fields(old_test)?, &fields(new_test)?, &ignore) {
+                    differences.push(format!("{}: {}", test_name(old_test), change));
+                }
+            }
+            None => differences.push(format!("{} removed", test_name(old_test))),
+        }
+    }
+    for test in new {
+        if let Some(tests) = unmatched.get_mut(&test_key(test)) {
+            if tests.pop().is_some() {
+                differences.push(format!("{} added", test_name(test)));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The semantic differences between two lists of test suites.
+pub fn diff(old: &[TestSuite], new: &[TestSuite]) -> Result<Vec<String>> {
+    let mut differences = Vec::new();
+    for (i, (old_suite, new_suite)) in old.iter().zip(new).enumerate() {
+        let mut suite_differences = changed_fields(
+            &fields(old_suite)?,
+            &fields(new_suite)?,
+            &["comments", "source", "tests"],
+        );
+        diff_tests(&old_suite.tests, &new_suite.tests, &mut suite_differences)?;
+        differences.extend(
+            suite_differences
+                .into_iter()
+                .map(|difference| format!("suite {}: {}", i + 1, difference)),
+        );
+    }
+    for (i, suite) in old.iter().enumerate().skip(new.len()) {
+        differences.push(format!(
+            "suite {} removed ({} tests)",
+            i + 1,
+            suite.tests.len()
+        ));
+    }
+    for (i, suite) in new.iter().enumerate().skip(old.len()) {
+        differences.push(format!(
+            "suite {} added ({} tests)",
+            i + 1,
+            suite.tests.len()
+        ));
+    }
+    Ok(differences)
+}
+
+pub fn run(args: &DiffArgs) -> Result<()> {
+    let old = load_file(&args.old)?;
+    let new = load_file(&args.new)?;
+    let differences = diff(&old, &new)?;
+    for difference in &differences {
+        println!("{}", difference);
+    }
+    if !differences.is_empty() {
+        bail!("{} difference(s) found", differences.len());
+    }
+    Ok(())
+}
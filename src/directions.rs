@@ -0,0 +1,288 @@
+//! Splitting `bothDirections` suites into a forward and a backward suite,
+//! and merging such pairs back.
+//!
+//! A harness that only knows single-direction suites can still run the
+//! tests of a `bothDirections` suite once they are written as two suites.
+//! The forward suite keeps the tests as they are. The backward suite takes
+//! the expected braille as input and expects the input back, or the
+//! `expected_backward` of the test if it has one, the way a legacy
+//! `backward` suite is written. Positions, cursors and emphasis classes
+//! describe the forward translation, so they stay with the forward test.
+//! An `xfail` given per direction is split between the two tests.
+//!
+//! Merging is the inverse: a backward suite over the same tables and with
+//! the same translation modes and variables as a forward suite is merged
+//! into it if its tests mirror those of the forward suite one by one. The
+//! backward input of a test has to be its expected braille, the backward
+//! output becomes the `expected_backward` of the test if it is not the
+//! input. A test that does not mirror its counterpart, e.g. because the
+//! braille differs or it has positions of its own, keeps the suites apart.
+
+use std::{fmt, mem};
+
+use louis_migrate_yaml::normalized::{DirectionXfail, Test, TestMode, TestSuite, Xfail};
+
+use crate::diagnostics::Position;
+
+/// The xfail of a single-direction test for `xfail` of a test run in both
+/// directions.
+fn direction_xfail(xfail: DirectionXfail) -> Xfail {
+    match xfail {
+        DirectionXfail::Scalar(xfail) => Xfail::Scalar(xfail),
+        DirectionXfail::Reason(reason) => Xfail::Reason(reason),
+    }
+}
+
+/// Split `test` of a `bothDirections` suite into its forward and its
+/// backward test.
+fn split_test(mut test: Test) -> (Test, Test) {
+    let (forward_xfail, backward_xfail) = match mem::take(&mut test.xfail) {
+        Xfail::Map { forward, backward } => (direction_xfail(forward), direction_xfail(backward)),
+        xfail => (xfail.clone(), xfail),
+    };
+    let backward = Test {
+        // the id of a test depends on its direction
+        id: None,
+        name: test.name.clone(),
+        comments: test.comments.clone(),
+        tags: test.tags.clone(),
+        input: test.expected.clone(),
+        expected: test
+            .expected_backward
+            .take()
+            .unwrap_or_else(|| test.input.clone()),
+        xfail: backward_xfail,
+        extra: test.extra.clone(),
+        mode: test.mode.clone(),
+        max_output_length: test.max_output_length,
+        source: test.source,
+        ..Test::default()
+    };
+    test.xfail = forward_xfail;
+    (test, backward)
+}
+
+/// The name of the suite for `direction` split from a suite named `name`.
+fn direction_name(name: &Option<String>, direction: &str) -> Option<String> {
+    name.as_ref().map(|name| format!("{}_{}", name, direction))
+}
+
+/// Replace every `bothDirections` suite of `test_suites` by a forward
+/// suite followed by a backward suite. Returns the number of suites split.
+pub fn explode(test_suites: &mut Vec<TestSuite>) -> usize {
+    let mut split = 0;
+    let mut exploded = Vec::with_capacity(test_suites.len());
+    for mut suite in mem::take(test_suites) {
+        if suite.mode != TestMode::BothDirections {
+            exploded.push(suite);
+            continue;
+        }
+        let (forward_tests, backward_tests) = mem::take(&mut suite.tests)
+            .into_iter()
+            .map(split_test)
+            .unzip();
+        let backward = TestSuite {
+            name: direction_name(&suite.name, "backward"),
+            comments: Vec::new(),
+            tags: suite.tags.clone(),
+            display_table: suite.display_table.clone(),
+            table: suite.table.clone(),
+            mode: TestMode::Backward,
+            translation_modes: suite.translation_modes.clone(),
+            variables: suite.variables.clone(),
+            source: suite.source,
+            tests: backward_tests,
+        };
+        suite.name = direction_name(&suite.name, "forward");
+        suite.mode = TestMode::Forward;
+        suite.tests = forward_tests;
+        exploded.push(suite);
+        exploded.push(backward);
+        split += 1;
+    }
+    *test_suites = exploded;
+    split
+}
+
+/// A forward and a backward suite that have the same tables but could not
+/// be merged.
+#[derive(Debug)]
+pub struct Unmerged {
+    /// 1-based index of the forward suite in the file
+    pub forward: usize,
+    /// 1-based index of the backward suite in the file
+    pub backward: usize,
+    pub reason: String,
+    /// Where the test that kept the suites apart starts in the legacy file,
+    /// if it is known
+    pub position: Option<Position>,
+}
+
+impl fmt::Display for Unmerged {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "suites {} and {} not merged: {}",
+            self.forward, self.backward, self.reason
+        )
+    }
+}
+
+/// Whether `forward` and `backward` are run with the same tables, modes
+/// and variables, so that they could be one `bothDirections` suite.
+fn same_setup(forward: &TestSuite, backward: &TestSuite) -> bool {
+    let table = |suite: &TestSuite| serde_json::to_string(&suite.table).ok();
+    forward.mode == TestMode::Forward
+        && backward.mode == TestMode::Backward
+        && table(forward) == table(backward)
+        && forward.display_table == backward.display_table
+        && forward.translation_modes == backward.translation_modes
+        && forward.variables == backward.variables
+}
+
+/// Why `backward` is not the mirror image of `forward`, if it is not.
+fn mirror_problem(forward: &Test, backward: &Test) -> Option<String> {
+    if backward.input != forward.expected {
+        Some(format!(
+            "the backward input {:?} is not the expected braille {:?}",
+            backward.input, forward.expected
+        ))
+    } else if backward.mode != forward.mode {
+        Some("the translation modes differ".to_string())
+    } else if backward.max_output_length != forward.max_output_length {
+        Some("the maximum output lengths differ".to_string())
+    } else if backward.extra != forward.extra {
+        Some("the options kept verbatim differ".to_string())
+    } else if backward.expected_backward.is_some()
+        || !backward.input_pos.is_empty()
+        || !backward.output_pos.is_empty()
+        || backward.cursor_pos.is_some()
+        || backward.brl_cursor_pos.is_some()
+        || !backward.input_class.is_empty()
+        || !backward.output_class.is_empty()
+    {
+        Some("the backward test has positions or emphasis classes of its own".to_string())
+    } else if backward.name.is_some() && forward.name.is_some() && backward.name != forward.name {
+        Some("the names differ".to_string())
+    } else {
+        None
+    }
+}
+
+/// Why the tests of `backward` do not mirror those of `forward`, and where
+/// the first offending test starts, if they do not.
+fn suite_mirror_problem(
+    forward: &TestSuite,
+    backward: &TestSuite,
+) -> Option<(String, Option<Position>)> {
+    if forward.tests.len() != backward.tests.len() {
+        return Some((
+            format!(
+                "the forward suite has {} tests, the backward suite {}",
+                forward.tests.len(),
+                backward.tests.len()
+            ),
+            backward.source.map(Position::from),
+        ));
+    }
+    forward
+        .tests
+        .iter()
+        .zip(&backward.tests)
+        .enumerate()
+        .find_map(|(i, (forward, backward))| {
+            let problem = mirror_problem(forward, backward)?;
+            Some((
+                format!("test {}: {}", i + 1, problem),
+                backward.source.map(Position::from),
+            ))
+        })
+}
+
+/// The xfail of a single-direction test, forward if `is_forward` is set
+/// and backward otherwise, as part of the xfail of a test run in both
+/// directions.
+fn xfail_part(xfail: Xfail, is_forward: bool) -> DirectionXfail {
+    match xfail {
+        Xfail::Scalar(xfail) => DirectionXfail::Scalar(xfail),
+        Xfail::Reason(reason) => DirectionXfail::Reason(reason),
+        Xfail::Map { forward, .. } if is_forward => forward,
+        Xfail::Map { backward, .. } => backward,
+    }
+}
+
+/// Merge `backward`, which mirrors `forward`, into it.
+fn merge_test(forward: &mut Test, backward: Test) {
+    if backward.expected != forward.input {
+        forward.expected_backward = Some(backward.expected);
+    }
+    forward.xfail = match (mem::take(&mut forward.xfail), backward.xfail) {
+        (forward, backward) if forward.is_false() && backward.is_false() => Xfail::default(),
+        (forward, backward) => Xfail::Map {
+            forward: xfail_part(forward, true),
+            backward: xfail_part(backward, false),
+        },
+    };
+    if forward.name.is_none() {
+        forward.name = backward.name;
+    }
+    for comment in backward.comments {
+        if !forward.comments.contains(&comment) {
+            forward.comments.push(comment);
+        }
+    }
+    forward.tags.extend(backward.tags);
+}
+
+/// Merge every backward suite of `test_suites` that mirrors a forward suite
+/// into it, which becomes a `bothDirections` suite. Returns the number of
+/// merged pairs and the pairs with the same setup that could not be merged.
+pub fn merge(test_suites: &mut Vec<TestSuite>) -> (usize, Vec<Unmerged>) {
+    let mut unmerged = Vec::new();
+    // the forward suite each backward suite is merged into
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    for (j, backward) in test_suites.iter().enumerate() {
+        let candidates: Vec<usize> = (0..test_suites.len())
+            .filter(|i| !pairs.iter().any(|(paired, _)| paired == i))
+            .filter(|&i| same_setup(&test_suites[i], backward))
+            .collect();
+        let mut problems = Vec::new();
+        for i in candidates {
+            match suite_mirror_problem(&test_suites[i], backward) {
+                None => {
+                    pairs.push((i, j));
+                    problems.clear();
+                    break;
+                }
+                Some(problem) => problems.push((i, problem)),
+            }
+        }
+        // only the problem with the first candidate is reported
+        if let Some((i, (reason, position))) = problems.into_iter().next() {
+            unmerged.push(Unmerged {
+                forward: i + 1,
+                backward: j + 1,
+                reason,
+                position,
+            });
+        }
+    }
+
+    let mut suites: Vec<Option<TestSuite>> = mem::take(test_suites).into_iter().map(Some).collect();
+    for &(i, j) in &pairs {
+        let backward = suites[j].take().expect("a backward suite is merged once");
+        let forward = suites[i].as_mut().expect("a forward suite is merged once");
+        for (test, mirrored) in forward.tests.iter_mut().zip(backward.tests) {
+            merge_test(test, mirrored);
+        }
+        for comment in backward.comments {
+            if !forward.comments.contains(&comment) {
+                forward.comments.push(comment);
+            }
+        }
+        forward.tags.extend(backward.tags);
+        forward.mode = TestMode::BothDirections;
+    }
+    *test_suites = suites.into_iter().flatten().collect();
+    (pairs.len(), unmerged)
+}
@@ -0,0 +1,64 @@
+//! Preview of what a conversion would write.
+//!
+//! With `--dry-run` the whole conversion runs but nothing is written.
+//! Instead every output file is compared with what is on disk, and it is
+//! reported whether the file would be created, overwritten or left
+//! unchanged, with the number of lines that would be inserted and deleted.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::Result;
+
+/// The lines of a conversion that are compared, i.e. without the time of
+/// conversion in the provenance metadata, which differs on every run.
+fn compared_lines(output: &str) -> Vec<&str> {
+    output
+        .lines()
+        .filter(|line| !line.starts_with("  timestamp: "))
+        .collect()
+}
+
+/// The number of lines inserted into and deleted from `old` to get `new`.
+/// Lines that merely moved are counted as unchanged.
+fn line_changes(old: &[&str], new: &[&str]) -> (usize, usize) {
+    let mut counts: HashMap<&str, isize> = HashMap::new();
+    for line in old {
+        *counts.entry(line).or_default() -= 1;
+    }
+    for line in new {
+        *counts.entry(line).or_default() += 1;
+    }
+    let insertions = counts.values().filter(|count| **count > 0).sum::<isize>();
+    let deletions = -counts.values().filter(|count| **count < 0).sum::<isize>();
+    (insertions as usize, deletions as usize)
+}
+
+/// Print what writing `output` to `path`, or to stdout if `None`, would
+/// change.
+pub fn preview(path: Option<&Path>, output: &str) -> Result<()> {
+    let new = compared_lines(output);
+    let Some(path) = path else {
+        println!("would write {} line(s) to stdout", new.len());
+        return Ok(());
+    };
+    let (status, (insertions, deletions)) = match fs::read_to_string(path) {
+        Ok(existing) => {
+            let old = compared_lines(&existing);
+            if old == new {
+                ("unchanged", (0, 0))
+            } else {
+                ("would overwrite", line_changes(&old, &new))
+            }
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => ("would create", (new.len(), 0)),
+        Err(e) => return Err(e.into()),
+    };
+    println!("{} {}", status, path.display());
+    println!(
+        " {} file(s) would change, {} insertion(s)(+), {} deletion(s)(-)",
+        usize::from(status != "unchanged"),
+        insertions,
+        deletions
+    );
+    Ok(())
+}
@@ -0,0 +1,390 @@
+//! A small yaml emitter with control over the output style.
+//!
+//! serde_yaml always emits block style and decides on its own when to quote
+//! strings, by the rules of yaml 1.2: it writes `no`, `on` or `1_000`
+//! unquoted, which yaml 1.1 parsers read back as a boolean or a number.
+//! This emitter renders the serialized model (via [`serde_yaml::Value`])
+//! itself, so that tests can be written as compact one-line flow mappings,
+//! the quoting policy can be chosen and strings stay strings under either
+//! version.
+
+use std::sync::LazyLock;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use louis_migrate_yaml::{
+    format::{Formatted, Formatter},
+    normalized::{self, Meta, TestSuite},
+};
+use regex::Regex;
+use serde::Serialize;
+use serde_yaml::{Mapping, Value};
+
+use crate::unicode;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum TestStyle {
+    /// One line per field of a test
+    #[default]
+    Block,
+    /// One flow mapping per line for all simple tests
+    Flow,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Quote {
+    /// Quote all string values
+    Always,
+    /// Quote strings only if they would not be read back as the same string
+    #[default]
+    Minimal,
+}
+
+/// Words that a yaml 1.1 or 1.2 parser would read as something other than
+/// a string, in lowercase. `<<` and `=` are the merge and value keys of
+/// yaml 1.1.
+const RESERVED: &[&str] = &[
+    "~", "null", "true", "false", "yes", "no", "on", "off", "y", "n", ".inf", "-.inf", "+.inf",
+    ".nan", "<<", "=",
+];
+
+/// The integers, floats and timestamps of yaml 1.1 and 1.2, a little more
+/// generously than either, e.g. `0.1.2`, which yaml 1.1 reads as a float.
+/// Some of them, like `1_000` or `1:20`, are plain strings under yaml 1.2
+/// but numbers under yaml 1.1.
+static NUMBER_OR_TIMESTAMP: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(concat!(
+        r"^(?:",
+        // integers, binary, octal, hexadecimal, decimal and sexagesimal
+        r"[-+]?(?:0b[01_]+|0o?[0-7_]+|0x[0-9a-fA-F_]+|[0-9][0-9_]*(?::[0-5]?[0-9])*)",
+        // floats with a decimal point or an exponent
+        r"|[-+]?(?:[0-9][0-9_]*(?::[0-5]?[0-9])*)?\.[0-9._]*(?:[eE][-+]?[0-9]+)?",
+        r"|[-+]?[0-9][0-9_]*[eE][-+]?[0-9]+",
+        // dates and timestamps
+        r"|[0-9]{4}-[0-9]{1,2}-[0-9]{1,2}",
+        r"(?:(?:[Tt]|[ \t]+)[0-9]{1,2}:[0-9]{2}:[0-9]{2}(?:\.[0-9]*)?",
+        r"(?:[ \t]*(?:Z|[-+][0-9]{1,2}(?::[0-9]{2})?))?)?",
+        r")$"
+    ))
+    .expect("the pattern is valid")
+});
+
+/// Return true if `s` cannot be written as a plain scalar. In flow context
+/// the flow indicators are not allowed either.
+fn needs_quotes(s: &str, flow: bool) -> bool {
+    let Some(first) = s.chars().next() else {
+        return true;
+    };
+    RESERVED.contains(&s.to_lowercase().as_str())
+        || looks_like_number(s)
+        || "-?:,[]{}#&*!|>'\"%@`".contains(first)
+        || first.is_whitespace()
+        || s.ends_with(char::is_whitespace)
+        || s.ends_with(':')
+        || s.contains(": ")
+        || s.contains(" #")
+        || s.chars().any(char::is_control)
+        || (flow && s.contains([',', '[', ']', '{', '}']))
+}
+
+/// Whether a yaml 1.1 or 1.2 parser might resolve `s` to an integer, a
+/// float or a timestamp.
+fn looks_like_number(s: &str) -> bool {
+    s.contains(|c: char| c.is_ascii_digit()) && NUMBER_OR_TIMESTAMP.is_match(s)
+}
+
+fn single_quoted(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// `s` in double quotes, with the characters for which `escape` is set
+/// written as escapes.
+fn double_quoted(s: &str, escape: impl Fn(char) -> bool) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if escape(c) => quoted.push_str(&format!("\\u{:04X}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A simple value can be written in flow style on a single line.
+fn is_simple(value: &Value) -> bool {
+    match value {
+        Value::Mapping(mapping) => mapping.values().all(|value| match value {
+            Value::Sequence(seq) => seq.iter().all(is_scalar),
+            Value::Mapping(mapping) => mapping.values().all(is_scalar),
+            value => is_scalar(value) && !is_multiline(value),
+        }),
+        value => is_scalar(value),
+    }
+}
+
+fn is_scalar(value: &Value) -> bool {
+    !matches!(value, Value::Sequence(..) | Value::Mapping(..))
+}
+
+fn is_multiline(value: &Value) -> bool {
+    matches!(value, Value::String(s) if s.contains('\n'))
+}
+
+fn is_empty_collection(value: &Value) -> bool {
+    match value {
+        Value::Sequence(seq) => seq.is_empty(),
+        Value::Mapping(mapping) => mapping.is_empty(),
+        _ => false,
+    }
+}
+
+struct Emitter {
+    test_style: TestStyle,
+    quote: Quote,
+    /// Write invisible and bidi control characters as escapes
+    escape_special: bool,
+    out: String,
+}
+
+impl Emitter {
+    /// Whether `c` is written as an escape.
+    fn escapes(&self, c: char) -> bool {
+        c.is_control() || (self.escape_special && unicode::is_special(c))
+    }
+
+    fn quoted(&self, s: &str) -> String {
+        if s.contains(|c| self.escapes(c)) {
+            double_quoted(s, |c| self.escapes(c))
+        } else {
+            single_quoted(s)
+        }
+    }
+
+    fn key(&self, key: &Value) -> String {
+        match key {
+            Value::String(s) if !needs_quotes(s, false) && !s.contains(|c| self.escapes(c)) => {
+                s.clone()
+            }
+            Value::String(s) => self.quoted(s),
+            other => self.scalar(other, false),
+        }
+    }
+
+    fn scalar(&self, value: &Value, flow: bool) -> String {
+        match value {
+            Value::Null => "null".to_string(),
+            Value::Bool(b) => b.to_string(),
+            Value::Number(n) => n.to_string(),
+            Value::String(s)
+                if self.quote == Quote::Minimal
+                    && !needs_quotes(s, flow)
+                    && !s.contains(|c| self.escapes(c)) =>
+            {
+                s.clone()
+            }
+            Value::String(s) => self.quoted(s),
+            Value::Tagged(tagged) => self.scalar(&tagged.value, flow),
+            Value::Sequence(seq) if seq.is_empty() => "[]".to_string(),
+            Value::Mapping(mapping) if mapping.is_empty() => "{}".to_string(),
+            other => self.flow(other),
+        }
+    }
+
+    fn flow(&self, value: &Value) -> String {
+        match value {
+            Value::Sequence(seq) => {
+                let items: Vec<String> = seq.iter().map(|item| self.flow(item)).collect();
+                format!("[{}]", items.join(", "))
+            }
+            Value::Mapping(mapping) => {
+                let entries: Vec<String> = mapping
+                    .iter()
+                    .map(|(k, v)| format!("{}: {}", self.key(k), self.flow(v)))
+                    .collect();
+                format!("{{{}}}", entries.join(", "))
+            }
+            scalar => self.scalar(scalar, true),
+        }
+    }
+
+    fn indent(&mut self, indent: usize) {
+        for _ in 0..indent {
+            self.out.push(' ');
+        }
+    }
+
+    fn literal(&mut self, s: &str, indent: usize) {
+        let chomping = if s.ends_with("\n\n") {
+            "+"
+        } else if s.ends_with('\n') {
+            ""
+        } else {
+            "-"
+        };
+        self.out.push_str(" |");
+        self.out.push_str(chomping);
+        self.out.push('\n');
+        for line in s.lines() {
+            if !line.is_empty() {
+                self.indent(indent);
+            }
+            self.out.push_str(line);
+            self.out.push('\n');
+        }
+    }
+
+    /// Write `value` after a `key:` or `- ` prefix. Nested block collections
+    /// start on a new line at `indent`.
+    fn value(&mut self, value: &Value, indent: usize, flow_items: bool) {
+        match value {
+            Value::Sequence(seq) if !seq.is_empty() => {
+                self.out.push('\n');
+                self.sequence(seq, indent, false, flow_items);
+            }
+            Value::Mapping(mapping) if !mapping.is_empty() => {
+                self.out.push('\n');
+                self.mapping(mapping, indent + 2, false);
+            }
+            // a literal block cannot start with a space or contain escapes
+            Value::String(s)
+                if s.contains('\n')
+                    && !s.starts_with(' ')
+                    && !s.chars().any(|c| self.escapes(c) && c != '\n') =>
+            {
+                self.literal(s, indent + 2);
+            }
+            scalar => {
+                self.out.push(' ');
+                self.out.push_str(&self.scalar(scalar, false));
+                self.out.push('\n');
+            }
+        }
+    }
+
+    /// Write a block mapping. If `inline` is set the first entry continues
+    /// the current line, as needed for a mapping inside a sequence.
+    fn mapping(&mut self, mapping: &Mapping, indent: usize, inline: bool) {
+        for (i, (key, value)) in mapping.iter().enumerate() {
+            if !(inline && i == 0) {
+                self.indent(indent);
+            }
+            self.out.push_str(&self.key(key));
+            self.out.push(':');
+            let flow_items = self.test_style == TestStyle::Flow && key.as_str() == Some("tests");
+            self.value(value, indent, flow_items);
+        }
+    }
+
+    /// Write a block sequence. Items for which `flow_items` is set are written
+    /// as flow collections if they are simple enough.
+    fn sequence(&mut self, seq: &[Value], indent: usize, inline: bool, flow_items: bool) {
+        for (i, item) in seq.iter().enumerate() {
+            if !(inline && i == 0) {
+                self.indent(indent);
+            }
+            self.out.push('-');
+            match item {
+                item if flow_items && is_simple(item) => {
+                    self.out.push(' ');
+                    self.out.push_str(&self.flow(item));
+                    self.out.push('\n');
+                }
+                Value::Mapping(mapping) if !is_empty_collection(item) => {
+                    self.out.push(' ');
+                    self.mapping(mapping, indent + 2, true);
+                }
+                Value::Sequence(nested) if !is_empty_collection(item) => {
+                    self.out.push(' ');
+                    self.sequence(nested, indent + 2, true, false);
+                }
+                item => self.value(item, indent, false),
+            }
+        }
+    }
+}
+
+/// Serialize `value` to yaml using the given test style and quoting policy.
+/// If `escape_special` is set, strings with invisible or bidi control
+/// characters are double-quoted with these characters as escapes.
+pub fn to_string<T: Serialize>(
+    value: &T,
+    test_style: TestStyle,
+    quote: Quote,
+    escape_special: bool,
+) -> Result<String> {
+    let value = serde_yaml::to_value(value)?;
+    let mut emitter = Emitter {
+        test_style,
+        quote,
+        escape_special,
+        out: String::new(),
+    };
+    match &value {
+        Value::Sequence(seq) if !seq.is_empty() => emitter.sequence(seq, 0, false, false),
+        Value::Mapping(mapping) if !mapping.is_empty() => emitter.mapping(mapping, 0, false),
+        other => {
+            emitter.out.push_str(&emitter.flow(other));
+            emitter.out.push('\n');
+        }
+    }
+    Ok(emitter.out)
+}
+
+/// The normalized yaml format
+pub struct Yaml {
+    pub test_style: TestStyle,
+    pub quote: Quote,
+    /// Write invisible and bidi control characters as escapes
+    pub escape_special: bool,
+    /// Write the tables used by most suites once in the meta document
+    pub factor_defaults: bool,
+}
+
+impl Yaml {
+    /// Serialize `value` in the style of this format.
+    pub fn to_string<T: Serialize>(&self, value: &T) -> Result<String> {
+        to_string(value, self.test_style, self.quote, self.escape_special)
+    }
+}
+
+impl Formatter for Yaml {
+    fn name(&self) -> &'static str {
+        "yaml"
+    }
+
+    fn extension(&self) -> Option<&'static str> {
+        None
+    }
+
+    /// The suites, preceded by `meta` unless it is empty.
+    fn format(&self, test_suites: &[TestSuite], meta: &Meta) -> Result<Formatted> {
+        let mut meta = meta.clone();
+        let suites = if self.factor_defaults {
+            let (defaults, suites) = normalized::factor_defaults(test_suites)?;
+            meta.suite_defaults = Some(defaults);
+            self.to_string(&suites)?
+        } else {
+            self.to_string(&test_suites)?
+        };
+        // the suites make up most of the output, they are not copied
+        // unless there is a meta document to go before them
+        let output = if meta.meta.is_some() || meta.suite_defaults.is_some() {
+            let mut output = self.to_string(&meta)?;
+            output.push_str("---\n");
+            output.push_str(&suites);
+            output
+        } else {
+            suites
+        };
+        Ok(Formatted {
+            output,
+            warnings: Vec::new(),
+        })
+    }
+}
@@ -0,0 +1,161 @@
+//! Selection and order of the tests to convert.
+
+use std::collections::BTreeSet;
+
+use clap::{Args, ValueEnum};
+use regex::Regex;
+
+use louis_migrate_yaml::normalized::{Test, TestMode, TestSuite};
+
+#[derive(Args, Debug, Default, Clone)]
+pub struct Filter {
+    /// Only convert tests that are expected to fail
+    #[arg(long, conflicts_with = "skip_xfail")]
+    only_xfail: bool,
+    /// Only convert tests that are not expected to fail
+    #[arg(long)]
+    skip_xfail: bool,
+    /// Only convert suites with this test mode. Suites testing both
+    /// directions match forward and backward.
+    #[arg(long, value_enum)]
+    mode: Option<TestMode>,
+    /// Only convert tests whose input or expected output matches PATTERN
+    #[arg(long, value_name = "PATTERN")]
+    grep: Option<Regex>,
+    /// Only convert tests with the tag TAG, given by themselves or their
+    /// suite (repeatable, a test needs one of the tags)
+    #[arg(long, value_name = "TAG")]
+    only_tag: Vec<String>,
+    /// Leave out tests with the tag TAG, given by themselves or their suite
+    /// (repeatable)
+    #[arg(long, value_name = "TAG")]
+    skip_tag: Vec<String>,
+    /// Keep at most the first N tests of each suite
+    #[arg(long, value_name = "N")]
+    max_tests: Option<usize>,
+    /// Keep a random sample of N tests of each suite, in their original order
+    #[arg(long, value_name = "N", conflicts_with = "max_tests")]
+    sample: Option<usize>,
+    /// Seed for --sample, the same seed selects the same tests
+    #[arg(long, value_name = "S", requires = "sample", default_value_t = 0)]
+    seed: u64,
+}
+
+/// A small generator (splitmix64) whose output is fixed for a given seed,
+/// unlike the generators of the rand crate, which may change between
+/// versions. Sampling stays reproducible across builds that way.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
+
+/// Keep `n` randomly selected tests, preserving their order (selection
+/// sampling, Knuth's algorithm S).
+fn sample(tests: &mut Vec<Test>, n: usize, rng: &mut SplitMix64) {
+    let mut remaining = tests.len();
+    let mut needed = n.min(remaining);
+    tests.retain(|_| {
+        let keep = rng.next() % (remaining as u64) < needed as u64;
+        remaining -= 1;
+        if keep {
+            needed -= 1;
+        }
+        keep
+    });
+}
+
+impl Filter {
+    fn is_active(&self) -> bool {
+        self.only_xfail
+            || self.skip_xfail
+            || self.mode.is_some()
+            || self.grep.is_some()
+            || !self.only_tag.is_empty()
+            || !self.skip_tag.is_empty()
+            || self.max_tests.is_some()
+            || self.sample.is_some()
+    }
+
+    fn matches_mode(&self, mode: &TestMode) -> bool {
+        match &self.mode {
+            None => true,
+            Some(TestMode::Forward | TestMode::Backward) if *mode == TestMode::BothDirections => {
+                true
+            }
+            Some(wanted) => wanted == mode,
+        }
+    }
+
+    fn matches_test(&self, suite_tags: &BTreeSet<String>, test: &Test) -> bool {
+        let xfail = !test.xfail.is_false();
+        let has_tag = |tag: &String| suite_tags.contains(tag) || test.tags.contains(tag);
+        (!self.only_xfail || xfail)
+            && (!self.skip_xfail || !xfail)
+            && self
+                .grep
+                .as_ref()
+                .is_none_or(|re| re.is_match(&test.input) || re.is_match(&test.expected))
+            && (self.only_tag.is_empty() || self.only_tag.iter().any(has_tag))
+            && !self.skip_tag.iter().any(has_tag)
+    }
+
+    /// Remove the tests that do not match or exceed the limits, and the
+    /// suites left without any tests by that. Returns the number of removed
+    /// tests.
+    pub fn apply(&self, test_suites: &mut Vec<TestSuite>) -> usize {
+        if !self.is_active() {
+            return 0;
+        }
+        let mut rng = SplitMix64(self.seed);
+        let mut removed = 0;
+        test_suites.retain_mut(|suite| {
+            let before = suite.tests.len();
+            if self.matches_mode(&suite.mode) {
+                let suite_tags = &suite.tags;
+                suite.tests.retain(|test| self.matches_test(suite_tags, test));
+            } else {
+                suite.tests.clear();
+            }
+            if let Some(max_tests) = self.max_tests {
+                suite.tests.truncate(max_tests);
+            }
+            if let Some(n) = self.sample {
+                sample(&mut suite.tests, n, &mut rng);
+            }
+            removed += before - suite.tests.len();
+            before == 0 || !suite.tests.is_empty()
+        });
+        removed
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// Order tests by input
+    Input,
+    /// Order tests by expected output
+    Expected,
+    /// Keep the order of the source file
+    #[default]
+    None,
+}
+
+/// Sort the tests of each suite by `key`. Strings are compared by code
+/// point, independent of the locale, and tests with equal keys keep their
+/// relative order.
+pub fn sort(test_suites: &mut [TestSuite], key: SortKey) {
+    for suite in test_suites.iter_mut() {
+        match key {
+            SortKey::Input => suite.tests.sort_by(|a, b| a.input.cmp(&b.input)),
+            SortKey::Expected => suite.tests.sort_by(|a, b| a.expected.cmp(&b.expected)),
+            SortKey::None => {}
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Output formats for the normalized model.
+//!
+//! Every format the converter can write implements [`Formatter`] and is
+//! registered under its name in a [`Registry`], from which `--format`
+//! picks it. Tools building on this crate can register formats of their
+//! own in the same way.
+
+use anyhow::Result;
+
+use crate::normalized::{Meta, TestSuite};
+
+/// A converted file in some format
+#[derive(Debug, Default)]
+pub struct Formatted {
+    pub output: String,
+    /// Values of the model the format cannot represent and left out
+    pub warnings: Vec<String>,
+}
+
+pub trait Formatter {
+    /// The name the format is selected by
+    fn name(&self) -> &'static str;
+
+    /// The extension of files in this format, `None` to keep the name of
+    /// the converted file
+    fn extension(&self) -> Option<&'static str>;
+
+    /// Write `test_suites`, converted from the file described by `meta`.
+    fn format(&self, test_suites: &[TestSuite], meta: &Meta) -> Result<Formatted>;
+}
+
+/// The known formats by name
+#[derive(Default)]
+pub struct Registry {
+    formatters: Vec<Box<dyn Formatter>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `formatter`, replacing a format of the same name.
+    pub fn register(&mut self, formatter: impl Formatter + 'static) {
+        self.formatters
+            .retain(|registered| registered.name() != formatter.name());
+        self.formatters.push(Box::new(formatter));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Formatter> {
+        self.formatters
+            .iter()
+            .find(|formatter| formatter.name() == name)
+            .map(|formatter| formatter.as_ref())
+    }
+
+    /// The names of the registered formats, in the order they were
+    /// registered.
+    pub fn names(&self) -> Vec<&'static str> {
+        self.formatters
+            .iter()
+            .map(|formatter| formatter.name())
+            .collect()
+    }
+}
@@ -0,0 +1,254 @@
+//! Import of the harness files that preceded the yaml tests.
+//!
+//! Before the yaml format, liblouis kept tests in `*_harness.txt` files
+//! read by a Python script. They are JSON as far as Python dicts are JSON:
+//! they may contain `#` comments, `u"..."` and single-quoted string
+//! literals, `True`, `False` and `None`, and trailing commas. A file holds
+//! the tables, the tests and a `testmode` flag:
+//!
+//! ```text
+//! # comment
+//! {
+//!     "tables": ["en-us-g2.ctb"],
+//!     "flags": {"testmode": "translate"},
+//!     "tests": [
+//!         {"txt": u"foo", "brl": u"⠋⠕⠕", "mode": ["noContractions"]},
+//!     ],
+//! }
+//! ```
+//!
+//! Test keys without a counterpart in the normalized model are kept as
+//! `extra` options, with a warning.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Args;
+use louis_migrate_yaml::normalized::{Table, Test, TestMode, TestSuite, Xfail};
+use serde_json::{Map, Value};
+
+use crate::{
+    diagnostics,
+    emit::{self, Quote, TestStyle},
+};
+
+#[derive(Args, Debug)]
+pub struct ImportHarnessArgs {
+    /// The harness file to convert
+    file: PathBuf,
+    /// Write the normalized yaml to FILE instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+/// Copy `source` to a new string, passing every character outside of
+/// string literals to `outside` together with the characters after it.
+/// `outside` writes what it wants to keep. String literals are copied as
+/// JSON strings, also those in single quotes.
+fn rewrite(
+    source: &str,
+    mut outside: impl FnMut(char, &mut std::iter::Peekable<std::str::Chars>, &mut String),
+) -> String {
+    let mut rewritten = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    let mut quote = None;
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                rewritten.push('"');
+            }
+            (None, c) => outside(c, &mut chars, &mut rewritten),
+            (Some(_), '\\') => match chars.next() {
+                // not an escape in JSON
+                Some('\'') => rewritten.push('\''),
+                Some(c) => {
+                    rewritten.push('\\');
+                    rewritten.push(c);
+                }
+                None => (),
+            },
+            (Some(quote_char), c) if c == quote_char => {
+                quote = None;
+                rewritten.push('"');
+            }
+            (Some(_), '"') => rewritten.push_str("\\\""),
+            (Some(_), c) => rewritten.push(c),
+        }
+    }
+    rewritten
+}
+
+/// `source` as plain JSON: without comments, `u` string prefixes and
+/// trailing commas, and with the Python constants spelled as in JSON.
+fn to_json(source: &str) -> String {
+    let without_comments = rewrite(source, |c, rest, out| match c {
+        'u' | 'U' if matches!(rest.peek(), Some('"' | '\'')) => (),
+        '#' => while rest.next_if(|&c| c != '\n').is_some() {},
+        c if c.is_ascii_alphabetic() => {
+            let mut word = String::from(c);
+            while let Some(c) = rest.next_if(char::is_ascii_alphanumeric) {
+                word.push(c);
+            }
+            out.push_str(match word.as_str() {
+                "True" => "true",
+                "False" => "false",
+                "None" => "null",
+                word => word,
+            });
+        }
+        c => out.push(c),
+    });
+    rewrite(&without_comments, |c, rest, out| {
+        let next = rest.clone().find(|c| !c.is_whitespace());
+        if c != ',' || !matches!(next, Some(']' | '}')) {
+            out.push(c);
+        }
+    })
+}
+
+fn string(value: &Value, key: &str) -> Result<String> {
+    value
+        .as_str()
+        .map(String::from)
+        .ok_or_else(|| anyhow!("{} is not a string: {}", key, value))
+}
+
+fn positions(value: &Value, key: &str) -> Result<Vec<u16>> {
+    serde_json::from_value(value.clone()).with_context(|| format!("invalid {}", key))
+}
+
+fn test_mode(flags: Option<&Value>) -> Result<TestMode> {
+    let testmode = flags
+        .and_then(|flags| flags.get("testmode"))
+        .and_then(Value::as_str)
+        .unwrap_or("translate");
+    match testmode {
+        "translate" => Ok(TestMode::Forward),
+        "backtranslate" => Ok(TestMode::Backward),
+        "hyphenate" => Ok(TestMode::Hyphenate),
+        other => bail!("unknown testmode {:?}", other),
+    }
+}
+
+/// Convert one test of the harness file. Keys that have no place in the
+/// model are returned, they are kept as extra options.
+fn parse_test(fields: &Map<String, Value>) -> Result<(Test, Vec<String>)> {
+    let mut test = Test::default();
+    let mut unknown = Vec::new();
+    for (key, value) in fields {
+        match key.as_str() {
+            "txt" => test.input = string(value, key)?,
+            "brl" => test.expected = string(value, key)?,
+            "comment" => {
+                test.comments = match value {
+                    Value::Array(lines) => lines
+                        .iter()
+                        .map(|line| string(line, key))
+                        .collect::<Result<_>>()?,
+                    line => vec![string(line, key)?],
+                }
+            }
+            "mode" => {
+                let modes = match value {
+                    Value::Array(..) => value.clone(),
+                    mode => Value::Array(vec![mode.clone()]),
+                };
+                test.mode = serde_json::from_value(modes).context("invalid mode")?;
+            }
+            "xfail" => {
+                test.xfail = match value {
+                    Value::Bool(xfail) => Xfail::Scalar(*xfail),
+                    reason => Xfail::Reason(string(reason, key)?),
+                }
+            }
+            "cursorPos" => {
+                test.cursor_pos =
+                    Some(serde_json::from_value(value.clone()).context("invalid cursorPos")?)
+            }
+            "brlCursorPos" => {
+                test.brl_cursor_pos =
+                    Some(serde_json::from_value(value.clone()).context("invalid brlCursorPos")?)
+            }
+            "inputPos" => test.input_pos = positions(value, key)?,
+            "outputPos" => test.output_pos = positions(value, key)?,
+            _ => {
+                let value = serde_yaml::to_value(value)?;
+                test.extra.insert(key.clone(), value);
+                unknown.push(key.clone());
+            }
+        }
+    }
+    if !fields.contains_key("txt") {
+        bail!("test without txt");
+    }
+    Ok((test, unknown))
+}
+
+/// Convert the harness file at `path` into a single suite.
+pub fn from_path(path: &Path) -> Result<TestSuite> {
+    let source = fs::read_to_string(path)?;
+    let harness: Map<String, Value> =
+        serde_json::from_str(&to_json(&source)).context("not a dict of JSON values")?;
+    let mut tables: Vec<PathBuf> = match harness.get("tables") {
+        Some(tables) => serde_json::from_value(tables.clone()).context("invalid tables")?,
+        None => bail!("no tables given"),
+    };
+    let table = match tables.len() {
+        1 => Table::Single(tables.remove(0)),
+        _ => Table::List(tables),
+    };
+    let mut tests = Vec::new();
+    for (i, test) in harness
+        .get("tests")
+        .and_then(Value::as_array)
+        .ok_or_else(|| anyhow!("no tests given"))?
+        .iter()
+        .enumerate()
+    {
+        let fields = test
+            .as_object()
+            .ok_or_else(|| anyhow!("test {} is not an object", i + 1))?;
+        let (test, unknown) = parse_test(fields).with_context(|| format!("test {}", i + 1))?;
+        for key in unknown {
+            diagnostics::warn(
+                path,
+                "unknown-key",
+                format!("test {}: unknown key {}, kept as extra option", i + 1, key),
+            );
+        }
+        tests.push(test);
+    }
+    for key in harness.keys() {
+        if !["tables", "tests", "flags"].contains(&key.as_str()) {
+            diagnostics::warn(path, "unknown-key", format!("unknown key {} ignored", key));
+        }
+    }
+    Ok(TestSuite {
+        name: None,
+        comments: Vec::new(),
+        tags: BTreeSet::new(),
+        display_table: None,
+        table,
+        mode: test_mode(harness.get("flags"))?,
+        translation_modes: BTreeSet::new(),
+        variables: BTreeMap::new(),
+        source: None,
+        tests,
+    })
+}
+
+pub fn import(args: &ImportHarnessArgs) -> Result<()> {
+    let suite =
+        from_path(&args.file).with_context(|| format!("importing {}", args.file.display()))?;
+    let yaml = emit::to_string(&[suite], TestStyle::Block, Quote::Minimal, false)?;
+    match &args.output {
+        Some(path) => fs::write(path, yaml)?,
+        None => print!("{}", yaml),
+    }
+    Ok(())
+}
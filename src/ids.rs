@@ -0,0 +1,77 @@
+//! Stable identifiers of the converted tests.
+//!
+//! Dashboards and xfail-tracking issues refer to individual tests, so
+//! every test gets an `id` derived from what makes it the test it is: the
+//! table of its suite, the direction, its input and the modes it is run
+//! with, given by the suite or the test itself. The id does not depend on
+//! the position of the test, so it survives reordering and repeated
+//! migrations. Tests that agree in all of these are told apart by
+//! a suffix counting their occurrences in the suite, `-2` for the second.
+
+use std::collections::HashMap;
+
+use clap::ValueEnum;
+use louis_migrate_yaml::normalized::{Test, TestSuite};
+use sha2::{Digest, Sha256};
+
+/// The number of hex digits of the hash used as id
+const ID_LENGTH: usize = 12;
+
+/// The id of `test` in `suite`, without the occurrence suffix. `prefix`
+/// is the hash of what the tests of the suite have in common, see
+/// [`suite_prefix`], and `modes` a buffer to serialize the modes into.
+fn base_id(prefix: &Sha256, suite: &TestSuite, test: &Test, modes: &mut Vec<u8>) -> String {
+    let mut hasher = prefix.clone();
+    modes.clear();
+    serde_json::to_writer(&mut *modes, &suite.test_modes(test)).expect("modes serialize to JSON");
+    for part in [test.input.as_bytes(), modes.as_slice()] {
+        hasher.update(part);
+        hasher.update([0]);
+    }
+    let mut id = format!("{:x}", hasher.finalize());
+    id.truncate(ID_LENGTH);
+    id
+}
+
+/// The hash of the table and direction of `suite`, which every id in the
+/// suite starts from.
+fn suite_prefix(suite: &TestSuite) -> Sha256 {
+    let mut hasher = Sha256::new();
+    let table = serde_json::to_string(&suite.table).expect("tables serialize to JSON");
+    let direction = suite
+        .mode
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default();
+    for part in [&table, &direction] {
+        hasher.update(part.as_bytes());
+        hasher.update([0]);
+    }
+    hasher
+}
+
+/// Give every test of `test_suites` without an id its id. Ids already
+/// present, e.g. in a normalized file, are kept.
+pub fn assign(test_suites: &mut [TestSuite]) {
+    let mut modes = Vec::new();
+    for suite in test_suites {
+        let prefix = suite_prefix(suite);
+        let mut occurrences: HashMap<String, usize> = HashMap::with_capacity(suite.tests.len());
+        let ids: Vec<_> = suite
+            .tests
+            .iter()
+            .map(|test| {
+                let id = base_id(&prefix, suite, test, &mut modes);
+                let occurrence = occurrences.entry(id.clone()).or_default();
+                *occurrence += 1;
+                match *occurrence {
+                    1 => id,
+                    n => format!("{}-{}", id, n),
+                }
+            })
+            .collect();
+        for (test, id) in suite.tests.iter_mut().zip(ids) {
+            test.id.get_or_insert(id);
+        }
+    }
+}
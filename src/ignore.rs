@@ -0,0 +1,132 @@
+//! Ignore files of the batch commands.
+//!
+//! Test directories hold yaml that is not meant to be checked, like helper
+//! files, fixtures or known-broken files tracked elsewhere. A `.louisignore`
+//! file lists them in the syntax of a `.gitignore`: one glob pattern per
+//! line, `#` starting a comment, `!` re-including what an earlier pattern
+//! ignored and a trailing `/` matching only directories. A pattern with a
+//! `/` other than a trailing one is relative to the directory of the
+//! ignore file, one without matches at any depth below it. The last
+//! matching pattern decides, and the ignore files of deeper directories
+//! come after those of the directories above them.
+//!
+//! The ignore files apply to the files found by searching a directory or
+//! expanding a glob pattern. They are read from every directory on the path
+//! to a file as it is given, so a relative path only picks up those from
+//! the working directory downwards. Files named on the command line are
+//! never ignored, and `--no-ignore` disables the ignore files.
+
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Result};
+use glob::{MatchOptions, Pattern};
+
+/// The name of the ignore files
+const FILE_NAME: &str = ".louisignore";
+
+/// `*` and `?` do not match a `/`, only `**` crosses directories
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: true,
+    require_literal_separator: true,
+    require_literal_leading_dot: false,
+};
+
+/// A pattern of an ignore file
+#[derive(Debug)]
+struct Rule {
+    pattern: Pattern,
+    /// The pattern started with `!`
+    negated: bool,
+    /// The pattern ended with `/`
+    dir_only: bool,
+}
+
+impl Rule {
+    /// Whether the rule matches `relative`, a file relative to the
+    /// directory of the ignore file, or one of the directories it is in.
+    fn matches(&self, relative: &Path) -> bool {
+        let file = (!self.dir_only).then_some(relative);
+        let dirs = relative
+            .ancestors()
+            .skip(1)
+            .filter(|dir| !dir.as_os_str().is_empty());
+        file.into_iter()
+            .chain(dirs)
+            .any(|path| self.pattern.matches_path_with(path, MATCH_OPTIONS))
+    }
+}
+
+/// Parse the ignore file at `path` with the contents `source`.
+fn parse(path: &Path, source: &str) -> Result<Vec<Rule>> {
+    let mut rules = Vec::new();
+    for (i, line) in source.lines().enumerate() {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        // `\#` and `\!` start patterns with these characters
+        let line = line.strip_prefix('\\').unwrap_or(line);
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        let glob = match line.strip_prefix('/') {
+            Some(anchored) => anchored.to_string(),
+            None if line.contains('/') => line.to_string(),
+            None => format!("**/{}", line),
+        };
+        let pattern = Pattern::new(&glob)
+            .map_err(|e| anyhow!("{}:{}: invalid pattern: {}", path.display(), i + 1, e))?;
+        rules.push(Rule {
+            pattern,
+            negated,
+            dir_only,
+        });
+    }
+    Ok(rules)
+}
+
+/// The ignore files read so far, by directory
+#[derive(Debug, Default)]
+pub struct IgnoreFiles {
+    rules: HashMap<PathBuf, Vec<Rule>>,
+}
+
+impl IgnoreFiles {
+    /// The rules of the ignore file in `dir`, none if there is none.
+    fn rules(&mut self, dir: &Path) -> Result<&[Rule]> {
+        if !self.rules.contains_key(dir) {
+            let file = dir.join(FILE_NAME);
+            let rules = match fs::read_to_string(&file) {
+                Ok(source) => parse(&file, &source)?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+                Err(e) => return Err(anyhow!("reading {}: {}", file.display(), e)),
+            };
+            self.rules.insert(dir.to_path_buf(), rules);
+        }
+        Ok(&self.rules[dir])
+    }
+
+    /// Whether the ignore files on the way to `path` ignore it.
+    pub fn is_ignored(&mut self, path: &Path) -> Result<bool> {
+        let dirs: Vec<&Path> = path.ancestors().skip(1).collect();
+        let mut ignored = false;
+        // from the outermost directory inwards
+        for dir in dirs.into_iter().rev() {
+            let relative = path.strip_prefix(dir).unwrap_or(path);
+            let rules = self.rules(dir)?;
+            if let Some(rule) = rules.iter().rev().find(|rule| rule.matches(relative)) {
+                ignored = !rule.negated;
+            }
+        }
+        Ok(ignored)
+    }
+}
@@ -0,0 +1,307 @@
+//! Analysis of the tables the suites need, including what they include.
+//!
+//! liblouis tables pull in other tables with `include` directives, so the
+//! tables named by a suite are only the roots of what has to be packaged
+//! with it. `analyze-tables` follows the includes from every table of every
+//! suite and reports the closure, the includes that cannot be found and
+//! the cycles among the includes. Like liblouis, an include is looked up
+//! next to the including table first and in the tables directory after.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write as _,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::{Args, ValueEnum};
+use louis_migrate_yaml::normalized::{Table, TestSuite};
+use serde::Serialize;
+
+use crate::{diff::load_file, inputs, progress::Progress, tables::canonical_path};
+
+#[derive(Args, Debug)]
+pub struct AnalyzeTablesArgs {
+    /// The test files whose tables to analyze, legacy or normalized,
+    /// directories to search for them or glob patterns
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    #[command(flatten)]
+    inputs: inputs::InputOptions,
+    /// The directory the tables are read from
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    tables_dir: PathBuf,
+    /// Format of the analysis
+    #[arg(long, value_enum, default_value_t)]
+    format: AnalysisFormat,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum AnalysisFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+/// An include, or a table of a suite, that is not in the tables directory
+#[derive(Debug, Serialize)]
+struct Missing {
+    table: PathBuf,
+    /// The table including it, `None` for a table of the suite itself
+    #[serde(skip_serializing_if = "Option::is_none")]
+    included_by: Option<PathBuf>,
+}
+
+/// The tables one suite needs
+#[derive(Debug, Default, Serialize)]
+struct SuiteTables {
+    /// The tables named by the suite, its display table last
+    tables: Vec<PathBuf>,
+    /// The tables named by the suite and everything they include, in the
+    /// order they were found
+    closure: Vec<PathBuf>,
+    missing: Vec<Missing>,
+    /// Include chains leading back to where they started, the first table
+    /// repeated at the end
+    cycles: Vec<Vec<PathBuf>>,
+    /// Why the tables of the suite were not analyzed, if they were not
+    #[serde(skip_serializing_if = "Option::is_none")]
+    skipped: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct FileTables {
+    file: PathBuf,
+    suites: Vec<SuiteTables>,
+}
+
+/// The include directives of `contents`, as paths of the included tables
+/// in the tables directory. `dir` is the directory of the including table.
+fn include_directives(contents: &str, dir: &Path, tables_dir: &Path) -> Vec<PathBuf> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let mut tokens = line.split_whitespace();
+            match (tokens.next(), tokens.next()) {
+                (Some("include"), Some(name)) => Some(name),
+                _ => None,
+            }
+        })
+        .map(|name| {
+            let beside = canonical_path(&dir.join(name));
+            if tables_dir.join(&beside).is_file() {
+                beside
+            } else {
+                canonical_path(Path::new(name))
+            }
+        })
+        .collect()
+}
+
+/// The include graph of the tables read so far
+struct Graph<'a> {
+    tables_dir: &'a Path,
+    /// The includes of every table, `None` if it does not exist
+    includes: HashMap<PathBuf, Option<Vec<PathBuf>>>,
+}
+
+impl Graph<'_> {
+    /// The tables `table` includes, `None` if it does not exist.
+    fn includes(&mut self, table: &Path) -> Result<Option<Vec<PathBuf>>> {
+        if let Some(includes) = self.includes.get(table) {
+            return Ok(includes.clone());
+        }
+        let file = self.tables_dir.join(table);
+        let includes = match fs::read(&file) {
+            Ok(bytes) => Some(include_directives(
+                &String::from_utf8_lossy(&bytes),
+                table.parent().unwrap_or(Path::new("")),
+                self.tables_dir,
+            )),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+            Err(e) => {
+                return Err(e).with_context(|| format!("Could not read table {}", file.display()))
+            }
+        };
+        self.includes.insert(table.to_path_buf(), includes.clone());
+        Ok(includes)
+    }
+
+    /// Add `table`, included by `included_by`, and everything it includes
+    /// to `tables`. `stack` holds the chain of includes leading to it.
+    fn visit(
+        &mut self,
+        table: &Path,
+        included_by: Option<&Path>,
+        stack: &mut Vec<PathBuf>,
+        tables: &mut SuiteTables,
+    ) -> Result<()> {
+        if let Some(start) = stack.iter().position(|on_stack| on_stack == table) {
+            let mut cycle = stack[start..].to_vec();
+            cycle.push(table.to_path_buf());
+            tables.cycles.push(cycle);
+            return Ok(());
+        }
+        if tables.closure.iter().any(|seen| seen == table)
+            || tables.missing.iter().any(|missing| missing.table == table)
+        {
+            return Ok(());
+        }
+        let Some(includes) = self.includes(table)? else {
+            tables.missing.push(Missing {
+                table: table.to_path_buf(),
+                included_by: included_by.map(Path::to_path_buf),
+            });
+            return Ok(());
+        };
+        tables.closure.push(table.to_path_buf());
+        stack.push(table.to_path_buf());
+        for include in includes {
+            self.visit(&include, Some(table), stack, tables)?;
+        }
+        stack.pop();
+        Ok(())
+    }
+
+    fn analyze(&mut self, suite: &TestSuite) -> Result<SuiteTables> {
+        let mut tables = SuiteTables::default();
+        match &suite.table {
+            Table::Single(path) => tables.tables.push(canonical_path(path)),
+            Table::List(paths) => tables
+                .tables
+                .extend(paths.iter().map(|p| canonical_path(p))),
+            Table::MetaData(..) => {
+                tables.skipped = Some("the table is given by a metadata query".to_string());
+                return Ok(tables);
+            }
+            Table::Inline(contents) => {
+                let includes = include_directives(contents, Path::new(""), self.tables_dir);
+                tables.tables.extend(includes);
+            }
+        }
+        tables
+            .tables
+            .extend(suite.display_table.as_deref().map(canonical_path));
+        for table in tables.tables.clone() {
+            self.visit(&table, None, &mut Vec::new(), &mut tables)?;
+        }
+        Ok(tables)
+    }
+}
+
+fn path_list(paths: &[PathBuf], separator: &str) -> String {
+    paths
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn text(files: &[FileTables]) -> String {
+    let mut out = String::new();
+    let mut needed = BTreeSet::new();
+    let mut missing = BTreeSet::new();
+    let mut cycles = 0;
+    for file in files {
+        writeln!(out, "{}", file.file.display()).unwrap();
+        for (i, suite) in file.suites.iter().enumerate() {
+            if let Some(reason) = &suite.skipped {
+                writeln!(out, "  suite {}: skipped, {}", i + 1, reason).unwrap();
+                continue;
+            }
+            writeln!(
+                out,
+                "  suite {}: {} ({} table(s))",
+                i + 1,
+                path_list(&suite.tables, ", "),
+                suite.closure.len()
+            )
+            .unwrap();
+            for table in &suite.closure {
+                writeln!(out, "    {}", table.display()).unwrap();
+            }
+            for table in &suite.missing {
+                match &table.included_by {
+                    Some(by) => writeln!(
+                        out,
+                        "    missing: {} (included by {})",
+                        table.table.display(),
+                        by.display()
+                    ),
+                    None => writeln!(out, "    missing: {}", table.table.display()),
+                }
+                .unwrap();
+            }
+            for cycle in &suite.cycles {
+                writeln!(out, "    cycle: {}", path_list(cycle, " -> ")).unwrap();
+            }
+            needed.extend(&suite.closure);
+            missing.extend(suite.missing.iter().map(|missing| &missing.table));
+            cycles += suite.cycles.len();
+        }
+    }
+    writeln!(
+        out,
+        "{} table(s) needed, {} missing, {} include cycle(s)",
+        needed.len(),
+        missing.len(),
+        cycles
+    )
+    .unwrap();
+    out
+}
+
+pub fn run(args: &AnalyzeTablesArgs, quiet: bool) -> Result<()> {
+    let files = inputs::expand(&args.files, &args.inputs, &["yaml", "json"])?;
+    let progress = Progress::new(files.len(), quiet);
+    let mut graph = Graph {
+        tables_dir: &args.tables_dir,
+        includes: HashMap::new(),
+    };
+    let mut analyses = Vec::new();
+    for file in &files {
+        let mut suites = Vec::new();
+        for (i, suite) in load_file(file)?.iter().enumerate() {
+            let tables = graph.analyze(suite)?;
+            for missing in &tables.missing {
+                let message = match &missing.included_by {
+                    Some(by) => format!(
+                        "suite {}: {} includes {}, which is not in {}",
+                        i + 1,
+                        by.display(),
+                        missing.table.display(),
+                        args.tables_dir.display()
+                    ),
+                    None => format!(
+                        "suite {}: table {} is not in {}",
+                        i + 1,
+                        missing.table.display(),
+                        args.tables_dir.display()
+                    ),
+                };
+                progress.warn(file, "missing-table", message);
+            }
+            for cycle in &tables.cycles {
+                progress.warn(
+                    file,
+                    "include-cycle",
+                    format!("suite {}: {}", i + 1, path_list(cycle, " -> ")),
+                );
+            }
+            suites.push(tables);
+        }
+        progress.file_done();
+        analyses.push(FileTables {
+            file: file.clone(),
+            suites,
+        });
+    }
+    progress.finish();
+
+    match args.format {
+        AnalysisFormat::Text => print!("{}", text(&analyses)),
+        AnalysisFormat::Json => println!("{}", serde_json::to_string_pretty(&analyses)?),
+    }
+    Ok(())
+}
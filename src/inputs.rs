@@ -0,0 +1,176 @@
+//! Expansion of the input arguments of the batch commands.
+//!
+//! Each argument is a file, a directory that is searched recursively for
+//! files with one of the extensions of the command, or a glob pattern like
+//! `tests/**/*de*.yaml`. Patterns are expanded by the tool itself so that
+//! they work the same with every shell. Files matching one of the
+//! `--exclude` patterns are left out, as are the files found in a directory
+//! or by a pattern that a `.louisignore` file ignores, see [`crate::ignore`].
+//!
+//! Symbolic links to directories are only searched with `--follow-symlinks`,
+//! which does not enter a directory it is already in so that a link cycle
+//! ends. A file reached under several names, through a link or in another
+//! case on a case-insensitive file system, is only processed once, under
+//! the first of its names. Files whose names only differ in case are warned
+//! about, as their outputs would overwrite each other on such a system.
+
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::Args;
+use glob::Pattern;
+
+use crate::{diagnostics, ignore::IgnoreFiles};
+
+/// How the batch commands find their input files
+#[derive(Args, Debug, Default, Clone)]
+pub struct InputOptions {
+    /// Leave out files matching the glob PATTERN (repeatable)
+    #[arg(long, value_name = "PATTERN")]
+    exclude: Vec<Pattern>,
+    /// Do not leave out the files listed in `.louisignore` files
+    #[arg(long)]
+    no_ignore: bool,
+    /// Also search directories that are symbolic links
+    #[arg(long)]
+    follow_symlinks: bool,
+}
+
+/// A search for files with one of `extensions` below some directories
+struct Search<'a> {
+    extensions: &'a [&'a str],
+    follow_symlinks: bool,
+    /// The canonical paths of the directories being searched, outermost
+    /// first
+    stack: Vec<PathBuf>,
+}
+
+impl Search<'_> {
+    /// Collect the files below `dir` into `files`.
+    fn files_below(&mut self, dir: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        let canonical =
+            fs::canonicalize(dir).with_context(|| format!("reading {}", dir.display()))?;
+        if self.stack.contains(&canonical) {
+            return Ok(());
+        }
+        self.stack.push(canonical);
+        for entry in fs::read_dir(dir).with_context(|| format!("reading {}", dir.display()))? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_dir() {
+                if self.follow_symlinks || !entry.file_type()?.is_symlink() {
+                    self.files_below(&path, files)?;
+                }
+            } else if path
+                .extension()
+                .is_some_and(|ext| self.extensions.iter().any(|wanted| ext == *wanted))
+            {
+                files.push(path);
+            }
+        }
+        self.stack.pop();
+        Ok(())
+    }
+}
+
+fn is_pattern(path: &str) -> bool {
+    path.contains(['*', '?', '['])
+}
+
+/// Keep only the first of the `files` that are the same file, and warn
+/// about files whose names only differ in case.
+fn remove_duplicates(files: &mut Vec<PathBuf>) {
+    let mut canonical: HashMap<PathBuf, PathBuf> = HashMap::new();
+    files.retain(|file| {
+        // a file that does not exist is reported by the command
+        let Ok(path) = fs::canonicalize(file) else {
+            return true;
+        };
+        match canonical.entry(path) {
+            Entry::Occupied(first) => {
+                let message = format!("the same file as {}, skipped", first.get().display());
+                diagnostics::warn(file, "duplicate-file", message);
+                false
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(file.clone());
+                true
+            }
+        }
+    });
+    let mut lowercase: HashMap<String, &PathBuf> = HashMap::new();
+    for file in files.iter() {
+        let name = file.to_string_lossy().to_lowercase();
+        if let Some(first) = lowercase.insert(name, file) {
+            diagnostics::warn(
+                file,
+                "case-variant",
+                format!(
+                    "only differs in case from {}, their outputs collide on \
+                     case-insensitive file systems",
+                    first.display()
+                ),
+            );
+        }
+    }
+}
+
+/// The files given by `paths`, sorted and without the ones matching one of
+/// the excluded patterns or given twice. Directories are searched for
+/// files with one of `extensions`, a pattern that matches no file is an
+/// error. The files found are filtered by the ignore files unless
+/// `--no-ignore` is given.
+pub fn expand(
+    paths: &[PathBuf],
+    options: &InputOptions,
+    extensions: &[&str],
+) -> Result<Vec<PathBuf>> {
+    let mut search = Search {
+        extensions,
+        follow_symlinks: options.follow_symlinks,
+        stack: Vec::new(),
+    };
+    let mut files = Vec::new();
+    // the files not named explicitly
+    let mut found = Vec::new();
+    for path in paths {
+        match path.to_str() {
+            Some(pattern) if is_pattern(pattern) => {
+                let before = found.len();
+                for entry in glob::glob(pattern)? {
+                    let entry = entry?;
+                    if entry.is_dir() {
+                        search.files_below(&entry, &mut found)?;
+                    } else {
+                        found.push(entry);
+                    }
+                }
+                if found.len() == before {
+                    bail!("no files match {}", pattern);
+                }
+            }
+            _ if path.is_dir() => search.files_below(path, &mut found)?,
+            _ => files.push(path.clone()),
+        }
+    }
+    let mut ignore_files = IgnoreFiles::default();
+    for file in found {
+        if options.no_ignore || !ignore_files.is_ignored(&file)? {
+            files.push(file);
+        }
+    }
+    files.retain(|file| {
+        !options
+            .exclude
+            .iter()
+            .any(|pattern| pattern.matches_path(file))
+    });
+    files.sort();
+    files.dedup();
+    remove_duplicates(&mut files);
+    Ok(files)
+}
@@ -0,0 +1,11 @@
+//! Building blocks of louis-migrate-yaml for other tools that process
+//! liblouis yaml files.
+
+pub mod builder;
+pub mod checkyaml;
+pub mod format;
+pub mod normalized;
+pub mod parse;
+pub mod transcode;
+pub mod visit;
+pub mod yaml_events;
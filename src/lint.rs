@@ -0,0 +1,744 @@
+//! Static checks on legacy liblouis yaml test files.
+//!
+//! The linter walks the same event stream as the converter but instead of
+//! bailing on the first oddity it records a [`Finding`] for each rule
+//! violation. Every [`Rule`] has a default [`Level`] which can be
+//! overridden on the command line with `--allow` and `--deny`.
+
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use clap::{Args, ValueEnum};
+use louis_migrate_yaml::{
+    normalized::{Mode, TestMode},
+    parse::{
+        is_ambiguous_xfail_reason, key_alias, parse_flags, parse_modes, parse_table,
+        DEFAULT_IDENTITY_TESTS,
+    },
+    transcode,
+    yaml_events::{Event, YamlCursor},
+};
+use serde::Serialize;
+
+use crate::{
+    diagnostics::{self, Position, Severity},
+    inputs,
+    progress::Progress,
+    report::{self, Case, ReportSpec},
+    source_map::SourceMap,
+    unicode,
+};
+
+#[derive(Args, Debug)]
+pub struct LintArgs {
+    /// The yaml files to check, directories to search for them or glob
+    /// patterns
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    #[command(flatten)]
+    inputs: inputs::InputOptions,
+    /// Disable RULE
+    #[arg(long, value_name = "RULE")]
+    allow: Vec<Rule>,
+    /// Report violations of RULE as errors
+    #[arg(long, value_name = "RULE")]
+    deny: Vec<Rule>,
+    /// Output format of the findings
+    #[arg(long, value_enum, default_value_t = Format::Human)]
+    format: Format,
+    /// Write a report with one case per file, given as FORMAT[=PATH], e.g. junit=lint.xml or tap
+    #[arg(long, value_name = "FORMAT[=PATH]")]
+    report: Vec<ReportSpec>,
+    /// Stop at the first file with lint errors instead of checking all
+    /// files and counting the errors at the end
+    #[arg(long)]
+    fail_fast: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Format {
+    Human,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, ValueEnum, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Rule {
+    /// The file could not be parsed at all
+    ParseError,
+    /// A top-level key that is not `display`, `table`, `flags`,
+    /// `variables`, `defaults` or `tests`
+    UnknownKey,
+    /// A `tests:` block without any tests
+    EmptyTests,
+    /// A `tests:` block before any `table:` has been defined
+    TestsBeforeTable,
+    /// An xfail value that is neither a boolean, a reason nor a direction map
+    InvalidXfail,
+    /// A per-test option the converter does not know about
+    UnknownTestOption,
+    /// The same input occurs more than once in a `tests:` block
+    DuplicateTest,
+    /// A top-level key spelled the way older test files did, e.g. `tables`
+    DeprecatedKey,
+    /// An xfail reason that starts like a boolean, e.g. "no reason"
+    AmbiguousXfail,
+    /// The `partialTrans` mode in a suite that is not a forward translation
+    PartialTransDirection,
+    /// Control, invisible or bidi control characters in the input or
+    /// expected output of a test, which make it hard to review
+    InvisibleCharacters,
+    /// A test option the test mode of its suite has no use for, e.g.
+    /// `cursorPos` in a display suite
+    InapplicableOption,
+}
+
+impl Rule {
+    fn default_level(self) -> Level {
+        match self {
+            Rule::EmptyTests
+            | Rule::DuplicateTest
+            | Rule::DeprecatedKey
+            | Rule::AmbiguousXfail
+            | Rule::InvisibleCharacters => Level::Warn,
+            _ => Level::Deny,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Rule::ParseError => "parse-error",
+            Rule::UnknownKey => "unknown-key",
+            Rule::EmptyTests => "empty-tests",
+            Rule::TestsBeforeTable => "tests-before-table",
+            Rule::InvalidXfail => "invalid-xfail",
+            Rule::UnknownTestOption => "unknown-test-option",
+            Rule::DuplicateTest => "duplicate-test",
+            Rule::DeprecatedKey => "deprecated-key",
+            Rule::AmbiguousXfail => "ambiguous-xfail",
+            Rule::PartialTransDirection => "partial-trans-direction",
+            Rule::InvisibleCharacters => "invisible-characters",
+            Rule::InapplicableOption => "inapplicable-option",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Level::Allow => write!(f, "allow"),
+            Level::Warn => write!(f, "warning"),
+            Level::Deny => write!(f, "error"),
+        }
+    }
+}
+
+/// Where in a legacy file a finding was made. Legacy files repeat their
+/// top-level keys, so a location is given as the n-th occurrence of a key
+/// and, inside a `tests:` block, the position of the test.
+#[derive(Debug, Clone, Serialize)]
+pub struct Location {
+    key: String,
+    occurrence: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    test: Option<usize>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "`{}` #{}", self.key, self.occurrence)?;
+        if let Some(test) = self.test {
+            write!(f, ", test {}", test)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Finding {
+    file: PathBuf,
+    rule: Rule,
+    level: Level,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    location: Option<Location>,
+    /// Where the location is in the file, if it could be found
+    #[serde(skip_serializing_if = "Option::is_none")]
+    position: Option<Position>,
+    message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.file.display())?;
+        if let Some(Position { line, column }) = self.position {
+            write!(f, ":{}:{}", line, column)?;
+        }
+        write!(
+            f,
+            ": {}[{}]: {}",
+            self.level,
+            self.rule.name(),
+            self.message
+        )?;
+        if let Some(location) = &self.location {
+            write!(f, " (at {})", location)?;
+        }
+        Ok(())
+    }
+}
+
+/// The effective level of each rule after applying `--allow` and `--deny`.
+pub struct Levels(HashMap<Rule, Level>);
+
+impl Levels {
+    pub fn new(allow: &[Rule], deny: &[Rule]) -> Self {
+        let mut levels = HashMap::new();
+        for rule in allow {
+            levels.insert(*rule, Level::Allow);
+        }
+        for rule in deny {
+            levels.insert(*rule, Level::Deny);
+        }
+        Levels(levels)
+    }
+
+    fn get(&self, rule: Rule) -> Level {
+        self.0
+            .get(&rule)
+            .copied()
+            .unwrap_or_else(|| rule.default_level())
+    }
+}
+
+/// The test options that only make sense for a translation: the
+/// positions, cursors, emphasis and translation modes of a test and the
+/// length of its output
+const TRANSLATION_OPTIONS: &[&str] = &[
+    "inputPos",
+    "outputPos",
+    "cursorPos",
+    "brlCursorPos",
+    "inputClass",
+    "outputClass",
+    "mode",
+    "maxOutputLength",
+];
+
+/// Whether the tests of `test_mode` translate their input. Display and
+/// hyphenation tests do not, so they have no use for the
+/// [`TRANSLATION_OPTIONS`].
+fn translates(test_mode: &TestMode) -> bool {
+    !matches!(
+        test_mode,
+        TestMode::Display | TestMode::Hyphenate | TestMode::HyphenateBraille
+    )
+}
+
+/// The name of `test_mode` in the legacy files, e.g. `bothDirections`.
+fn test_mode_name(test_mode: &TestMode) -> String {
+    test_mode
+        .to_possible_value()
+        .map(|value| value.get_name().to_string())
+        .unwrap_or_default()
+}
+
+struct Linter<'a> {
+    file: &'a Path,
+    levels: &'a Levels,
+    findings: Vec<Finding>,
+    /// The test mode of the most recent `flags`
+    test_mode: TestMode,
+}
+
+impl<'a> Linter<'a> {
+    fn report(&mut self, rule: Rule, location: Option<Location>, message: String) {
+        let level = self.levels.get(rule);
+        if level != Level::Allow {
+            self.findings.push(Finding {
+                file: self.file.to_path_buf(),
+                rule,
+                level,
+                location,
+                position: None,
+                message,
+            });
+        }
+    }
+
+    /// Look up the positions of the locations of the findings in `map`.
+    fn locate(&mut self, map: &SourceMap) {
+        for finding in &mut self.findings {
+            let Some(location) = &finding.location else {
+                continue;
+            };
+            let test = location
+                .test
+                .and_then(|test| map.test(location.occurrence, test));
+            finding.position = test.or_else(|| map.key(&location.key, location.occurrence));
+        }
+    }
+
+    fn lint_document(&mut self, iter: &mut YamlCursor) -> Result<()> {
+        iter.read_stream_start()?;
+        iter.read_document_start()?;
+        iter.read_mapping_start()?;
+
+        let mut occurrences: HashMap<String, usize> = HashMap::new();
+        let mut has_table = false;
+
+        while let Some(event) = iter.next_event()? {
+            match event {
+                Event::Scalar { value, .. } => {
+                    let key = key_alias(&value).unwrap_or(&value).to_string();
+                    let occurrence = occurrences.entry(key.clone()).or_default();
+                    *occurrence += 1;
+                    let location = Location {
+                        key: key.clone(),
+                        occurrence: *occurrence,
+                        test: None,
+                    };
+                    if key != value {
+                        self.report(
+                            Rule::DeprecatedKey,
+                            Some(location.clone()),
+                            format!("`{}` is deprecated, use `{}` instead", value, key),
+                        );
+                    }
+                    match key.as_str() {
+                        "display" => {
+                            iter.read_scalar()?;
+                        }
+                        "variables" => {
+                            iter.skip_value()?;
+                        }
+                        "table" => {
+                            parse_table(iter)?;
+                            has_table = true;
+                        }
+                        "flags" => {
+                            let flags = parse_flags(iter)?;
+                            self.test_mode = flags.test_mode;
+                            if !flags.modes.is_empty() && !translates(&self.test_mode) {
+                                self.report(
+                                    Rule::InapplicableOption,
+                                    Some(location.clone()),
+                                    format!(
+                                        "translation modes have no effect in testmode {}",
+                                        test_mode_name(&self.test_mode)
+                                    ),
+                                );
+                            }
+                            self.lint_modes(&flags.modes, &location);
+                        }
+                        "defaults" => {
+                            iter.read_mapping_start()?;
+                            self.lint_test_options(iter, &location)?;
+                        }
+                        "tests" => {
+                            if !has_table {
+                                self.report(
+                                    Rule::TestsBeforeTable,
+                                    Some(location.clone()),
+                                    "tests defined before any table".to_string(),
+                                );
+                            }
+                            let identity = DEFAULT_IDENTITY_TESTS.contains(&self.test_mode);
+                            self.lint_tests(iter, identity, location)?;
+                        }
+                        other => {
+                            self.report(
+                                Rule::UnknownKey,
+                                Some(location),
+                                format!("unknown key {:?}", other),
+                            );
+                            iter.skip_value()?;
+                        }
+                    }
+                }
+                Event::MappingEnd => {
+                    break;
+                }
+                _ => bail!("expected Scalar, got {:?}", event),
+            }
+        }
+
+        iter.read_document_end()?;
+        iter.read_stream_end()?;
+        Ok(())
+    }
+
+    fn lint_tests(&mut self, iter: &mut YamlCursor, identity: bool, location: Location) -> Result<()> {
+        let mut inputs = HashSet::new();
+        let mut count = 0;
+
+        match iter.next_event()? {
+            Some(Event::SequenceStart) => (),
+            Some(event) if event.is_null() => {
+                self.report(Rule::EmptyTests, Some(location), "no tests defined".to_string());
+                return Ok(());
+            }
+            other => bail!("Expected SequenceStart, got {:?}", other),
+        }
+        while let Some(event) = iter.next_event()? {
+            let mapping = match event {
+                Event::SequenceEnd => break,
+                Event::SequenceStart => false,
+                Event::MappingStart => true,
+                _ => bail!("Expected SequenceStart or MappingStart, got {:?}", event),
+            };
+            count += 1;
+            let location = Location {
+                test: Some(count),
+                ..location.clone()
+            };
+            let input = if mapping {
+                self.lint_test_mapping(iter, identity, &location)?
+            } else {
+                self.lint_test_sequence(iter, identity, &location)?
+            };
+            if !inputs.insert(input.clone()) {
+                self.report(
+                    Rule::DuplicateTest,
+                    Some(location.clone()),
+                    format!("input {:?} is tested more than once", input),
+                );
+            }
+        }
+
+        if count == 0 {
+            self.report(
+                Rule::EmptyTests,
+                Some(location),
+                "no tests defined".to_string(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Lint a test given as a sequence, everything after the SequenceStart.
+    /// Returns the input of the test.
+    fn lint_test_sequence(
+        &mut self,
+        iter: &mut YamlCursor,
+        identity: bool,
+        location: &Location,
+    ) -> Result<String> {
+        let input = iter.read_scalar()?;
+        let mut expected = None;
+        let next = match iter.next_event()? {
+            Some(Event::Scalar { value, .. }) => {
+                expected = Some(value);
+                iter.next_event()?
+            }
+            next if identity => next,
+            _ => bail!("Test {:?} without expected output", input),
+        };
+        match next {
+            Some(Event::SequenceEnd) => (),
+            Some(Event::MappingStart) => {
+                self.lint_test_options(iter, location)?;
+                match iter.next_event()? {
+                    Some(Event::SequenceEnd) => (),
+                    other => bail!("Expected SequenceEnd, got {:?}", other),
+                }
+            }
+            other => bail!("Expected SequenceEnd or MappingStart, got {:?}", other),
+        }
+        self.lint_strings(&input, expected.as_deref(), location);
+        Ok(input)
+    }
+
+    /// Lint a test given as a mapping, everything after the MappingStart.
+    /// Returns the input of the test.
+    fn lint_test_mapping(
+        &mut self,
+        iter: &mut YamlCursor,
+        identity: bool,
+        location: &Location,
+    ) -> Result<String> {
+        let mut input = None;
+        let mut expected = None;
+        while let Some(event) = iter.next_event()? {
+            match event {
+                Event::Scalar { ref value, .. } if value == "input" => {
+                    input = Some(iter.read_scalar()?);
+                }
+                Event::Scalar { ref value, .. } if value == "expected" => {
+                    expected = Some(iter.read_scalar()?);
+                }
+                Event::Scalar { value, .. } => self.lint_test_option(iter, value, location)?,
+                Event::MappingEnd => {
+                    break;
+                }
+                _ => bail!("Expected Scalar or MappingEnd inside test, got {:?}", event),
+            }
+        }
+        match input {
+            Some(input) if expected.is_some() || identity => {
+                self.lint_strings(&input, expected.as_deref(), location);
+                Ok(input)
+            }
+            Some(input) => bail!("Test {:?} without expected output", input),
+            None => bail!("Test without input"),
+        }
+    }
+
+    /// Report the control, invisible and bidi control characters in the
+    /// input and expected output of a test.
+    fn lint_strings(&mut self, input: &str, expected: Option<&str>, location: &Location) {
+        for (what, s) in [("input", Some(input)), ("expected output", expected)] {
+            let Some(s) = s else {
+                continue;
+            };
+            let special = unicode::describe_special(s);
+            if !special.is_empty() {
+                self.report(
+                    Rule::InvisibleCharacters,
+                    Some(location.clone()),
+                    format!("{} {:?} contains {}", what, s, special.join(", ")),
+                );
+            }
+        }
+    }
+
+    fn lint_test_options(&mut self, iter: &mut YamlCursor, location: &Location) -> Result<()> {
+        while let Some(event) = iter.next_event()? {
+            match event {
+                Event::Scalar { value, .. } => self.lint_test_option(iter, value, location)?,
+                Event::MappingEnd => {
+                    break;
+                }
+                _ => bail!("Expected Scalar or MappingEnd inside test, got {:?}", event),
+            }
+        }
+        Ok(())
+    }
+
+    fn lint_test_option(
+        &mut self,
+        iter: &mut YamlCursor,
+        key: String,
+        location: &Location,
+    ) -> Result<()> {
+        if !translates(&self.test_mode) && TRANSLATION_OPTIONS.contains(&key.as_str()) {
+            self.report(
+                Rule::InapplicableOption,
+                Some(location.clone()),
+                format!(
+                    "test option {:?} has no effect in testmode {}",
+                    key,
+                    test_mode_name(&self.test_mode)
+                ),
+            );
+            return iter.skip_value();
+        }
+        match key.as_str() {
+            "xfail" => self.lint_xfail(iter, location),
+            "mode" => {
+                let modes = parse_modes(iter)?;
+                self.lint_modes(&modes, location);
+                Ok(())
+            }
+            "name" | "description" | "cursorPos" | "brlCursorPos" | "inputPos" | "outputPos"
+            | "inputClass" | "outputClass" => iter.skip_value(),
+            _ => {
+                self.report(
+                    Rule::UnknownTestOption,
+                    Some(location.clone()),
+                    format!("unknown test option {:?}", key),
+                );
+                iter.skip_value()
+            }
+        }
+    }
+
+    /// Check that `partialTrans` is only used in forward translation.
+    fn lint_modes(&mut self, modes: &BTreeSet<Mode>, location: &Location) {
+        if modes.contains(&Mode::PartialTrans) && self.test_mode != TestMode::Forward {
+            self.report(
+                Rule::PartialTransDirection,
+                Some(location.clone()),
+                format!(
+                    "partialTrans only applies to forward translation, not to testmode {}",
+                    test_mode_name(&self.test_mode)
+                ),
+            );
+        }
+    }
+
+    fn lint_xfail_reason(&mut self, value: &str, location: &Location) {
+        if is_ambiguous_xfail_reason(value) {
+            self.report(
+                Rule::AmbiguousXfail,
+                Some(location.clone()),
+                format!("xfail reason {:?} reads like a boolean", value),
+            );
+        }
+    }
+
+    fn lint_xfail(&mut self, iter: &mut YamlCursor, location: &Location) -> Result<()> {
+        match iter.next_event()? {
+            Some(Event::Scalar { value, .. }) => self.lint_xfail_reason(&value, location),
+            Some(Event::MappingStart) => {
+                while let Some(event) = iter.next_event()? {
+                    match event {
+                        Event::Scalar { ref value, .. }
+                            if value == "forward" || value == "backward" =>
+                        {
+                            match iter.next_event()? {
+                                Some(Event::Scalar { value: reason, .. }) => {
+                                    self.lint_xfail_reason(&reason, location)
+                                }
+                                Some(event) => {
+                                    self.report(
+                                        Rule::InvalidXfail,
+                                        Some(location.clone()),
+                                        format!("xfail value for {:?} is not a scalar", value),
+                                    );
+                                    iter.skip_subtree(&event)?;
+                                }
+                                other => bail!("Invalid event {:?}", other),
+                            }
+                        }
+                        Event::Scalar { value, .. } => {
+                            self.report(
+                                Rule::InvalidXfail,
+                                Some(location.clone()),
+                                format!(
+                                    "expected 'forward' or 'backward' in xfail, got {:?}",
+                                    value
+                                ),
+                            );
+                            iter.skip_value()?;
+                        }
+                        Event::MappingEnd => {
+                            break;
+                        }
+                        _ => bail!("Expected Scalar or MappingEnd, got {:?}", event),
+                    }
+                }
+            }
+            Some(event) => {
+                self.report(
+                    Rule::InvalidXfail,
+                    Some(location.clone()),
+                    "xfail is neither a boolean, a reason nor a direction map".to_string(),
+                );
+                iter.skip_subtree(&event)?;
+            }
+            other => bail!("Invalid event {:?}", other),
+        }
+        Ok(())
+    }
+}
+
+/// Lint a single file and return all findings that are not allowed.
+pub fn lint_file(path: &Path, levels: &Levels) -> Vec<Finding> {
+    let mut linter = Linter {
+        file: path,
+        levels,
+        findings: Vec::new(),
+        test_mode: TestMode::Forward,
+    };
+    let result = transcode::read_to_string(path).and_then(|(source, _)| {
+        let mut iter = YamlCursor::new(&source)?;
+        let result = linter.lint_document(&mut iter).map_err(|e| iter.locate(e));
+        linter.locate(&SourceMap::new(&source));
+        result
+    });
+    if let Err(e) = result {
+        linter.report(Rule::ParseError, None, e.to_string());
+    }
+    linter.findings
+}
+
+pub fn run(args: &LintArgs, quiet: bool) -> Result<()> {
+    let levels = Levels::new(&args.allow, &args.deny);
+    let mut findings: Vec<Finding> = Vec::new();
+    let mut cases = Vec::new();
+    let files = inputs::expand(&args.files, &args.inputs, &["yaml"])?;
+    let progress = Progress::new(files.len(), quiet);
+    // the files left unchecked by --fail-fast
+    let mut skipped = 0;
+    for (i, file) in files.iter().enumerate() {
+        let file_findings = lint_file(file, &levels);
+        progress.add_warnings(
+            file_findings
+                .iter()
+                .filter(|f| f.level == Level::Warn)
+                .count(),
+        );
+        progress.file_done();
+        let errors: Vec<String> = file_findings
+            .iter()
+            .filter(|f| f.level == Level::Deny)
+            .map(|f| f.to_string())
+            .collect();
+        let failed = !errors.is_empty();
+        cases.push(Case {
+            name: file.display().to_string(),
+            failure: failed
+                .then(|| (format!("{} lint error(s)", errors.len()), errors.join("\n"))),
+        });
+        findings.extend(file_findings);
+        if failed && args.fail_fast {
+            skipped = files.len() - i - 1;
+            break;
+        }
+    }
+    progress.finish();
+
+    for finding in &findings {
+        let severity = match finding.level {
+            Level::Deny => Severity::Error,
+            _ => Severity::Warning,
+        };
+        diagnostics::record(
+            &finding.file,
+            finding.position,
+            severity,
+            finding.rule.name(),
+            &finding.message,
+        );
+    }
+
+    match args.format {
+        Format::Human if !diagnostics::human() => {}
+        Format::Human => {
+            for finding in &findings {
+                eprintln!("{}", finding);
+            }
+        }
+        Format::Json => {
+            println!("{}", serde_json::to_string_pretty(&findings)?);
+        }
+    }
+
+    for spec in &args.report {
+        report::write(spec, "lint", &cases)?;
+    }
+
+    let errors = findings.iter().filter(|f| f.level == Level::Deny).count();
+    if errors > 0 && skipped > 0 {
+        bail!(
+            "{} lint error(s) found, {} remaining file(s) not checked because of --fail-fast",
+            errors,
+            skipped
+        );
+    } else if errors > 0 {
+        bail!("{} lint error(s) found", errors);
+    }
+    Ok(())
+}
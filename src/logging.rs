@@ -0,0 +1,26 @@
+//! Diagnostic logging, controlled by `--verbose` and `--quiet`.
+//!
+//! Warnings are shown by default, `-v` adds a line per parsed section,
+//! `-vv` every consumed yaml event and `-vvv` everything else. The
+//! `RUST_LOG` environment variable overrides the level chosen on the
+//! command line.
+
+use std::io::Write;
+
+use log::LevelFilter;
+
+/// Install the logger for a run with the given `-v` count.
+pub fn init(verbose: u8, quiet: bool) {
+    let level = match (quiet, verbose) {
+        (true, _) => LevelFilter::Error,
+        (false, 0) => LevelFilter::Warn,
+        (false, 1) => LevelFilter::Info,
+        (false, 2) => LevelFilter::Debug,
+        (false, _) => LevelFilter::Trace,
+    };
+    env_logger::Builder::new()
+        .filter_level(level)
+        .parse_default_env()
+        .format(|buf, record| writeln!(buf, "{}", record.args()))
+        .init();
+}
@@ -1,403 +1,869 @@
-use serde::Serialize;
-
 use std::{
-    collections::{HashMap, HashSet},
     fs::{self, File},
-    path::PathBuf,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
+
+use louis_migrate_yaml::{
+    format::Registry,
+    normalized::{Meta, TestMode, TestSuite},
+    parse::{self, ParseOptions, TestSuiteIter, UnknownKeys, Warning},
+    transcode::{self, Mapping},
+    yaml_events::YamlError,
 };
 
-use libyaml::{self, Encoding, Event, ParserIter, ScalarStyle};
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand};
 
-use clap::Parser;
+use serde::Serialize;
 
 use anyhow::{anyhow, bail, Result};
 
+mod braille;
+mod cache;
+mod comments;
+mod config;
+mod conflicts;
+mod diagnostics;
+mod diff;
+mod directions;
+mod dry_run;
+mod emit;
+mod filter;
+mod harness;
+mod ids;
+mod ignore;
+mod includes;
+mod inputs;
+mod lint;
+mod logging;
+mod manifest;
+mod names;
+mod positions;
+mod progress;
+mod report;
+mod review;
+mod rust_module;
+mod scan;
+mod schema;
+mod shard;
+mod snapshot;
+mod snippet;
+mod source_map;
+mod spreadsheet;
+mod summary;
+mod tables;
+mod tags;
+mod template;
+#[cfg(feature = "run-tests")]
+mod run;
+mod unicode;
+mod validate;
+mod watch;
+
+use progress::Progress;
+
+/// The exit status of a run, which scripts and CI jobs can rely on
+#[derive(Debug, Clone, Copy)]
+enum Status {
+    /// Everything was converted or checked without warnings
+    Ok = 0,
+    /// Everything was converted or checked, with warnings
+    Warnings = 1,
+    /// A file could not be parsed or converted, or there were more
+    /// warnings than `--max-warnings` allows
+    Failed = 2,
+    /// A file could not be read or written
+    Io = 3,
+}
+
+const EXIT_STATUS: &str = "\
+Exit status:
+  0  done without warnings
+  1  done with warnings
+  2  parse or conversion errors, or more warnings than --max-warnings
+  3  a file could not be read or written";
+
 /// A migration tool to "normalize" the liblouis yaml test files
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, after_help = EXIT_STATUS)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Only print errors, no warnings, notes or progress bar
+    #[arg(short, long, global = true)]
+    quiet: bool,
+    /// Log what the parser is doing, repeat for more detail (-vv logs
+    /// every yaml event)
+    #[arg(short, long, global = true, action = ArgAction::Count, conflicts_with = "quiet")]
+    verbose: u8,
+    /// Also write all warnings and errors as JSON, given as json[=PATH].
+    /// Without a path the JSON replaces the human-readable messages on stderr
+    #[arg(long, global = true, value_name = "json[=PATH]")]
+    diagnostics: Option<diagnostics::DiagnosticsSpec>,
+    /// Fail if there are more than N warnings
+    #[arg(long, global = true, value_name = "N")]
+    max_warnings: Option<usize>,
     /// The yaml file to convert
-    yaml: PathBuf,
+    #[arg(required = true)]
+    yaml: Option<PathBuf>,
     /// Write output to FILE instead of stdout.
     #[arg(short, long)]
     output: Option<PathBuf>,
+    /// Write output to a file named like the input in DIR instead of stdout
+    #[arg(long, value_name = "DIR", conflicts_with = "output")]
+    output_dir: Option<PathBuf>,
+    /// Write output to the file given by TEMPLATE instead of stdout, in the
+    /// output directory if one is given, e.g. "{stem}.norm.{ext}". The
+    /// placeholders are {dir}, {stem}, {ext}, {mode} and {table_stem}
+    #[arg(long, value_name = "TEMPLATE", conflicts_with = "output")]
+    output_template: Option<template::OutputTemplate>,
+    /// Read settings from the TOML file at PATH instead of
+    /// louis-migrate.toml in the working directory
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// Warn about expected strings of forward tests that are not Unicode braille
+    #[arg(long)]
+    validate_braille: bool,
+    /// Convert expected values given in liblouis dot notation to Unicode braille
+    #[arg(long, conflicts_with = "unicode_to_dots")]
+    dots_to_unicode: bool,
+    /// Convert expected values given in Unicode braille to liblouis dot notation
+    #[arg(long)]
+    unicode_to_dots: bool,
+    /// Rewrite table paths starting with OLD to start with NEW instead (repeatable)
+    #[arg(long, value_name = "OLD=NEW")]
+    map_table_prefix: Vec<tables::PrefixMapping>,
+    /// Split tables given as one string of whitespace-separated file names,
+    /// an old convention, into a list of tables
+    #[arg(long)]
+    split_table_scalar: bool,
+    /// Write every bothDirections suite as a forward suite and a backward
+    /// suite with input and expected output swapped
+    #[arg(long)]
+    explode_directions: bool,
+    /// Merge a backward suite whose tests mirror those of a forward suite
+    /// with the same tables into one bothDirections suite
+    #[arg(long, conflicts_with_all = ["explode_directions", "stream"])]
+    merge_directions: bool,
+    /// Embed the contents of small tables instead of referring to them
+    #[arg(long)]
+    inline_tables: bool,
+    /// The directory the tables are read from for --inline-tables
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    tables_dir: PathBuf,
+    /// Only inline tables of at most BYTES bytes
+    #[arg(long, value_name = "BYTES", default_value_t = 4096)]
+    inline_max_size: u64,
+    /// Apply Unicode normalization to the input strings
+    #[arg(long, value_enum, default_value_t)]
+    normalize_unicode: unicode::Normalization,
+    /// Also apply Unicode normalization to the expected strings
+    #[arg(long, requires = "normalize_unicode")]
+    normalize_expected: bool,
+    /// Preserve comments as `comments` annotations on suites and tests
+    #[arg(long)]
+    comments: bool,
+    /// Give every suite the tag TAG (repeatable)
+    #[arg(long, value_name = "TAG", value_parser = tags::parse_tag)]
+    tag: Vec<String>,
+    /// Give every suite the name of its file, without the extension, as tag
+    #[arg(long)]
+    tag_file_name: bool,
+    /// Turn comments like `# tags: regression, issue-1234` into tags of the
+    /// suite or test they precede
+    #[arg(long, conflicts_with = "stream")]
+    comment_tags: bool,
+    /// Name every suite after the file, its direction and its number among
+    /// the suites of that direction, e.g. `en-ueb-g2_backward#2`
+    #[arg(long)]
+    name_suites: bool,
+    #[command(flatten)]
+    filter: filter::Filter,
+    /// Order of the tests within each suite
+    #[arg(long, value_enum, default_value_t)]
+    sort: filter::SortKey,
+    /// Write each suite as soon as it has been parsed instead of building
+    /// the whole output in memory
+    #[arg(long, conflicts_with = "comments")]
+    stream: bool,
+    /// Map the input files into memory instead of reading them, which
+    /// saves a copy of big files. Files of 64 MiB and more are mapped
+    /// anyway, except with --watch
+    #[arg(long, conflicts_with = "watch")]
+    mmap: bool,
+    /// Keep running and convert the file again whenever it changes
+    #[arg(long, conflicts_with = "diagnostics")]
+    watch: bool,
+    /// Write a report of the conversion, given as FORMAT[=PATH], e.g. junit=report.xml or tap
+    #[arg(long, value_name = "FORMAT[=PATH]")]
+    report: Vec<report::ReportSpec>,
+    /// What to write the conversion as: yaml, rust (a data module for a
+    /// Rust test harness), csv (one row per test) or html (a review page)
+    #[arg(
+        long,
+        value_name = "FORMAT",
+        default_value = "yaml",
+        conflicts_with_all = ["stream", "factor_defaults"]
+    )]
+    format: String,
+    /// The field delimiter of --format csv, e.g. a tab for TSV
+    #[arg(long, value_name = "CHAR", default_value_t = ',')]
+    delimiter: char,
+    /// Layout of the emitted tests
+    #[arg(long, value_enum, default_value_t)]
+    test_style: emit::TestStyle,
+    /// Quoting policy for emitted strings
+    #[arg(long, value_enum, default_value_t)]
+    quote: emit::Quote,
+    /// Write invisible characters like zero width joiners and bidi controls
+    /// as escapes in double-quoted strings, so they can be seen in review
+    #[arg(long)]
+    escape_special: bool,
+    /// What to do with per-test options the converter does not know about
+    #[arg(long, value_enum, default_value_t)]
+    unknown_keys: UnknownKeys,
+    /// Test modes in which a test may be given as just its input, which is
+    /// then also its expected output
+    #[arg(
+        long,
+        value_enum,
+        value_name = "MODE",
+        value_delimiter = ',',
+        default_value = "display"
+    )]
+    identity_tests: Vec<TestMode>,
+    /// Reject the historical variations of the legacy format: deprecated
+    /// and unknown keys, tests given as mappings or without expected
+    /// output, `description` for `name` and the xfail values `on` and `off`
+    #[arg(long, conflicts_with_all = ["unknown_keys", "identity_tests"])]
+    strict: bool,
+    /// Do not record the source file, its checksum, the tool version and
+    /// the time of conversion in a leading `meta` document
+    #[arg(long)]
+    no_provenance: bool,
+    /// Write the table and display table used by most suites once in the
+    /// leading document, the suites only name the tables that differ
+    #[arg(long, conflicts_with = "stream")]
+    factor_defaults: bool,
+    /// Convert but write nothing, print whether the output would be
+    /// created, overwritten or unchanged and how many lines would change
+    #[arg(long, conflicts_with_all = ["stream", "watch"])]
+    dry_run: bool,
+    /// Do not give the tests an `id` derived from their table, direction,
+    /// input and modes
+    #[arg(long)]
+    no_ids: bool,
+    /// Record where each suite and test starts in the legacy file as its
+    /// `source` line and column
+    #[arg(long)]
+    source_positions: bool,
+    /// Ask how to resolve each pair of tests with the same input and modes
+    /// but different expected output, instead of warning about them
+    #[arg(long, conflicts_with_all = ["stream", "watch"])]
+    interactive: bool,
+    /// Append the conflicts resolved with --interactive and the decisions
+    /// to PATH, one JSON object per line
+    #[arg(long, value_name = "PATH", requires = "interactive")]
+    resolution_log: Option<PathBuf>,
+    /// Write the values of the legacy file that the conversion left out to
+    /// PATH as JSON, an empty list if nothing was lost
+    #[arg(long, value_name = "PATH")]
+    loss_ledger: Option<PathBuf>,
+    /// Record the output file with its size and SHA-256 in the JSON
+    /// manifest at PATH, which is updated if it exists
+    #[arg(long, value_name = "PATH")]
+    manifest: Option<PathBuf>,
+    /// Remove the outputs listed in the manifest whose legacy file no
+    /// longer exists, only list them with --dry-run. Outputs changed since
+    /// they were converted are kept with a warning, or a note with
+    /// --dry-run
+    #[arg(long, requires = "manifest")]
+    prune: bool,
+    /// Run the tests through liblouis and mark the ones that fail as xfail
+    #[cfg(feature = "run-tests")]
+    #[arg(long)]
+    auto_xfail: bool,
+    /// With --auto-xfail, also remove xfail from tests that pass
+    #[cfg(feature = "run-tests")]
+    #[arg(long, requires = "auto_xfail")]
+    clear_xfail: bool,
+    /// Run the expected output of `bothDirections` tests backward through
+    /// liblouis and record the result as `expected_backward` where it is
+    /// not the input
+    #[cfg(feature = "run-tests")]
+    #[arg(long)]
+    synthesize_backward: bool,
 }
 
-#[derive(Debug, Default, Clone, Serialize)]
-#[serde(rename_all = "camelCase")]
-enum TestMode {
-    #[default]
-    Forward,
-    Backward,
-    BothDirections,
-    Display,
-    Hyphenate,
-    HyphenateBraille,
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check legacy yaml files for common problems
+    Lint(lint::LintArgs),
+    /// Compare two test files, legacy or normalized, ignoring formatting
+    Diff(diff::DiffArgs),
+    /// Print a JSON Schema describing the normalized format
+    Schema,
+    /// Check normalized files against the model of the normalized format
+    Validate(validate::ValidateArgs),
+    /// Record the hash of the conversion of every file in a directory, or
+    /// compare with such a record
+    Snapshot(snapshot::SnapshotArgs),
+    /// Read tests exported with --format csv, e.g. after review in a
+    /// spreadsheet, back into a normalized file
+    ImportCsv(spreadsheet::ImportCsvArgs),
+    /// Convert a harness file of the format that preceded the yaml tests
+    /// (`*_harness.txt`)
+    ImportHarness(harness::ImportHarnessArgs),
+    /// Follow the includes of the tables of every suite and report the
+    /// tables needed, missing includes and include cycles
+    AnalyzeTables(includes::AnalyzeTablesArgs),
+    /// Split converted files into shards with about the same number of
+    /// tests, for running them in parallel
+    Shard(shard::ShardArgs),
+    /// Summarize the migration of legacy files as a Markdown or HTML report
+    Summary(summary::SummaryArgs),
+    /// Run the tests of a legacy yaml file through liblouis
+    #[cfg(feature = "run-tests")]
+    Run(run::RunArgs),
+    /// Run the tests of a legacy file and of its normalized conversion
+    /// through liblouis and check that every test has the same result
+    #[cfg(feature = "run-tests")]
+    VerifyEquivalence(run::VerifyEquivalenceArgs),
 }
 
-#[derive(Debug, Serialize, Clone)]
-#[serde(untagged)]
-enum Table {
-    Single (PathBuf),
-    List (Vec<PathBuf>),
-    MetaData (HashMap<String, String>),
-    Inline (String),
-}
-
-#[derive(Debug, Serialize)]
-pub struct TestSuite {
-    #[serde(skip_serializing_if = "Option::is_none")]
-    display_table: Option<PathBuf>,
-    table: Table,
-    mode: TestMode,
-    tests: Vec<Test>,
+/// Report the `warnings` the parser found in `yaml`.
+fn report_warnings(yaml: &Path, warnings: &[Warning]) {
+    for warning in warnings {
+        diagnostics::warn(yaml, warning.code, &warning.message);
+    }
 }
 
-#[derive(Debug, Hash, PartialEq, Eq, Serialize)]
-#[serde(rename_all = "camelCase")]
-pub enum Mode {
-    NoContractions,
-    CompbrlAtCursor,
-    DotsIo,
-    CompbrlLeftCursor,
-    UcBrl,
-    NoUndefined,
-    PartialTrans,
+/// Parse the legacy file at `path` and report the warnings about it.
+fn parse_file(path: &Path, options: &ParseOptions) -> Result<Vec<TestSuite>> {
+    let (test_suites, warnings) = parse::parse_file(path, options)?;
+    report_warnings(path, &warnings);
+    Ok(test_suites)
 }
 
-#[derive(Debug, Serialize)]
-#[serde(untagged)]
-enum Xfail {
-    Scalar( bool),
-    Reason(String),
-    Map{forward: bool, backward: bool}
+/// Write the losses among `warnings` to the loss ledger, if one was asked
+/// for.
+fn write_loss_ledger(args: &Args, warnings: &[Warning]) -> Result<()> {
+    if let (Some(path), false) = (&args.loss_ledger, args.dry_run) {
+        let losses: Vec<_> = warnings.iter().filter_map(|w| w.loss.as_ref()).collect();
+        fs::write(path, serde_json::to_string_pretty(&losses)? + "\n")?;
+    }
+    Ok(())
 }
 
-impl Xfail {
-    fn is_false(&self) -> bool {
-	match self {
-	    Self::Scalar ( xfail ) => !(*xfail),
-	    Self::Reason ( .. ) => false,
-	    Self::Map { forward, backward } => !(*forward || *backward)
-	}
+/// Record `output`, the conversion of `yaml`, in the manifest, if one was
+/// asked for.
+fn record_output(args: &Args, output: &Path, yaml: &Path) -> Result<()> {
+    if let (Some(path), false) = (&args.manifest, args.dry_run) {
+        manifest::record(path, output, yaml)?;
     }
+    Ok(())
 }
 
-impl Default for Xfail {
-    fn default() -> Self {
-        Xfail::Scalar( false)
+/// Remove the outputs in the manifest at `path` whose source is gone, or
+/// list them with `--dry-run`.
+fn prune_outputs(args: &Args, path: &Path, progress: &Progress) -> Result<()> {
+    for (output, pruning) in manifest::prune(path, args.dry_run)? {
+        match (pruning, args.dry_run) {
+            (manifest::Pruning::Remove, true) => println!("would remove {}", output.display()),
+            (manifest::Pruning::Remove, false) => progress.note(&output, "removed"),
+            // a dry run changes nothing, so there is nothing to warn about yet
+            (manifest::Pruning::Keep, true) => progress.note(
+                &output,
+                "the source is gone, but the file changed since it was converted, would be kept",
+            ),
+            (manifest::Pruning::Keep, false) => progress.warn(
+                &output,
+                "changed-output",
+                "the source is gone, but the file changed since it was converted, kept",
+            ),
+            (manifest::Pruning::Missing, _) => (),
+        }
     }
+    Ok(())
 }
 
-#[derive(Debug, Default, Serialize)]
-pub struct Test {
-    input: String,
-    expected: String,
-    #[serde(skip_serializing_if = "Xfail::is_false")]
-    xfail: Xfail,
-    // FIXME: add support for typeform:
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    input_pos: Vec<u16>,
-    #[serde(skip_serializing_if = "Vec::is_empty")]
-    output_pos: Vec<u16>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    cursor_pos: Option<u16>,
-    #[serde(skip_serializing_if = "HashSet::is_empty")]
-    mode: HashSet<Mode>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    max_output_length: Option<u16>,
+/// Fail if there were more warnings than `max_warnings`.
+fn check_max_warnings(max_warnings: Option<usize>) -> Result<()> {
+    let warnings = diagnostics::warnings();
+    match max_warnings {
+        Some(max) if warnings > max => bail!(
+            "{} warning(s), more than the {} allowed by --max-warnings",
+            warnings,
+            max
+        ),
+        _ => Ok(()),
+    }
 }
 
-fn read_stream_start(iter: &mut ParserIter) -> Result<()> {
-    match iter.next() {
-        Some(Ok(Event::StreamStart { encoding })) => match encoding {
-            Some(Encoding::Utf8) => Ok(()),
-            _ => bail!("Encoding {:?} not supported", encoding),
-        },
-        _ => bail!("Expected StreamStart"),
+fn main() -> ExitCode {
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    logging::init(args.verbose, args.quiet);
+    diagnostics::init(args.diagnostics.clone());
+
+    let result = config::apply(&mut args, &matches)
+        .and_then(|()| dispatch(&args))
+        .and_then(|()| check_max_warnings(args.max_warnings))
+        .and(diagnostics::finish());
+    let status = match &result {
+        Ok(()) if diagnostics::warnings() > 0 => Status::Warnings,
+        Ok(()) => Status::Ok,
+        Err(e) if e.chain().any(|cause| cause.is::<io::Error>()) => Status::Io,
+        Err(_) => Status::Failed,
+    };
+    // otherwise the error is part of the diagnostics on stderr already
+    if let (Err(e), true) = (result, diagnostics::human()) {
+        if !e.is::<diagnostics::Reported>() {
+            eprintln!("Error: {:?}", e);
+        }
     }
+    ExitCode::from(status as u8)
 }
 
-fn read_stream_end(iter: &mut ParserIter) -> Result<()> {
-    match iter.next() {
-        Some(Ok(Event::StreamEnd)) => Ok(()),
-        _ => bail!("Expected StreamEnd"),
+fn dispatch(args: &Args) -> Result<()> {
+    match &args.command {
+        Some(Command::Lint(lint_args)) => return lint::run(lint_args, args.quiet),
+        Some(Command::Diff(diff_args)) => return diff::run(diff_args),
+        Some(Command::Schema) => return schema::run(),
+        Some(Command::Validate(validate_args)) => {
+            return validate::run(validate_args, args.quiet)
+        }
+        Some(Command::Snapshot(snapshot_args)) => {
+            return snapshot::run(snapshot_args, args.quiet)
+        }
+        Some(Command::ImportCsv(import_args)) => return spreadsheet::import(import_args),
+        Some(Command::ImportHarness(import_args)) => return harness::import(import_args),
+        Some(Command::AnalyzeTables(analyze_args)) => {
+            return includes::run(analyze_args, args.quiet)
+        }
+        Some(Command::Shard(shard_args)) => return shard::run(shard_args, args.quiet),
+        Some(Command::Summary(summary_args)) => return summary::run(summary_args, args.quiet),
+        #[cfg(feature = "run-tests")]
+        Some(Command::Run(run_args)) => return run::run(run_args),
+        #[cfg(feature = "run-tests")]
+        Some(Command::VerifyEquivalence(verify_args)) => {
+            return run::verify_equivalence(verify_args)
+        }
+        None => (),
     }
-}
 
-fn read_document_start(iter: &mut ParserIter) -> Result<()> {
-    match iter.next() {
-        Some(Ok(Event::DocumentStart { .. })) => Ok(()),
-        _ => bail!("Expected DocumentStart"),
+    let yaml = args.yaml.clone().ok_or_else(|| anyhow!("No yaml file given"))?;
+    if args.watch {
+        return watch::run(args, &yaml);
     }
-}
 
-fn read_document_end(iter: &mut ParserIter) -> Result<()> {
-    match iter.next() {
-        Some(Ok(Event::DocumentEnd { .. })) => Ok(()),
-        _ => bail!("Expected DocumentEnd"),
+    let result = convert(args, &yaml).map_err(|e| report_conversion_error(&yaml, e));
+
+    let cases = [report::Case::new(yaml.display().to_string(), &result)];
+    for spec in &args.report {
+        report::write(spec, "convert", &cases)?;
     }
+
+    result
 }
 
-fn read_mapping_start(iter: &mut ParserIter) -> Result<()> {
-    match iter.next() {
-        Some(Ok(Event::MappingStart { .. })) => Ok(()),
-        _ => bail!("Expected MappingStart"),
+/// Record why the conversion of `yaml` failed. An error of the legacy
+/// parser is printed as a snippet of the source at its position, which
+/// comes from the parser if the backend tells it, otherwise from the path
+/// of the error, and is returned as [`diagnostics::Reported`].
+fn report_conversion_error(yaml: &Path, e: anyhow::Error) -> anyhow::Error {
+    let message = format!("{:#}", e);
+    let located = e.downcast_ref::<YamlError>().and_then(|error| {
+        let (source, _) = transcode::read_to_string(yaml).ok()?;
+        let position = match error.mark() {
+            Some(mark) => diagnostics::Position {
+                line: mark.line,
+                column: mark.column,
+            },
+            None => source_map::SourceMap::new(&source).node(error.path())?,
+        };
+        Some((source, position))
+    });
+    match located {
+        Some((source, position)) => {
+            // the snippet shows where the error is, the path is not needed
+            let path = e.downcast_ref::<YamlError>().map_or("", YamlError::path);
+            let message = message
+                .strip_suffix(&format!(" at {}", path))
+                .unwrap_or(&message);
+            diagnostics::error_at(yaml, &source, position, "conversion-failed", message);
+            diagnostics::Reported(e).into()
+        }
+        None => {
+            diagnostics::record(
+                yaml,
+                None,
+                diagnostics::Severity::Error,
+                "conversion-failed",
+                message,
+            );
+            e
+        }
     }
 }
 
-fn read_mapping_end(iter: &mut ParserIter) -> Result<()> {
-    match iter.next() {
-        Some(Ok(Event::MappingEnd)) => Ok(()),
-        _ => bail!("Expected MappingEnd"),
+fn yaml_format(args: &Args) -> emit::Yaml {
+    emit::Yaml {
+        test_style: args.test_style,
+        quote: args.quote,
+        escape_special: args.escape_special,
+        factor_defaults: args.factor_defaults,
     }
 }
 
-fn read_sequence_start(iter: &mut ParserIter) -> Result<()> {
-    match iter.next() {
-        Some(Ok(Event::SequenceStart { .. })) => Ok(()),
-        _ => bail!("Expected SequenceStart"),
-    }
+fn to_yaml<T: Serialize>(args: &Args, value: &T) -> Result<String> {
+    yaml_format(args).to_string(value)
 }
 
-fn read_sequence_end(iter: &mut ParserIter) -> Result<()> {
-    match iter.next() {
-        Some(Ok(Event::SequenceEnd)) => Ok(()),
-        _ => bail!("Expected SequenceEnd"),
-    }
+/// The formats `--format` can select.
+fn formats(args: &Args) -> Registry {
+    let mut registry = Registry::new();
+    registry.register(yaml_format(args));
+    registry.register(rust_module::RustModule);
+    registry.register(spreadsheet::Csv {
+        delimiter: args.delimiter,
+    });
+    registry.register(review::ReviewPage);
+    registry
 }
 
-fn read_scalar(iter: &mut ParserIter) -> Result<String> {
-    match iter.next() {
-        Some(Ok(Event::Scalar { value, .. })) => Ok(value),
-        _ => bail!("Expected Scalar"),
+/// Apply the transformations selected on the command line to suites
+/// parsed from `yaml`.
+fn transform(
+    args: &Args,
+    yaml: &Path,
+    test_suites: &mut Vec<TestSuite>,
+    progress: &Progress,
+) -> Result<()> {
+    tags::add(test_suites, &args.tag);
+    if let Some(tag) = args.tag_file_name.then(|| tags::file_name_tag(yaml)).flatten() {
+        tags::add(test_suites, &[tag]);
     }
-}
 
-fn read_table_metadata(iter: &mut ParserIter) -> Result<Table> {
-    let mut metadata = HashMap::new();
-    while let Some(Ok(event)) = iter.next() {
-        match event {
-            Event::Scalar { value, .. } => {
-                metadata.insert(value, read_scalar(iter)?);
-            }
-            Event::MappingEnd => {
-                break;
-            }
-            _ => bail!("Expected Scalar or MappingEnd, got {:?}", event),
-        };
+    if args.explode_directions {
+        let split = directions::explode(test_suites);
+        progress.note(yaml, format!("{} bothDirections suite(s) split", split));
+    }
+    if args.merge_directions {
+        let (merged, unmerged) = directions::merge(test_suites);
+        progress.note(yaml, format!("{} pair(s) of suites merged", merged));
+        for unmerged in unmerged {
+            progress.warn_at(yaml, unmerged.position, "unmerged-directions", unmerged);
+        }
     }
-    Ok(Table::MetaData (metadata))
-}
 
-fn read_table_files(iter: &mut ParserIter) -> Result<Table> {
-    let mut files = Vec::new();
-    while let Some(Ok(event)) = iter.next() {
-        match event {
-            Event::Scalar { value, .. } => {
-                files.push(value.into());
-            }
-            Event::SequenceEnd => {
-                break;
-            }
-            _ => bail!("Expected Scalar or SequenceEnd, got {:?}", event),
-        };
+    let removed = args.filter.apply(test_suites);
+    if removed > 0 {
+        progress.note(yaml, format!("{} test(s) filtered out", removed));
     }
-    Ok(Table::List (files ))
-}
+    filter::sort(test_suites, args.sort);
 
-fn parse_table(iter: &mut ParserIter) -> Result<Table> {
-    match iter.next() {
-        Some(Ok(event)) => match event {
-            Event::MappingStart { .. } => read_table_metadata(iter),
-            Event::Scalar { value, style, .. } => match style {
-                Some(ScalarStyle::Plain) => Ok(Table::Single (value.into())),
-                Some(ScalarStyle::Literal) => Ok(Table::Inline (value)),
-                other => bail!("Scalar of style {:?} not supported", other),
-            },
-            Event::SequenceStart { .. } => read_table_files(iter),
-            other => bail!(
-                "Expected Scalar, MappingStart or SequenceStart, got {:?}",
-                other
-            ),
-        },
-        other => bail!("Invalid event {:?}", other),
+    if args.split_table_scalar {
+        let split = tables::split_scalars(test_suites);
+        progress.note(yaml, format!("tables of {} suite(s) split", split));
     }
-}
 
-fn parse_flags(iter: &mut ParserIter) -> Result<TestMode> {
-    read_mapping_start(iter)?;
-    match iter.next() {
-        Some(Ok(Event::Scalar { ref value, .. })) if value == "testmode" => match iter.next() {
-            Some(Ok(Event::Scalar { value, .. })) => {
-                let mode = match value.as_str() {
-                    "forward" => TestMode::Forward,
-                    "backward" => TestMode::Backward,
-                    "bothDirections" => TestMode::BothDirections,
-                    "display" => TestMode::Display,
-                    "hyphenate" => TestMode::Hyphenate,
-                    "hyphenateBraille" => TestMode::HyphenateBraille,
-                    _ => bail!("Testmode {:?} not supported", value),
-                };
-                read_mapping_end(iter)?;
-                Ok(mode)
-            }
-            _ => bail!("Expected Scalar"),
-        },
-        _ => bail!("Expected Scalar testmode"),
+    for warning in tables::canonicalize(test_suites) {
+        progress.warn(yaml, "duplicate-table", warning);
     }
-}
 
-fn read_xfail_value(value: String) -> Xfail {
-    match value.as_str() {
-        "off"| "false" => Xfail::Scalar(false),
-        "on" | "true" => Xfail::Scalar(true),
-        _ => Xfail::Reason(value),
+    if args.inline_tables {
+        let inlined = tables::inline(test_suites, &args.tables_dir, args.inline_max_size)?;
+        progress.note(yaml, format!("tables of {} suite(s) inlined", inlined));
     }
-}
 
-fn parse_xfail_value(iter: &mut ParserIter) -> Result<Xfail> {
-    let xfail = match iter.next() {
-        Some(Ok(Event::Scalar { value, .. })) => read_xfail_value(value),
-        Some(Ok(Event::MappingStart { .. })) => {
-	    let mut forward = false;
-	    let mut backward = false;
-            while let Some(Ok(event)) = iter.next() {
-                match event {
-                    Event::Scalar { value, .. } => {
-			match value.as_str() {
-			    "forward" => forward = !matches!(read_scalar(iter)?.as_str(), "off" | "false" ),
-			    "backward" => backward = !matches!(read_scalar(iter)?.as_str(), "off" | "false" ),
-			    other => bail!("Expected 'forward' or 'backward', got {:?}", other),
-			};
-                    }
-                    Event::MappingEnd => {
-                        break;
-                    }
-                    _ => {
-                        bail!("Expected Scalar or MappingEnd, got {:?}", event);
-                    }
-		}
-	    }
-            Xfail::Map{forward, backward}
+    if !args.map_table_prefix.is_empty() {
+        for (old, new) in tables::map_prefixes(test_suites, &args.map_table_prefix) {
+            progress.note(
+                yaml,
+                format!("rewrote table {} -> {}", old.display(), new.display()),
+            );
         }
-        other => bail!("Expected scalar xfail value, got {:?}", other),
-    };
-    Ok(xfail)
-}
+    }
 
-fn parse_test(iter: &mut ParserIter) -> Result<Test> {
-    let input = read_scalar(iter)?;
-    let expected = read_scalar(iter)?;
-    match iter.next() {
-        Some(Ok(Event::SequenceEnd)) => Ok(Test {
-            input,
-            expected,
-            ..Default::default()
-        }),
-        Some(Ok(Event::MappingStart { .. })) => {
-            let mut xfail = Default::default();
-            while let Some(Ok(event)) = iter.next() {
-                match event {
-                    Event::Scalar { ref value, .. } if value == "xfail" => {
-                        xfail = parse_xfail_value(iter)?;
-                    }
-                    Event::MappingEnd => {
-                        break;
-                    }
-                    _ => {
-                        bail!("Expected Scalar or MappingEnd inside test, got {:?}", event);
-                    }
-                }
-            }
+    if args.dots_to_unicode {
+        braille::convert_expected(test_suites, braille::dots_to_unicode);
+    } else if args.unicode_to_dots {
+        braille::convert_expected(test_suites, braille::unicode_to_dots);
+    }
 
-            read_sequence_end(iter)?;
+    if args.normalize_unicode != unicode::Normalization::None {
+        let changed = unicode::normalize(
+            test_suites,
+            args.normalize_unicode,
+            args.normalize_expected,
+        );
+        progress.note(
+            yaml,
+            format!("{} string(s) changed by Unicode normalization", changed),
+        );
+    }
 
-            Ok(Test {
-                input,
-                expected,
-                xfail,
-                ..Default::default()
-            })
-            // handle options
+    if args.validate_braille {
+        let (suspicious, unknown) = braille::validate(test_suites);
+        for table in unknown {
+            progress.warn_at(yaml, table.position, "unknown-display-table", table);
+        }
+        for suspicious in suspicious {
+            progress.warn_at(yaml, suspicious.position, "not-unicode-braille", suspicious);
         }
-        _ => bail!("Expected SequenceEnd or MappingStart"),
     }
-}
 
-fn parse_tests(iter: &mut ParserIter) -> Result<Vec<Test>> {
-    let mut tests: Vec<Test> = Vec::new();
+    for mismatch in positions::validate(test_suites) {
+        progress.warn_at(yaml, mismatch.position, mismatch.code, mismatch);
+    }
 
-    read_sequence_start(iter)?;
-    while let Some(Ok(event)) = iter.next() {
-        if event == Event::SequenceEnd {
-            break;
-        };
-        let Event::SequenceStart { .. } = event else {
-	    bail!("Expected SequenceStart, got {:?}", event)
-	};
-        tests.push(parse_test(iter)?);
+    #[cfg(feature = "run-tests")]
+    if args.synthesize_backward {
+        let synthesized = run::synthesize_backward(test_suites)?;
+        progress.note(yaml, format!("{} expected_backward value(s) synthesized", synthesized));
+    }
+
+    #[cfg(feature = "run-tests")]
+    if args.auto_xfail {
+        let (marked, cleared) = run::auto_xfail(test_suites, args.clear_xfail)?;
+        progress.note(
+            yaml,
+            format!("{} test(s) marked as xfail, {} xfail(s) cleared", marked, cleared),
+        );
+    }
+
+    if !args.no_ids {
+        ids::assign(test_suites);
     }
-    Ok(tests)
+
+    progress.add_tests(test_suites.iter().map(|suite| suite.tests.len()).sum());
+    Ok(())
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
-
-    let reader = File::open(args.yaml)?;
-    let parser = libyaml::Parser::new(reader)?;
-    let mut iter = parser.into_iter();
-
-    read_stream_start(&mut iter)?;
-    read_document_start(&mut iter)?;
-    read_mapping_start(&mut iter)?;
-
-    let mut test_suites: Vec<TestSuite> = Vec::new();
-    let mut display_table = None;
-    let mut table = None;
-    let mut test_mode: TestMode = TestMode::Forward;
-
-    while let Some(Ok(event)) = iter.next() {
-        match event {
-            Event::Scalar { value, .. } => match value.as_str() {
-                "display" => display_table = Some(read_scalar(&mut iter)?.into()),
-                "table" => table = Some(parse_table(&mut iter)?),
-                "flags" => test_mode = parse_flags(&mut iter)?,
-                "tests" => {
-                    let test_suite = TestSuite {
-                        display_table: display_table.clone(),
-                        table: table
-                            .clone()
-                            .ok_or_else(|| anyhow!("No table defined for tests"))?,
-                        mode: test_mode.clone(),
-                        tests: parse_tests(&mut iter)?,
-                    };
-                    test_suites.push(test_suite);
-                }
-                other => bail!("unknown key {:?}", other),
-            },
-            Event::MappingEnd => {
-                break;
+fn convert(args: &Args, yaml: &Path) -> Result<()> {
+    let options = ParseOptions {
+        unknown_keys: args.unknown_keys,
+        identity_tests: args.identity_tests.clone(),
+        strict: args.strict,
+    };
+
+    let formats = formats(args);
+    let format = formats.get(&args.format).ok_or_else(|| {
+        anyhow!(
+            "unknown format {}, expected one of {}",
+            args.format,
+            formats.names().join(", ")
+        )
+    })?;
+
+    // a watched file changes while it is in use, which a map would expose
+    let mapping = match (args.mmap, args.watch) {
+        (_, true) => Mapping::Never,
+        (true, false) => Mapping::Always,
+        (false, false) => Mapping::Large,
+    };
+    let (source, meta) = if args.no_provenance {
+        (parse::read_source(yaml, mapping)?, Meta::default())
+    } else {
+        let (source, transcoded_from, sha256) = parse::read_source_hashed(yaml, mapping)?;
+        (source, Meta::new(yaml, sha256, transcoded_from))
+    };
+
+    let to_stdout =
+        args.output.is_none() && args.output_dir.is_none() && args.output_template.is_none();
+    if args.manifest.is_some() && to_stdout {
+        bail!("--manifest needs an output file, see --output, --output-dir and --output-template");
+    }
+
+    let progress = Progress::new(1, args.quiet);
+    if let (Some(path), true) = (&args.manifest, args.prune) {
+        prune_outputs(args, path, &progress)?;
+    }
+    if args.stream {
+        let mut header = String::new();
+        if meta.meta.is_some() {
+            header.push_str(&to_yaml(args, &meta)?);
+            header.push_str("---\n");
+        }
+        let warnings = convert_streaming(args, yaml, &source, &options, header, &progress)?;
+        write_loss_ledger(args, &warnings)?;
+        if let Some(path) = output_path(args, yaml, None, None)? {
+            record_output(args, &path, yaml)?;
+        }
+        progress.file_done();
+        progress.finish();
+        return Ok(());
+    }
+
+    let (mut test_suites, warnings) = parse::parse_source(yaml, &source, &options)?;
+    report_warnings(yaml, &warnings);
+    write_loss_ledger(args, &warnings)?;
+
+    // a normalized file has its comments and positions in the suites
+    // already, if any
+    let is_normalized = parse::is_normalized(&source);
+    if !is_normalized {
+        source_map::SourceMap::new(&source).locate(&mut test_suites);
+        if args.comments || args.comment_tags {
+            for warning in comments::attach(&mut test_suites, &source) {
+                progress.warn(yaml, "unmatched-comments", warning);
             }
-            _ => {
-                bail!("expected Scalar, got {:?}", event);
+        }
+        if args.comment_tags {
+            let found = tags::from_comments(&mut test_suites);
+            progress.note(yaml, format!("{} tag(s) found in comments", found));
+            if !args.comments {
+                comments::forget(&mut test_suites);
             }
         }
     }
 
-    read_document_end(&mut iter)?;
-    read_stream_end(&mut iter)?;
-
-    let yaml = serde_yaml::to_string(&test_suites)?;
+    if args.name_suites {
+        names::SuiteNamer::new(yaml).assign(&mut test_suites);
+    }
+    transform(args, yaml, &mut test_suites, &progress)?;
+    if args.interactive {
+        let source = (!is_normalized).then_some(&*source);
+        let resolutions = progress.suspend(|| {
+            conflicts::resolve(
+                yaml,
+                source,
+                &mut test_suites,
+                &mut io::stdin().lock(),
+                &mut io::stderr(),
+            )
+        })?;
+        if let (Some(path), false) = (&args.resolution_log, args.dry_run) {
+            conflicts::log(path, &resolutions)?;
+        }
+    } else {
+        conflicts::warn(yaml, &test_suites, &progress);
+    }
+    if !args.source_positions {
+        source_map::forget(&mut test_suites);
+    }
+    let formatted = format.format(&test_suites, &meta)?;
+    for warning in formatted.warnings {
+        progress.warn(yaml, "not-representable", warning);
+    }
+    progress.file_done();
+    progress.finish();
 
-    match args.output {
+    let path = output_path(args, yaml, format.extension(), Some(&test_suites))?;
+    if args.dry_run {
+        return dry_run::preview(path.as_deref(), &formatted.output);
+    }
+    match path {
         Some(path) => {
-            fs::write(path, yaml)?;
-        }
-        None => {
-            println!("{}", yaml);
+            create_output_dir(&path)?;
+            fs::write(&path, formatted.output)?;
+            record_output(args, &path, yaml)?;
         }
+        // the yaml is written with an empty line at the end, as always
+        None if format.name() == "yaml" => println!("{}", formatted.output),
+        None => print!("{}", formatted.output),
     }
 
     Ok(())
 }
+
+/// Create the directory the output file `path` is written to.
+fn create_output_dir(path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+/// The file the conversion of `yaml` into `test_suites` is written to,
+/// `None` for stdout. In an output directory the file is named like `yaml`,
+/// with `extension` if one is given, unless there is an output template.
+/// `test_suites` is `None` when streaming.
+fn output_path(
+    args: &Args,
+    yaml: &Path,
+    extension: Option<&str>,
+    test_suites: Option<&[TestSuite]>,
+) -> Result<Option<PathBuf>> {
+    if let Some(template) = &args.output_template {
+        let path = template.render(yaml, extension, test_suites)?;
+        return Ok(Some(match &args.output_dir {
+            Some(dir) => dir.join(path),
+            None => path,
+        }));
+    }
+    match (&args.output, &args.output_dir) {
+        (Some(path), _) => Ok(Some(path.clone())),
+        (None, Some(dir)) => {
+            let name = yaml
+                .file_name()
+                .ok_or_else(|| anyhow!("{} is not a file", yaml.display()))?;
+            let path = dir.join(name);
+            match extension {
+                Some(extension) => Ok(Some(path.with_extension(extension))),
+                None => Ok(Some(path)),
+            }
+        }
+        (None, None) => Ok(None),
+    }
+}
+
+/// Convert `yaml`, whose contents are `source`, one suite at a time,
+/// writing each suite as soon as it has been parsed. `header` is written
+/// first. Returns the parser warnings.
+fn convert_streaming(
+    args: &Args,
+    yaml: &Path,
+    source: &str,
+    options: &ParseOptions,
+    header: String,
+    progress: &Progress,
+) -> Result<Vec<Warning>> {
+    if args
+        .output_template
+        .as_ref()
+        .is_some_and(|template| template.needs_suites())
+    {
+        bail!("{{mode}} and {{table_stem}} of --output-template cannot be used with --stream");
+    }
+    let output = output_path(args, yaml, None, None)?;
+    let mut out: Box<dyn Write> = match &output {
+        Some(path) => {
+            create_output_dir(path)?;
+            Box::new(BufWriter::new(File::create(path)?))
+        }
+        None => Box::new(io::stdout().lock()),
+    };
+    out.write_all(header.as_bytes())?;
+
+    // a sequence of suites is written as the concatenation of one-element
+    // sequences
+    let mut empty = true;
+    let mut suites = TestSuiteIter::from_source(yaml, source, options.clone())?;
+    let mut namer = args.name_suites.then(|| names::SuiteNamer::new(yaml));
+    for suite in &mut suites {
+        let mut test_suites = vec![suite?];
+        if let Some(namer) = &mut namer {
+            namer.assign(&mut test_suites);
+        }
+        transform(args, yaml, &mut test_suites, progress)?;
+        if !test_suites.is_empty() {
+            out.write_all(to_yaml(args, &test_suites)?.as_bytes())?;
+            empty = false;
+        }
+    }
+    let warnings = suites.into_warnings();
+    report_warnings(yaml, &warnings);
+    if empty {
+        out.write_all(to_yaml(args, &Vec::<TestSuite>::new())?.as_bytes())?;
+    }
+    if output.is_none() {
+        // the same trailing newline as in the buffered case
+        writeln!(out)?;
+    }
+    out.flush()?;
+    Ok(warnings)
+}
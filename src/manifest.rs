@@ -0,0 +1,128 @@
+//! A manifest of the converted files, for packaging.
+//!
+//! The release process packages the converted tests and has to verify
+//! them. `--manifest PATH` records the file a conversion writes with its
+//! size and SHA-256, and the legacy file it was converted from, in a JSON
+//! manifest. The manifest is updated in place, so converting a corpus file
+//! by file into one manifest lists all outputs. An entry whose file has
+//! changed since, or whose source is gone, is stale.
+//!
+//! With `--prune`, the outputs whose source is gone, e.g. because it was
+//! deleted or renamed upstream, are removed along with their entries. An
+//! output that changed since it was recorded is not ours to remove, it is
+//! kept and only its entry is dropped.
+//!
+//! Outputs are listed by their path relative to the directory of the
+//! manifest if they are below it, with `/` as separator on every platform.
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::cache;
+
+/// A file written by a conversion
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Entry {
+    /// The legacy file it was converted from
+    pub source: PathBuf,
+    /// The size in bytes
+    pub size: u64,
+    /// The SHA-256 of the contents, hex encoded
+    pub sha256: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Manifest {
+    /// The outputs by path
+    pub files: BTreeMap<String, Entry>,
+}
+
+impl Manifest {
+    /// Read the manifest at `path`, which is empty if there is none yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(json) => serde_json::from_str(&json)
+                .with_context(|| format!("reading the manifest {}", path.display())),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Manifest::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, serde_json::to_string_pretty(self)? + "\n")?;
+        Ok(())
+    }
+}
+
+/// The key of `output` in the manifest at `manifest`.
+fn key(manifest: &Path, output: &Path) -> String {
+    let dir = manifest.parent().unwrap_or(Path::new(""));
+    let relative = output.strip_prefix(dir).unwrap_or(output);
+    relative.to_string_lossy().replace('\\', "/")
+}
+
+/// Record `output`, converted from `source`, in the manifest at `manifest`.
+pub fn record(manifest: &Path, output: &Path, source: &Path) -> Result<()> {
+    let contents = fs::read(output)?;
+    let entry = Entry {
+        source: source.to_path_buf(),
+        size: contents.len() as u64,
+        sha256: cache::hash(&contents),
+    };
+    let mut files = Manifest::load(manifest)?;
+    files.files.insert(key(manifest, output), entry);
+    files.save(manifest)
+}
+
+/// What pruning does with an output whose source is gone
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pruning {
+    /// The output is removed
+    Remove,
+    /// The output changed since it was recorded and is kept
+    Keep,
+    /// The output does not exist anymore
+    Missing,
+}
+
+/// The path of the output listed as `key` in the manifest at `manifest`.
+fn output_path(manifest: &Path, key: &str) -> PathBuf {
+    manifest.parent().unwrap_or(Path::new("")).join(key)
+}
+
+/// Drop the entries of the manifest at `manifest` whose source no longer
+/// exists, and remove their outputs. With `dry_run` nothing is changed.
+/// Returns the outputs and what is done with them.
+pub fn prune(manifest: &Path, dry_run: bool) -> Result<Vec<(PathBuf, Pruning)>> {
+    let mut files = Manifest::load(manifest)?;
+    let mut pruned = Vec::new();
+    files.files.retain(|key, entry| {
+        if entry.source.exists() {
+            return true;
+        }
+        let output = output_path(manifest, key);
+        let pruning = match fs::read(&output) {
+            Ok(contents) if cache::hash(&contents) == entry.sha256 => Pruning::Remove,
+            Ok(_) => Pruning::Keep,
+            Err(_) => Pruning::Missing,
+        };
+        pruned.push((output, pruning));
+        false
+    });
+    if !dry_run && !pruned.is_empty() {
+        for (output, pruning) in &pruned {
+            if *pruning == Pruning::Remove {
+                fs::remove_file(output)
+                    .with_context(|| format!("removing {}", output.display()))?;
+            }
+        }
+        files.save(manifest)?;
+    }
+    Ok(pruned)
+}
@@ -0,0 +1,54 @@
+//! Names of the converted suites.
+//!
+//! A file holds several suites, often with the same table, so logs and
+//! reports of the harness cannot tell them apart by their table. With
+//! `--name-suites`, every suite is named after the file it comes from, its
+//! direction and its number among the suites of that direction in the
+//! file, e.g. `en-ueb-g2_backward#2` for the second backward suite of
+//! `en-ueb-g2.yaml`.
+
+use std::{collections::HashMap, path::Path};
+
+use clap::ValueEnum;
+use louis_migrate_yaml::normalized::TestSuite;
+
+/// Names the suites of one file, which may be passed in several parts as
+/// they are parsed.
+pub struct SuiteNamer {
+    stem: String,
+    /// The number of suites of each direction named so far
+    counts: HashMap<String, usize>,
+}
+
+impl SuiteNamer {
+    /// A namer for the suites of the file at `path`.
+    pub fn new(path: &Path) -> Self {
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        SuiteNamer {
+            stem,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Give every suite of `test_suites` without a name its name. Names
+    /// already present, e.g. in a normalized file, are kept but the suites
+    /// are counted all the same.
+    pub fn assign(&mut self, test_suites: &mut [TestSuite]) {
+        for suite in test_suites {
+            let direction = suite
+                .mode
+                .to_possible_value()
+                .map(|value| value.get_name().to_string())
+                .unwrap_or_default();
+            let count = self.counts.entry(direction.clone()).or_default();
+            *count += 1;
+            let count = *count;
+            suite
+                .name
+                .get_or_insert_with(|| format!("{}_{}#{}", self.stem, direction, count));
+        }
+    }
+}
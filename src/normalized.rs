@@ -0,0 +1,516 @@
+//! The normalized test format.
+//!
+//! These are the types the converter produces from the legacy files and
+//! that are written out, read back by `validate` and described by the
+//! JSON Schema.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use anyhow::{bail, Result};
+use clap::ValueEnum;
+use schemars::JsonSchema;
+use serde::{
+    ser::{Error as _, SerializeMap},
+    Deserialize, Serialize, Serializer,
+};
+
+/// The kind of test that is run for all tests of a suite
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema, ValueEnum)]
+#[serde(rename_all = "camelCase")]
+#[value(rename_all = "camelCase")]
+pub enum TestMode {
+    #[default]
+    Forward,
+    Backward,
+    BothDirections,
+    Display,
+    Hyphenate,
+    HyphenateBraille,
+}
+
+impl TestMode {
+    /// The name under which the expected output of a test is written in
+    /// this mode: the dots of a display test, the hyphenated word of a
+    /// hyphenation test and the expected translation otherwise.
+    pub fn expected_key(&self) -> &'static str {
+        match self {
+            TestMode::Display => "dots",
+            TestMode::Hyphenate | TestMode::HyphenateBraille => "hyphenated",
+            _ => "expected",
+        }
+    }
+}
+
+/// The translation table(s) used by a suite: a single file, a list of
+/// files, a metadata query or the inline contents of a table
+// Inline tables cannot be told apart from file names when reading a
+// normalized file, they are read back as a single file.
+#[derive(Debug, Serialize, Deserialize, Clone, JsonSchema)]
+#[serde(untagged)]
+pub enum Table {
+    Single(PathBuf),
+    List(Vec<PathBuf>),
+    MetaData(BTreeMap<String, String>),
+    Inline(String),
+}
+
+/// A group of tests sharing the same tables and test mode. The expected
+/// output of its tests is written under the [`TestMode::expected_key`] of
+/// the suite.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct TestSuite {
+    /// A name identifying the suite in logs and reports, see
+    /// `--name-suites`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+    /// Labels to select the tests by, e.g. `regression` or `issue-1234`.
+    /// They apply to all tests of the suite.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_table: Option<PathBuf>,
+    pub table: Table,
+    pub mode: TestMode,
+    /// The translation modes all tests of the suite are run with. A test is
+    /// run with these and the modes of its own `mode` merged, it cannot
+    /// drop a mode of its suite.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub translation_modes: BTreeSet<Mode>,
+    /// The `variables` the legacy harness was given for the suite, kept
+    /// verbatim
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schemars(with = "BTreeMap<String, serde_json::Value>")]
+    pub variables: BTreeMap<String, serde_yaml::Value>,
+    /// Where the `tests` block of the suite starts in the legacy file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourcePosition>,
+    pub tests: Vec<Test>,
+}
+
+impl Serialize for TestSuite {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut suite = serializer.serialize_map(None)?;
+        if let Some(name) = &self.name {
+            suite.serialize_entry("name", name)?;
+        }
+        if !self.comments.is_empty() {
+            suite.serialize_entry("comments", &self.comments)?;
+        }
+        if !self.tags.is_empty() {
+            suite.serialize_entry("tags", &self.tags)?;
+        }
+        if let Some(display_table) = &self.display_table {
+            suite.serialize_entry("display_table", display_table)?;
+        }
+        suite.serialize_entry("table", &self.table)?;
+        suite.serialize_entry("mode", &self.mode)?;
+        if !self.translation_modes.is_empty() {
+            suite.serialize_entry("translation_modes", &self.translation_modes)?;
+        }
+        if !self.variables.is_empty() {
+            suite.serialize_entry("variables", &self.variables)?;
+        }
+        if let Some(source) = &self.source {
+            suite.serialize_entry("source", source)?;
+        }
+        let tests: Vec<_> = self
+            .tests
+            .iter()
+            .map(|test| ModeTest {
+                test,
+                expected_key: self.mode.expected_key(),
+            })
+            .collect();
+        suite.serialize_entry("tests", &tests)?;
+        suite.end()
+    }
+}
+
+impl TestSuite {
+    /// The translation modes `test` of this suite is run with, those of
+    /// the suite and its own.
+    pub fn test_modes(&self, test: &Test) -> BTreeSet<Mode> {
+        self.translation_modes.union(&test.mode).cloned().collect()
+    }
+
+    /// Whether `test` of this suite has `tag`, by itself or through the
+    /// suite.
+    pub fn test_has_tag(&self, test: &Test, tag: &str) -> bool {
+        self.tags.contains(tag) || test.tags.contains(tag)
+    }
+}
+
+/// A test serialized with its expected output under `expected_key`
+struct ModeTest<'a> {
+    test: &'a Test,
+    expected_key: &'static str,
+}
+
+impl Serialize for ModeTest<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if self.expected_key == "expected" {
+            return self.test.serialize(serializer);
+        }
+        let serde_yaml::Value::Mapping(fields) =
+            serde_yaml::to_value(self.test).map_err(S::Error::custom)?
+        else {
+            return Err(S::Error::custom("a test is not serialized as a mapping"));
+        };
+        let mut test = serializer.serialize_map(Some(fields.len()))?;
+        for (key, value) in &fields {
+            match key.as_str() {
+                Some("expected") => test.serialize_entry(self.expected_key, value)?,
+                _ => test.serialize_entry(key, value)?,
+            }
+        }
+        test.end()
+    }
+}
+
+/// A translation mode passed to liblouis
+#[derive(
+    Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum Mode {
+    NoContractions,
+    CompbrlAtCursor,
+    #[serde(rename = "dotsIO")]
+    DotsIo,
+    CompbrlLeftCursor,
+    UcBrl,
+    NoUndefined,
+    PartialTrans,
+}
+
+/// Whether a test is expected to fail: a boolean, a reason (implying
+/// true) or a value per direction, such as `{forward: true, backward:
+/// false}`. A value per direction is written out for both directions.
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Xfail {
+    Scalar(bool),
+    Reason(String),
+    Map {
+        #[serde(default)]
+        forward: DirectionXfail,
+        #[serde(default)]
+        backward: DirectionXfail,
+    },
+}
+
+/// Whether a test is expected to fail in one direction: a boolean or a
+/// reason (implying true)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum DirectionXfail {
+    Scalar(bool),
+    Reason(String),
+}
+
+impl Xfail {
+    pub fn is_false(&self) -> bool {
+        match self {
+            Self::Scalar(xfail) => !(*xfail),
+            Self::Reason(..) => false,
+            Self::Map { forward, backward } => forward.is_false() && backward.is_false(),
+        }
+    }
+
+    #[cfg(feature = "run-tests")]
+    pub fn fails_forward(&self) -> bool {
+        match self {
+            Self::Scalar(xfail) => *xfail,
+            Self::Reason(..) => true,
+            Self::Map { forward, .. } => !forward.is_false(),
+        }
+    }
+
+    #[cfg(feature = "run-tests")]
+    pub fn fails_backward(&self) -> bool {
+        match self {
+            Self::Scalar(xfail) => *xfail,
+            Self::Reason(..) => true,
+            Self::Map { backward, .. } => !backward.is_false(),
+        }
+    }
+}
+
+impl Default for Xfail {
+    fn default() -> Self {
+        Xfail::Scalar(false)
+    }
+}
+
+impl DirectionXfail {
+    pub fn is_false(&self) -> bool {
+        matches!(self, Self::Scalar(false))
+    }
+}
+
+impl Default for DirectionXfail {
+    fn default() -> Self {
+        DirectionXfail::Scalar(false)
+    }
+}
+
+/// A single test
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Test {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// An identifier of the test that stays the same when the file is
+    /// migrated again or its tests are reordered
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<String>,
+    /// Labels to select the test by, in addition to those of its suite
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub tags: BTreeSet<String>,
+    pub input: String,
+    #[serde(alias = "dots", alias = "hyphenated")]
+    pub expected: String,
+    /// What the backward translation of `expected` is expected to give in
+    /// a `bothDirections` suite, if it is not `input`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expected_backward: Option<String>,
+    #[serde(default, skip_serializing_if = "Xfail::is_false")]
+    pub xfail: Xfail,
+    // FIXME: add support for typeform:
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub input_pos: Vec<u16>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub output_pos: Vec<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor_pos: Option<u16>,
+    /// The expected position of the cursor in the braille output
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub brl_cursor_pos: Option<u16>,
+    /// Emphasis classes applied to the input, by class name. Each value
+    /// marks the emphasized characters of the input.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub input_class: BTreeMap<String, String>,
+    /// Emphasis classes expected in the output, in the same form as
+    /// `input_class`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub output_class: BTreeMap<String, String>,
+    /// Options of the legacy test that the converter does not know about,
+    /// kept verbatim
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    #[schemars(with = "BTreeMap<String, serde_json::Value>")]
+    pub extra: BTreeMap<String, serde_yaml::Value>,
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub mode: BTreeSet<Mode>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_length: Option<u16>,
+    /// Where the test starts in the legacy file
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<SourcePosition>,
+}
+
+/// A position in the legacy file a suite or test was converted from.
+/// Lines and columns count from 1, columns in characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SourcePosition {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// The document preceding the test suites in a normalized file, recording
+/// where the file came from and the tables its suites share.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Meta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub meta: Option<Provenance>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suite_defaults: Option<SuiteDefaults>,
+}
+
+/// The tables used by every suite that does not name its own. A suite
+/// overrides the default display table with `display_table: null` if it
+/// has none.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SuiteDefaults {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_table: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table: Option<Table>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Provenance {
+    /// The legacy file the tests were converted from
+    pub source: PathBuf,
+    /// The SHA-256 of the legacy file, hex encoded
+    pub sha256: String,
+    /// Name and version of the tool that did the conversion
+    pub tool: String,
+    /// When the conversion was done, in RFC 3339 format
+    pub timestamp: String,
+    /// The encoding the legacy file was transcoded from, if it was not UTF-8
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub transcoded_from: Option<String>,
+}
+
+impl Meta {
+    /// The provenance of tests converted from the legacy file `source`,
+    /// whose bytes have the hex encoded `sha256` and which was transcoded
+    /// from the encoding `transcoded_from`, see
+    /// [`read_source_hashed`](crate::parse::read_source_hashed).
+    pub fn new(source: &Path, sha256: String, transcoded_from: Option<&str>) -> Self {
+        Meta {
+            meta: Some(Provenance {
+                source: source.to_path_buf(),
+                sha256,
+                tool: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+                timestamp: humantime::format_rfc3339_seconds(SystemTime::now()).to_string(),
+                transcoded_from: transcoded_from.map(String::from),
+            }),
+            suite_defaults: None,
+        }
+    }
+}
+
+/// The most common of `values`, the first one on a tie.
+fn most_common(values: Vec<serde_yaml::Value>) -> Option<serde_yaml::Value> {
+    let mut counts: Vec<(serde_yaml::Value, usize)> = Vec::new();
+    for value in values {
+        match counts.iter_mut().find(|(seen, _)| *seen == value) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((value, 1)),
+        }
+    }
+    let max = counts.iter().map(|(_, count)| *count).max()?;
+    counts
+        .into_iter()
+        .find(|(_, count)| *count == max)
+        .map(|(value, _)| value)
+}
+
+/// Factor the most common table and display table of `test_suites` out
+/// into defaults. Returns the defaults and the suites as a yaml value in
+/// which only the tables that differ from the defaults are given.
+pub fn factor_defaults(test_suites: &[TestSuite]) -> Result<(SuiteDefaults, serde_yaml::Value)> {
+    let mut suites = serde_yaml::to_value(test_suites)?;
+    let mappings: Vec<&mut serde_yaml::Mapping> = suites
+        .as_sequence_mut()
+        .into_iter()
+        .flatten()
+        .filter_map(serde_yaml::Value::as_mapping_mut)
+        .collect();
+    let table = most_common(
+        mappings
+            .iter()
+            .filter_map(|suite| suite.get("table").cloned())
+            .collect(),
+    );
+    let display_table = most_common(
+        mappings
+            .iter()
+            .filter_map(|suite| suite.get("display_table").cloned())
+            .collect(),
+    );
+    for suite in mappings {
+        if table.is_some() && suite.get("table") == table.as_ref() {
+            suite.shift_remove("table");
+        }
+        match suite.get("display_table") {
+            Some(own) if Some(own) == display_table.as_ref() => {
+                suite.shift_remove("display_table");
+            }
+            Some(_) => (),
+            None if display_table.is_some() => {
+                // keep the override in front of the tests
+                let mut with_override = serde_yaml::Mapping::new();
+                with_override.insert("display_table".into(), serde_yaml::Value::Null);
+                with_override.extend(std::mem::take(suite));
+                *suite = with_override;
+            }
+            None => (),
+        }
+    }
+    let defaults = SuiteDefaults {
+        display_table: display_table.map(serde_yaml::from_value).transpose()?,
+        table: table.map(serde_yaml::from_value).transpose()?,
+    };
+    Ok((defaults, suites))
+}
+
+/// Fill in the tables that the suites in `suites`, a yaml sequence, leave
+/// out from `defaults`.
+fn apply_suite_defaults(suites: &mut serde_yaml::Value, defaults: &SuiteDefaults) -> Result<()> {
+    let table = defaults
+        .table
+        .as_ref()
+        .map(serde_yaml::to_value)
+        .transpose()?;
+    let display_table = defaults
+        .display_table
+        .as_ref()
+        .map(serde_yaml::to_value)
+        .transpose()?;
+    let mappings = suites
+        .as_sequence_mut()
+        .into_iter()
+        .flatten()
+        .filter_map(serde_yaml::Value::as_mapping_mut);
+    for suite in mappings {
+        if let (None, Some(table)) = (suite.get("table"), &table) {
+            suite.insert("table".into(), table.clone());
+        }
+        match (suite.get("display_table"), &display_table) {
+            (Some(serde_yaml::Value::Null), _) => {
+                suite.shift_remove("display_table");
+            }
+            (None, Some(display_table)) => {
+                suite.insert("display_table".into(), display_table.clone());
+            }
+            _ => (),
+        }
+    }
+    Ok(())
+}
+
+/// Read the suites from `source`, the contents of the normalized file at
+/// `path`, which is json if its extension says so and yaml otherwise.
+pub fn from_source(path: &Path, source: &str) -> Result<Vec<TestSuite>> {
+    if path.extension().is_some_and(|ext| ext == "json") {
+        // unlike serde_yaml, serde_json does not say where in the document
+        // an error occurred
+        let deserializer = &mut serde_json::Deserializer::from_str(source);
+        serde_path_to_error::deserialize(deserializer).map_err(|e| {
+            let path = e.path().to_string();
+            anyhow::Error::new(e.into_inner()).context(format!("at {}", path))
+        })
+    } else {
+        // the suites may be preceded by a document with provenance metadata
+        // and the tables the suites share
+        let mut documents: Vec<_> = serde_yaml::Deserializer::from_str(source).collect();
+        let meta = match documents.len() {
+            1 => Meta::default(),
+            2 => Meta::deserialize(documents.remove(0))?,
+            n => bail!(
+                "expected the test suites and at most one meta document, found {} documents",
+                n
+            ),
+        };
+        let mut suites = serde_yaml::Value::deserialize(documents.remove(0))?;
+        if let Some(defaults) = &meta.suite_defaults {
+            apply_suite_defaults(&mut suites, defaults)?;
+        }
+        Ok(Vec::<TestSuite>::deserialize(suites)?)
+    }
+}
@@ -0,0 +1,1216 @@
+//! Parsing of the legacy test files.
+//!
+//! The legacy files are read event by event with a [`YamlCursor`], see
+//! [`crate::yaml_events`] for why, unless they are regular enough to be
+//! deserialized directly. Things that are odd but do not stop the
+//! conversion are returned as [`Warning`]s for the caller to report.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Result};
+use clap::ValueEnum;
+use serde::Serialize;
+
+use crate::{
+    normalized::{self, DirectionXfail, Mode, Table, Test, TestMode, TestSuite, Xfail},
+    transcode::{self, Mapping},
+    visit::{SuiteBuilder, TestFileVisitor},
+    yaml_events::{Event, ScalarStyle, YamlCursor},
+};
+
+mod regular;
+
+/// Something about a legacy file that did not stop its conversion
+#[derive(Debug, Clone, PartialEq)]
+pub struct Warning {
+    /// A short identifier of the kind of warning, e.g. `unknown-key`
+    pub code: &'static str,
+    pub message: String,
+    /// The input that did not make it into the output, if any
+    pub loss: Option<Loss>,
+}
+
+/// A value of the legacy file that the conversion left out
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Loss {
+    pub key: String,
+    pub value: serde_yaml::Value,
+    /// The path to the value in the legacy file, e.g. `tests[3][2].foo`
+    pub location: String,
+}
+
+impl Loss {
+    /// The value of `key` that starts with `event`, read from `iter`.
+    fn read(iter: &mut YamlCursor, key: &str, event: Event) -> Result<Self> {
+        let location = iter.path();
+        Ok(Loss {
+            key: key.to_string(),
+            value: read_value(iter, event)?,
+            location,
+        })
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum UnknownKeys {
+    /// Fail the conversion
+    #[default]
+    Error,
+    /// Drop the option with a warning
+    Warn,
+    /// Keep the option verbatim in the `extra` map of the test
+    Preserve,
+}
+
+/// The test modes in which a test may be given as just its input by default
+pub const DEFAULT_IDENTITY_TESTS: &[TestMode] = &[TestMode::Display];
+
+/// Options that control how lenient the legacy parser is
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    /// What to do with unknown test options
+    pub unknown_keys: UnknownKeys,
+    /// Test modes in which a test without expected output expects its input
+    pub identity_tests: Vec<TestMode>,
+    /// Reject historical variations of the format instead of accepting them
+    pub strict: bool,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            unknown_keys: UnknownKeys::default(),
+            identity_tests: DEFAULT_IDENTITY_TESTS.to_vec(),
+            strict: false,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Accept `variation`, a historical deviation from the canonical
+    /// format, unless in strict mode.
+    fn accept(&self, variation: impl std::fmt::Display) -> Result<()> {
+        if self.strict {
+            bail!("{} is not allowed with --strict", variation);
+        }
+        Ok(())
+    }
+}
+
+/// Top-level keys used by older test files and the keys they stand for
+const KEY_ALIASES: &[(&str, &str)] = &[("tables", "table"), ("options", "flags")];
+
+/// The canonical spelling of `key` if it is a deprecated alias.
+pub fn key_alias(key: &str) -> Option<&'static str> {
+    KEY_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == key)
+        .map(|(_, canonical)| *canonical)
+}
+
+/// Read the remainder of a mapping of scalars to scalars, i.e. everything
+/// after the MappingStart.
+fn read_string_map(iter: &mut YamlCursor) -> Result<BTreeMap<String, String>> {
+    let mut map = BTreeMap::new();
+    while let Some(event) = iter.next_event()? {
+        match event {
+            Event::Scalar { value, .. } => {
+                map.insert(value, iter.read_scalar()?);
+            }
+            Event::MappingEnd => {
+                break;
+            }
+            _ => bail!("Expected Scalar or MappingEnd, got {:?}", event),
+        };
+    }
+    Ok(map)
+}
+
+fn read_table_metadata(iter: &mut YamlCursor) -> Result<Table> {
+    Ok(Table::MetaData(read_string_map(iter)?))
+}
+
+fn read_table_files(iter: &mut YamlCursor) -> Result<Table> {
+    let mut files = Vec::new();
+    while let Some(event) = iter.next_event()? {
+        match event {
+            Event::Scalar { value, .. } => {
+                files.push(value.into());
+            }
+            Event::SequenceEnd => {
+                break;
+            }
+            _ => bail!("Expected Scalar or SequenceEnd, got {:?}", event),
+        };
+    }
+    Ok(Table::List(files))
+}
+
+pub fn parse_table(iter: &mut YamlCursor) -> Result<Table> {
+    match iter.next_event()? {
+        Some(event) => match event {
+            Event::MappingStart => read_table_metadata(iter),
+            Event::Scalar { value, style, .. } => match style {
+                ScalarStyle::Plain | ScalarStyle::SingleQuoted | ScalarStyle::DoubleQuoted => {
+                    Ok(Table::Single(value.into()))
+                }
+                ScalarStyle::Literal => Ok(Table::Inline(value)),
+                other => bail!("Scalar of style {:?} not supported", other),
+            },
+            Event::SequenceStart => read_table_files(iter),
+            other => bail!(
+                "Expected Scalar, MappingStart or SequenceStart, got {:?}",
+                other
+            ),
+        },
+        other => bail!("Invalid event {:?}", other),
+    }
+}
+
+/// The `flags` of a suite
+#[derive(Debug, Default, Clone)]
+pub struct Flags {
+    pub test_mode: TestMode,
+    /// The translation modes every test of the suite is run with, in
+    /// addition to its own
+    pub modes: BTreeSet<Mode>,
+}
+
+fn read_test_mode(value: &str) -> Result<TestMode> {
+    let test_mode = match value {
+        "forward" => TestMode::Forward,
+        "backward" => TestMode::Backward,
+        "bothDirections" => TestMode::BothDirections,
+        "display" => TestMode::Display,
+        "hyphenate" => TestMode::Hyphenate,
+        "hyphenateBraille" => TestMode::HyphenateBraille,
+        _ => bail!(
+            "Testmode {:?} not supported, expected one of `forward`, `backward`, \
+             `bothDirections`, `display`, `hyphenate` or `hyphenateBraille`",
+            value
+        ),
+    };
+    Ok(test_mode)
+}
+
+/// Parse the `flags` of a suite: its `testmode` and the translation
+/// `mode` of all its tests. Empty flags, `{}` or null, stand for the
+/// default test mode.
+pub fn parse_flags(iter: &mut YamlCursor) -> Result<Flags> {
+    let mut flags = Flags::default();
+    match iter.next_event()? {
+        Some(Event::MappingStart) => (),
+        Some(event) if event.is_null() => return Ok(flags),
+        other => bail!("Expected MappingStart, got {:?}", other),
+    }
+    while let Some(event) = iter.next_event()? {
+        match event {
+            Event::MappingEnd => break,
+            Event::Scalar { ref value, .. } if value == "testmode" => {
+                let value = match iter.next_event()? {
+                    Some(Event::Scalar { value, .. }) => value,
+                    _ => bail!("Expected Scalar"),
+                };
+                flags.test_mode = read_test_mode(&value)?;
+            }
+            Event::Scalar { ref value, .. } if value == "mode" => flags.modes = parse_modes(iter)?,
+            _ => bail!("Expected `testmode` or `mode` in flags"),
+        }
+    }
+    Ok(flags)
+}
+
+/// The boolean an xfail value stands for, if it is spelled as one of the
+/// YAML 1.1 booleans or as `1` or `0`.
+pub fn xfail_boolean(value: &str) -> Option<bool> {
+    match value {
+        "true" | "True" | "TRUE" | "yes" | "Yes" | "YES" | "y" | "Y" | "on" | "On" | "ON" | "1" => {
+            Some(true)
+        }
+        "false" | "False" | "FALSE" | "no" | "No" | "NO" | "n" | "N" | "off" | "Off" | "OFF"
+        | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Whether the xfail reason `value` starts with a boolean spelling, e.g.
+/// "no reason", which makes it unclear whether the test is meant to fail.
+pub fn is_ambiguous_xfail_reason(value: &str) -> bool {
+    xfail_boolean(value).is_none()
+        && value
+            .split_whitespace()
+            .next()
+            .map(|word| word.trim_end_matches(|c: char| c.is_ascii_punctuation()))
+            .and_then(xfail_boolean)
+            .is_some()
+}
+
+fn read_direction_xfail(value: String, options: &ParseOptions) -> Result<DirectionXfail> {
+    Ok(match xfail_boolean(&value) {
+        Some(xfail) => {
+            if value != "true" && value != "false" {
+                options.accept(format!("xfail value `{}`", value))?;
+            }
+            DirectionXfail::Scalar(xfail)
+        }
+        None => DirectionXfail::Reason(value),
+    })
+}
+
+fn read_xfail_value(value: String, options: &ParseOptions) -> Result<Xfail> {
+    Ok(match read_direction_xfail(value, options)? {
+        DirectionXfail::Scalar(xfail) => Xfail::Scalar(xfail),
+        DirectionXfail::Reason(reason) => Xfail::Reason(reason),
+    })
+}
+
+fn parse_xfail_value(iter: &mut YamlCursor, options: &ParseOptions) -> Result<Xfail> {
+    let xfail = match iter.next_event()? {
+        Some(Event::Scalar { value, .. }) => read_xfail_value(value, options)?,
+        Some(Event::MappingStart) => {
+            let mut forward = DirectionXfail::default();
+            let mut backward = DirectionXfail::default();
+            while let Some(event) = iter.next_event()? {
+                match event {
+                    Event::Scalar { value, .. } => {
+                        match value.as_str() {
+                            "forward" => {
+                                forward = read_direction_xfail(iter.read_scalar()?, options)?
+                            }
+                            "backward" => {
+                                backward = read_direction_xfail(iter.read_scalar()?, options)?
+                            }
+                            other => bail!("Expected 'forward' or 'backward', got {:?}", other),
+                        };
+                    }
+                    Event::MappingEnd => {
+                        break;
+                    }
+                    _ => {
+                        bail!("Expected Scalar or MappingEnd, got {:?}", event);
+                    }
+                }
+            }
+            Xfail::Map { forward, backward }
+        }
+        other => bail!("Expected scalar xfail value, got {:?}", other),
+    };
+    Ok(xfail)
+}
+
+fn read_mode(value: &str) -> Result<Mode> {
+    let mode = match value {
+        "noContractions" => Mode::NoContractions,
+        "compbrlAtCursor" => Mode::CompbrlAtCursor,
+        "dotsIO" => Mode::DotsIo,
+        "compbrlLeftCursor" => Mode::CompbrlLeftCursor,
+        "ucBrl" => Mode::UcBrl,
+        "noUndefined" => Mode::NoUndefined,
+        "partialTrans" => Mode::PartialTrans,
+        _ => bail!(
+            "Mode {:?} not supported, expected one of `noContractions`, `compbrlAtCursor`, \
+             `dotsIO`, `compbrlLeftCursor`, `ucBrl`, `noUndefined` or `partialTrans`",
+            value
+        ),
+    };
+    Ok(mode)
+}
+
+/// Parse a character position given in the option `key`.
+fn parse_position(value: &str, key: &str) -> Result<u16> {
+    value
+        .parse()
+        .map_err(|_| anyhow!("Invalid position {:?} in {}", value, key))
+}
+
+/// Parse an `inputPos` or `outputPos` option, a sequence of character
+/// positions.
+fn parse_positions(iter: &mut YamlCursor, key: &str) -> Result<Vec<u16>> {
+    iter.read_sequence_start()?;
+    let mut positions = Vec::new();
+    while let Some(event) = iter.next_event()? {
+        match event {
+            Event::Scalar { value, .. } => positions.push(parse_position(&value, key)?),
+            Event::SequenceEnd => break,
+            _ => bail!(
+                "Expected Scalar or SequenceEnd inside {}, got {:?}",
+                key,
+                event
+            ),
+        }
+    }
+    Ok(positions)
+}
+
+/// Parse a `mode` option, given either as a single scalar or as a
+/// (block or flow) sequence of scalars.
+pub fn parse_modes(iter: &mut YamlCursor) -> Result<BTreeSet<Mode>> {
+    let mut modes = BTreeSet::new();
+    match iter.next_event()? {
+        Some(Event::Scalar { value, .. }) => {
+            modes.insert(read_mode(&value)?);
+        }
+        Some(Event::SequenceStart) => {
+            while let Some(event) = iter.next_event()? {
+                match event {
+                    Event::Scalar { value, .. } => {
+                        modes.insert(read_mode(&value)?);
+                    }
+                    Event::SequenceEnd => {
+                        break;
+                    }
+                    _ => bail!("Expected Scalar or SequenceEnd, got {:?}", event),
+                }
+            }
+        }
+        other => bail!("Expected Scalar or SequenceStart, got {:?}", other),
+    }
+    Ok(modes)
+}
+
+/// Read the node starting with `event` into a generic yaml value. Plain
+/// scalars are resolved to booleans, numbers and null where possible.
+fn read_value(iter: &mut YamlCursor, event: Event) -> Result<serde_yaml::Value> {
+    let value = match event {
+        Event::Scalar {
+            value,
+            style: ScalarStyle::Plain,
+        } => match serde_yaml::from_str(&value) {
+            Ok(
+                resolved @ (serde_yaml::Value::Null
+                | serde_yaml::Value::Bool(..)
+                | serde_yaml::Value::Number(..)),
+            ) => resolved,
+            _ => serde_yaml::Value::String(value),
+        },
+        Event::Scalar { value, .. } => serde_yaml::Value::String(value),
+        Event::SequenceStart => {
+            let mut seq = Vec::new();
+            while let Some(event) = iter.next_event()? {
+                match event {
+                    Event::SequenceEnd => break,
+                    event => seq.push(read_value(iter, event)?),
+                }
+            }
+            serde_yaml::Value::Sequence(seq)
+        }
+        Event::MappingStart => {
+            let mut mapping = serde_yaml::Mapping::new();
+            while let Some(event) = iter.next_event()? {
+                match event {
+                    Event::MappingEnd => break,
+                    event => {
+                        let key = read_value(iter, event)?;
+                        let next = iter
+                            .next_event()?
+                            .ok_or_else(|| anyhow!("Expected value"))?;
+                        mapping.insert(key, read_value(iter, next)?);
+                    }
+                }
+            }
+            serde_yaml::Value::Mapping(mapping)
+        }
+        other => bail!(
+            "Expected Scalar, SequenceStart or MappingStart, got {:?}",
+            other
+        ),
+    };
+    Ok(value)
+}
+
+/// Parse a `variables` mapping, i.e. everything after the MappingStart,
+/// into its values by name.
+fn parse_variables(iter: &mut YamlCursor) -> Result<BTreeMap<String, serde_yaml::Value>> {
+    let mut variables = BTreeMap::new();
+    while let Some(event) = iter.next_event()? {
+        match event {
+            Event::Scalar { value, .. } => {
+                let next = iter
+                    .next_event()?
+                    .ok_or_else(|| anyhow!("Expected value of variable {:?}", value))?;
+                variables.insert(value, read_value(iter, next)?);
+            }
+            Event::MappingEnd => {
+                break;
+            }
+            _ => bail!(
+                "Expected Scalar or MappingEnd inside variables, got {:?}",
+                event
+            ),
+        }
+    }
+    Ok(variables)
+}
+
+/// Parse the test option `key` into `test`.
+fn parse_test_option(
+    iter: &mut YamlCursor,
+    key: String,
+    test: &mut Test,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<()> {
+    match key.as_str() {
+        "name" => test.name = Some(iter.read_scalar()?),
+        "description" => {
+            options.accept("`description` instead of `name`")?;
+            test.name = Some(iter.read_scalar()?);
+        }
+        "xfail" => test.xfail = parse_xfail_value(iter, options)?,
+        "mode" => test.mode = parse_modes(iter)?,
+        "cursorPos" => test.cursor_pos = Some(parse_position(&iter.read_scalar()?, &key)?),
+        "brlCursorPos" => {
+            test.brl_cursor_pos = Some(parse_position(&iter.read_scalar()?, &key)?);
+        }
+        "inputPos" => test.input_pos = parse_positions(iter, &key)?,
+        "outputPos" => test.output_pos = parse_positions(iter, &key)?,
+        "inputClass" => {
+            iter.read_mapping_start()?;
+            test.input_class = read_string_map(iter)?;
+        }
+        "outputClass" => {
+            iter.read_mapping_start()?;
+            test.output_class = read_string_map(iter)?;
+        }
+        _ => match options.unknown_keys {
+            UnknownKeys::Error => bail!("Unknown test option {:?}", key),
+            UnknownKeys::Warn => {
+                let next = iter
+                    .next_event()?
+                    .ok_or_else(|| anyhow!("Expected value of {:?}", key))?;
+                warnings.push(Warning {
+                    code: "unknown-test-option",
+                    message: format!("ignoring unknown test option {:?}", key),
+                    loss: Some(Loss::read(iter, &key, next)?),
+                });
+            }
+            UnknownKeys::Preserve => {
+                let next = iter
+                    .next_event()?
+                    .ok_or_else(|| anyhow!("Expected value of {:?}", key))?;
+                test.extra.insert(key, read_value(iter, next)?);
+            }
+        },
+    }
+    Ok(())
+}
+
+/// Parse the test options of a `defaults` block, i.e. everything after
+/// the MappingStart, into a test without input and expected output.
+fn parse_defaults(
+    iter: &mut YamlCursor,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Test> {
+    let mut defaults = Test::default();
+    while let Some(event) = iter.next_event()? {
+        match event {
+            Event::Scalar { value, .. } if value == "name" || value == "description" => {
+                bail!("`{}` cannot be given in `defaults`", value);
+            }
+            Event::Scalar { value, .. } => {
+                parse_test_option(iter, value, &mut defaults, options, warnings)?;
+            }
+            Event::MappingEnd => {
+                break;
+            }
+            _ => bail!(
+                "Expected Scalar or MappingEnd inside defaults, got {:?}",
+                event
+            ),
+        }
+    }
+    Ok(defaults)
+}
+
+/// Parse a test given as a sequence of input, expected output and
+/// optionally a mapping of options, i.e. everything after the
+/// SequenceStart. With `identity` the expected output may be left out, it
+/// is then the same as the input. The test starts out with the options of
+/// `defaults`.
+fn parse_test(
+    iter: &mut YamlCursor,
+    identity: bool,
+    defaults: &Test,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Test> {
+    let input = iter.read_scalar()?;
+    let (expected, next) = match iter.next_event()? {
+        Some(Event::Scalar { value, .. }) => (value, iter.next_event()?),
+        next if identity => {
+            options.accept(format!("test {:?} without expected output", input))?;
+            (input.clone(), next)
+        }
+        _ => bail!("Test {:?} without expected output", input),
+    };
+    let mut test = Test {
+        input,
+        expected,
+        ..defaults.clone()
+    };
+    match next {
+        Some(Event::SequenceEnd) => Ok(test),
+        Some(Event::MappingStart) => {
+            while let Some(event) = iter.next_event()? {
+                match event {
+                    Event::Scalar { value, .. } => {
+                        parse_test_option(iter, value, &mut test, options, warnings)?;
+                    }
+                    Event::MappingEnd => {
+                        break;
+                    }
+                    _ => {
+                        bail!("Expected Scalar or MappingEnd inside test, got {:?}", event);
+                    }
+                }
+            }
+            iter.read_sequence_end()?;
+            Ok(test)
+        }
+        _ => bail!("Expected SequenceEnd or MappingStart"),
+    }
+}
+
+/// Parse a test given as a mapping with `input` and `expected` keys next
+/// to the options, i.e. everything after the MappingStart. With `identity`
+/// the expected output may be left out, it is then the same as the input.
+/// The test starts out with the options of `defaults`.
+fn parse_test_mapping(
+    iter: &mut YamlCursor,
+    identity: bool,
+    defaults: &Test,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+) -> Result<Test> {
+    options.accept("a test given as a mapping")?;
+    let mut test = defaults.clone();
+    let mut input = None;
+    let mut expected = None;
+    while let Some(event) = iter.next_event()? {
+        match event {
+            Event::Scalar { ref value, .. } if value == "input" => {
+                input = Some(iter.read_scalar()?);
+            }
+            Event::Scalar { ref value, .. } if value == "expected" => {
+                expected = Some(iter.read_scalar()?);
+            }
+            Event::Scalar { value, .. } => {
+                parse_test_option(iter, value, &mut test, options, warnings)?;
+            }
+            Event::MappingEnd => {
+                break;
+            }
+            _ => bail!("Expected Scalar or MappingEnd inside test, got {:?}", event),
+        }
+    }
+    test.input = input.ok_or_else(|| anyhow!("Test without input"))?;
+    test.expected = match expected {
+        Some(expected) => expected,
+        None if identity => test.input.clone(),
+        None => bail!("Test {:?} without expected output", test.input),
+    };
+    Ok(test)
+}
+
+/// Whether `hyphenated` is a hyphenation of `input`: the input with
+/// hyphens added or a mask of `0` and `1` with one digit per character.
+fn is_hyphenation(input: &str, hyphenated: &str) -> bool {
+    let is_mask = hyphenated.chars().all(|c| c == '0' || c == '1')
+        && hyphenated.chars().count() == input.chars().count();
+    is_mask || input.replace('-', "") == hyphenated.replace('-', "")
+}
+
+/// Why `test` does not fit the test `mode`, if it does not: a display
+/// test maps each character to one cell of dots and a hyphenation test
+/// only marks where its input may be hyphenated.
+pub(crate) fn check_test_mode(test: &Test, mode: &TestMode) -> Option<String> {
+    match mode {
+        TestMode::Display if test.input.chars().count() != test.expected.chars().count() => {
+            Some(format!(
+                "display test {:?} does not map each character to one cell of dots {:?}",
+                test.input, test.expected
+            ))
+        }
+        TestMode::Hyphenate | TestMode::HyphenateBraille
+            if !is_hyphenation(&test.input, &test.expected) =>
+        {
+            Some(format!(
+                "hyphenated {:?} is not a hyphenation of {:?}",
+                test.expected, test.input
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// Parse a `tests` block, passing each test to `handle_test` as soon as it
+/// has been parsed.
+fn parse_tests(
+    iter: &mut YamlCursor,
+    mode: &TestMode,
+    defaults: &Test,
+    options: &ParseOptions,
+    warnings: &mut Vec<Warning>,
+    mut handle_test: impl FnMut(Test) -> Result<()>,
+) -> Result<()> {
+    let identity = options.identity_tests.contains(mode);
+
+    match iter.next_event()? {
+        Some(Event::SequenceStart) => (),
+        Some(event) if event.is_null() => return Ok(()),
+        other => bail!("Expected SequenceStart, got {:?}", other),
+    }
+    while let Some(event) = iter.next_event()? {
+        let test = match event {
+            Event::SequenceEnd => break,
+            Event::SequenceStart => parse_test(iter, identity, defaults, options, warnings)?,
+            Event::MappingStart => parse_test_mapping(iter, identity, defaults, options, warnings)?,
+            _ => bail!("Expected SequenceStart or MappingStart, got {:?}", event),
+        };
+        if let Some(message) = check_test_mode(&test, mode) {
+            warnings.push(Warning {
+                code: "mode-mismatch",
+                message,
+                loss: None,
+            });
+        }
+        handle_test(test)?;
+    }
+    Ok(())
+}
+
+/// Parse the legacy file at `path` into its suites and the warnings about
+/// it.
+pub fn parse_file(path: &Path, options: &ParseOptions) -> Result<(Vec<TestSuite>, Vec<Warning>)> {
+    let mut test_suites = Vec::new();
+    let warnings = parse_file_with(path, options, |suite| {
+        test_suites.push(suite);
+        Ok(())
+    })?;
+    Ok((test_suites, warnings))
+}
+
+/// Whether `source` is in the normalized format already, i.e. starts with
+/// the sequence of suites or with the meta document rather than with the
+/// mapping of a legacy file.
+pub fn is_normalized(source: &str) -> bool {
+    let Ok(mut iter) = YamlCursor::new(source) else {
+        return false;
+    };
+    if iter
+        .read_stream_start()
+        .and_then(|_| iter.read_document_start())
+        .is_err()
+    {
+        return false;
+    }
+    match iter.next_event() {
+        Ok(Some(Event::SequenceStart)) => true,
+        Ok(Some(Event::MappingStart)) => {
+            matches!(
+                iter.next_event(),
+                Ok(Some(Event::Scalar { value, .. })) if value == "meta" || value == "suite_defaults"
+            )
+        }
+        _ => false,
+    }
+}
+
+/// Parse the legacy file at `path`, passing each suite to `handle_suite` as
+/// soon as it has been parsed, and return the warnings about it. A file in
+/// the normalized format is read as such, so that converting it again
+/// yields the same output.
+pub fn parse_file_with(
+    path: &Path,
+    options: &ParseOptions,
+    handle_suite: impl FnMut(TestSuite) -> Result<()>,
+) -> Result<Vec<Warning>> {
+    parse_source_with(path, &read_source(path, Mapping::default())?, options, handle_suite)
+}
+
+/// Read the file at `path` as UTF-8, transcoding it if necessary. Whether
+/// the file is mapped into memory rather than read depends on `mapping`,
+/// see [`transcode::read`].
+pub fn read_source(path: &Path, mapping: Mapping) -> Result<transcode::Source> {
+    let (source, transcoded_from) = transcode::read(path, mapping)?;
+    log_transcoding(path, transcoded_from);
+    Ok(source)
+}
+
+/// Read the file at `path` like [`read_source`], also returning the
+/// encoding it was transcoded from and its SHA-256, for its provenance.
+pub fn read_source_hashed(
+    path: &Path,
+    mapping: Mapping,
+) -> Result<(transcode::Source, Option<&'static str>, String)> {
+    let (source, transcoded_from, sha256) = transcode::read_hashed(path, mapping)?;
+    log_transcoding(path, transcoded_from);
+    Ok((source, transcoded_from, sha256))
+}
+
+fn log_transcoding(path: &Path, transcoded_from: Option<&str>) {
+    if let Some(encoding) = transcoded_from {
+        log::info!("{}: transcoded from {}", path.display(), encoding);
+    }
+}
+
+/// Parse `source`, read from the legacy file at `path`, into its suites
+/// and the warnings about it. For callers that need the source itself as
+/// well, so that it is read only once.
+pub fn parse_source(
+    path: &Path,
+    source: &str,
+    options: &ParseOptions,
+) -> Result<(Vec<TestSuite>, Vec<Warning>)> {
+    let mut test_suites = Vec::new();
+    let warnings = parse_source_with(path, source, options, |suite| {
+        test_suites.push(suite);
+        Ok(())
+    })?;
+    Ok((test_suites, warnings))
+}
+
+/// Like [`parse_file_with`], with the contents of the file given as
+/// `source`.
+pub fn parse_source_with(
+    path: &Path,
+    source: &str,
+    options: &ParseOptions,
+    mut handle_suite: impl FnMut(TestSuite) -> Result<()>,
+) -> Result<Vec<Warning>> {
+    let mut test_suites = TestSuiteIter::from_source(path, source, options.clone())?;
+    for suite in &mut test_suites {
+        handle_suite(suite?)?;
+    }
+    Ok(test_suites.into_warnings())
+}
+
+/// Parse the test file at `path`, passing what it contains to `visitor`
+/// as it is parsed, and return the warnings about it. Legacy files are
+/// parsed test by test, files read whole, see [`TestSuiteIter`], are
+/// passed to `visitor` suite by suite.
+pub fn visit_file(
+    path: &Path,
+    options: &ParseOptions,
+    visitor: &mut impl TestFileVisitor,
+) -> Result<Vec<Warning>> {
+    visit_source(path, &read_source(path, Mapping::default())?, options, visitor)
+}
+
+/// Like [`visit_file`], with the contents of the file given as `source`.
+pub fn visit_source(
+    path: &Path,
+    source: &str,
+    options: &ParseOptions,
+    visitor: &mut impl TestFileVisitor,
+) -> Result<Vec<Warning>> {
+    if let Some((suites, warnings)) = read_whole(path, source, options)? {
+        for suite in suites {
+            visit_suite(suite, visitor)?;
+        }
+        return Ok(warnings);
+    }
+    let mut stream = LegacyStream::new(YamlCursor::new(source)?)?;
+    let mut warnings = Vec::new();
+    while stream
+        .visit_suite(path, options, &mut warnings, visitor)
+        .map_err(|e| stream.iter.locate(e))?
+    {}
+    Ok(warnings)
+}
+
+/// Pass `suite`, of a file read whole, to `visitor`.
+fn visit_suite(mut suite: TestSuite, visitor: &mut impl TestFileVisitor) -> Result<()> {
+    visitor.on_table(&suite.table)?;
+    visitor.on_flags(&Flags {
+        test_mode: suite.mode.clone(),
+        modes: suite.translation_modes.clone(),
+    })?;
+    let tests = std::mem::take(&mut suite.tests);
+    visitor.on_suite_start(&suite)?;
+    for test in tests {
+        visitor.on_test(test)?;
+    }
+    visitor.on_suite_end()
+}
+
+/// Record that the setting `key` has not been used by a `tests` block yet,
+/// warning about the previous value if that was never used either.
+fn set_unused(
+    unused: &mut BTreeMap<&'static str, Loss>,
+    key: &'static str,
+    loss: Loss,
+    warnings: &mut Vec<Warning>,
+) {
+    if let Some(overridden) = unused.insert(key, loss) {
+        warnings.push(Warning {
+            code: "unused-setting",
+            message: format!("`{}` is redefined before any tests use it", key),
+            loss: Some(overridden),
+        });
+    }
+}
+
+/// A legacy file in the middle of being parsed, with the settings the
+/// next `tests` block binds to.
+///
+/// `display`, `table`, `flags`, `variables` and `defaults` may appear in
+/// any order and be redefined between suites, every `tests` block binds to
+/// the most recent values. The options in `defaults` are copied into each
+/// test, an option given in the test itself replaces the default as a
+/// whole, e.g. its `mode` list is not merged with the default one.
+struct LegacyStream<'a> {
+    iter: YamlCursor<'a>,
+    display_table: Option<PathBuf>,
+    table: Option<Table>,
+    flags: Flags,
+    defaults: Test,
+    variables: BTreeMap<String, serde_yaml::Value>,
+    option_warnings: Vec<Warning>,
+    /// The settings no `tests` block has used yet, by key
+    unused: BTreeMap<&'static str, Loss>,
+}
+
+impl<'a> LegacyStream<'a> {
+    fn new(mut iter: YamlCursor<'a>) -> Result<Self> {
+        let start = iter
+            .read_stream_start()
+            .and_then(|_| iter.read_document_start())
+            .and_then(|_| iter.read_mapping_start());
+        if let Err(e) = start {
+            return Err(iter.locate(e));
+        }
+        Ok(LegacyStream {
+            iter,
+            display_table: None,
+            table: None,
+            flags: Flags::default(),
+            defaults: Test::default(),
+            variables: BTreeMap::new(),
+            option_warnings: Vec::new(),
+            unused: BTreeMap::new(),
+        })
+    }
+
+    /// Parse up to the end of the next `tests` block with tests, passing
+    /// what is parsed to `visitor`, and return whether there was one.
+    fn visit_suite(
+        &mut self,
+        path: &Path,
+        options: &ParseOptions,
+        warnings: &mut Vec<Warning>,
+        visitor: &mut impl TestFileVisitor,
+    ) -> Result<bool> {
+        let LegacyStream {
+            iter,
+            display_table,
+            table,
+            flags,
+            defaults,
+            variables,
+            option_warnings,
+            unused,
+        } = self;
+        while let Some(event) = iter.next_event()? {
+            match event {
+                Event::Scalar { value, .. } => {
+                    let key = match key_alias(&value) {
+                        Some(key) => {
+                            options.accept(format!("`{}` instead of `{}`", value, key))?;
+                            warnings.push(Warning {
+                                code: "deprecated-key",
+                                message: format!(
+                                    "`{}` is deprecated, use `{}` instead",
+                                    value, key
+                                ),
+                                loss: None,
+                            });
+                            key
+                        }
+                        None => value.as_str(),
+                    };
+                    let location = iter.path();
+                    let loss = |value| Loss {
+                        key: key.to_string(),
+                        value,
+                        location: location.clone(),
+                    };
+                    match key {
+                        "display" => {
+                            let display = iter.read_scalar()?;
+                            log::info!("{}: display table {}", path.display(), display);
+                            set_unused(unused, "display", loss(display.clone().into()), warnings);
+                            *display_table = Some(display.into());
+                        }
+                        "table" => {
+                            let new_table = parse_table(iter)?;
+                            log::info!("{}: table {:?}", path.display(), new_table);
+                            set_unused(
+                                unused,
+                                "table",
+                                loss(serde_yaml::to_value(&new_table)?),
+                                warnings,
+                            );
+                            visitor.on_table(&new_table)?;
+                            *table = Some(new_table);
+                        }
+                        "flags" => {
+                            *flags = parse_flags(iter)?;
+                            log::info!("{}: flags {:?}", path.display(), flags);
+                            let mut value = serde_yaml::Mapping::new();
+                            value
+                                .insert("testmode".into(), serde_yaml::to_value(&flags.test_mode)?);
+                            if !flags.modes.is_empty() {
+                                value.insert("mode".into(), serde_yaml::to_value(&flags.modes)?);
+                            }
+                            set_unused(unused, "flags", loss(value.into()), warnings);
+                            visitor.on_flags(flags)?;
+                        }
+                        "variables" => {
+                            iter.read_mapping_start()?;
+                            *variables = parse_variables(iter)?;
+                            log::info!("{}: variables {:?}", path.display(), variables);
+                            set_unused(
+                                unused,
+                                "variables",
+                                loss(serde_yaml::to_value(&variables)?),
+                                warnings,
+                            );
+                        }
+                        "defaults" => {
+                            iter.read_mapping_start()?;
+                            *defaults = parse_defaults(iter, options, option_warnings)?;
+                            log::info!("{}: test defaults {:?}", path.display(), defaults);
+                        }
+                        "tests" => {
+                            let test_suite = TestSuite {
+                                name: None,
+                                comments: Vec::new(),
+                                tags: BTreeSet::new(),
+                                display_table: display_table.clone(),
+                                table: table
+                                    .clone()
+                                    .ok_or_else(|| anyhow!("No table defined for tests"))?,
+                                mode: flags.test_mode.clone(),
+                                translation_modes: flags.modes.clone(),
+                                variables: variables.clone(),
+                                source: None,
+                                tests: Vec::new(),
+                            };
+                            // the suite starts with its first test, so that
+                            // a block without tests starts none
+                            let mut count = 0;
+                            parse_tests(
+                                iter,
+                                &flags.test_mode,
+                                defaults,
+                                options,
+                                option_warnings,
+                                |mut test| {
+                                    // the modes of the suite apply to its tests anyway
+                                    test.mode.retain(|mode| !flags.modes.contains(mode));
+                                    if count == 0 {
+                                        visitor.on_suite_start(&test_suite)?;
+                                    }
+                                    count += 1;
+                                    visitor.on_test(test)
+                                },
+                            )?;
+                            log::info!("{}: parsed suite with {} test(s)", path.display(), count);
+                            unused.clear();
+                            if count > 0 {
+                                visitor.on_suite_end()?;
+                                return Ok(true);
+                            }
+                            warnings.push(Warning {
+                                code: "empty-tests",
+                                message: "skipping a `tests` block without any tests".to_string(),
+                                loss: None,
+                            });
+                        }
+                        other => {
+                            options.accept(format!("unknown key {:?}", other))?;
+                            let next = iter
+                                .next_event()?
+                                .ok_or_else(|| anyhow!("Expected value of {:?}", other))?;
+                            warnings.push(Warning {
+                                code: "unknown-key",
+                                message: format!("ignoring unknown key {:?}", other),
+                                loss: Some(Loss::read(iter, other, next)?),
+                            });
+                        }
+                    }
+                }
+                Event::MappingEnd => {
+                    break;
+                }
+                _ => {
+                    bail!("expected Scalar, got {:?}", event);
+                }
+            }
+        }
+
+        iter.read_document_end()?;
+        iter.read_stream_end()?;
+
+        warnings.append(option_warnings);
+        for (key, loss) in std::mem::take(unused) {
+            warnings.push(Warning {
+                code: "unused-setting",
+                message: format!("`{}` is not followed by any tests", key),
+                loss: Some(loss),
+            });
+        }
+
+        Ok(false)
+    }
+}
+
+/// The suites and warnings of `source`, the contents of the test file at
+/// `path`, if it is read whole rather than suite by suite: a file in the
+/// normalized format, or a legacy file regular enough to be deserialized
+/// directly, see [`regular`].
+fn read_whole(
+    path: &Path,
+    source: &str,
+    options: &ParseOptions,
+) -> Result<Option<(Vec<TestSuite>, Vec<Warning>)>> {
+    if is_normalized(source) {
+        log::info!("{}: already normalized", path.display());
+        return Ok(Some((normalized::from_source(path, source)?, Vec::new())));
+    }
+    match regular::parse(source, options) {
+        Ok((suite, warnings)) => {
+            log::info!("{}: parsed as a regular file", path.display());
+            Ok(Some((vec![suite], warnings)))
+        }
+        Err(e) => {
+            log::info!("{}: parsing event by event, {:#}", path.display(), e);
+            Ok(None)
+        }
+    }
+}
+
+enum Suites<'a> {
+    /// The suites of a file read whole, see [`read_whole`]
+    Whole(std::vec::IntoIter<TestSuite>),
+    Legacy(Box<LegacyStream<'a>>),
+    /// The end of the file or an error was reached
+    Done,
+}
+
+/// The suites of a test file, parsed one at a time as they are asked for.
+///
+/// Legacy files are parsed up to the end of the next suite, so that a
+/// caller that processes one suite after the other, such as a test
+/// runner, need not hold all suites of a big file in memory. Files in the
+/// normalized format are read as such, but whole, and so are legacy files
+/// with a single suite that can be deserialized directly. The warnings about the
+/// file are complete once the iterator is exhausted. After an error the
+/// iterator ends.
+pub struct TestSuiteIter<'a> {
+    path: PathBuf,
+    options: ParseOptions,
+    suites: Suites<'a>,
+    warnings: Vec<Warning>,
+}
+
+impl TestSuiteIter<'static> {
+    /// The suites of the test file read from `reader`, see
+    /// [`TestSuiteIter::with_options`].
+    pub fn new(reader: impl Read) -> Result<Self> {
+        Self::with_options(reader, ParseOptions::default())
+    }
+
+    /// The suites of the test file read from `reader`, which may be in the
+    /// legacy or the normalized yaml format and is transcoded like
+    /// [`read_source`] does. The text is read right away, the suites only
+    /// as they are asked for.
+    pub fn with_options(mut reader: impl Read, options: ParseOptions) -> Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        let (source, _) = transcode::decode(&bytes)?;
+        drop(bytes);
+        let path = PathBuf::new();
+        let (suites, warnings) = match read_whole(&path, &source, &options)? {
+            Some((suites, warnings)) => (Suites::Whole(suites.into_iter()), warnings),
+            None => {
+                let stream = LegacyStream::new(YamlCursor::from_string(source)?)?;
+                (Suites::Legacy(Box::new(stream)), Vec::new())
+            }
+        };
+        Ok(TestSuiteIter {
+            path,
+            options,
+            suites,
+            warnings,
+        })
+    }
+}
+
+impl<'a> TestSuiteIter<'a> {
+    /// The suites of `source`, the contents of the test file at `path`.
+    /// A normalized file is json if its extension says so and yaml
+    /// otherwise.
+    pub fn from_source(path: &Path, source: &'a str, options: ParseOptions) -> Result<Self> {
+        let (suites, warnings) = match read_whole(path, source, &options)? {
+            Some((suites, warnings)) => (Suites::Whole(suites.into_iter()), warnings),
+            None => {
+                let stream = LegacyStream::new(YamlCursor::new(source)?)?;
+                (Suites::Legacy(Box::new(stream)), Vec::new())
+            }
+        };
+        Ok(TestSuiteIter {
+            path: path.to_path_buf(),
+            options,
+            suites,
+            warnings,
+        })
+    }
+
+    /// The warnings about the file found so far.
+    pub fn warnings(&self) -> &[Warning] {
+        &self.warnings
+    }
+
+    /// Consume the iterator and return the warnings about the file, all
+    /// of them if it was exhausted.
+    pub fn into_warnings(self) -> Vec<Warning> {
+        self.warnings
+    }
+}
+
+impl Iterator for TestSuiteIter<'_> {
+    type Item = Result<TestSuite>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let next = match &mut self.suites {
+            Suites::Whole(suites) => return suites.next().map(Ok),
+            Suites::Legacy(stream) => {
+                let mut builder = SuiteBuilder::default();
+                stream
+                    .visit_suite(&self.path, &self.options, &mut self.warnings, &mut builder)
+                    // the builder has a suite just if the stream had one
+                    .map(|_| builder.pop())
+                    .map_err(|e| stream.iter.locate(e))
+            }
+            Suites::Done => return None,
+        };
+        match next {
+            Ok(Some(suite)) => Some(Ok(suite)),
+            Ok(None) => {
+                self.suites = Suites::Done;
+                None
+            }
+            Err(e) => {
+                self.suites = Suites::Done;
+                Some(Err(e))
+            }
+        }
+    }
+}
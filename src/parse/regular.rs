@@ -0,0 +1,214 @@
+//! A fast path for legacy files that are regular enough to deserialize.
+//!
+//! The event parser exists for the quirks of the legacy format, repeated
+//! top-level keys above all, but most files have just a `display`, a
+//! `table`, `flags` and one `tests` block with the common test options.
+//! Such files are deserialized with serde into the structs below in one
+//! go. Anything else, e.g. a second `tests` block, an option these structs
+//! do not know or a test without expected output, makes [`parse`] fail,
+//! and the file is left to the event parser, which gives the same suites
+//! and warnings for regular files.
+
+use std::{collections::BTreeSet, fmt};
+
+use anyhow::{bail, Result};
+use serde::{
+    de::{self, IgnoredAny, SeqAccess, Visitor},
+    Deserialize, Deserializer,
+};
+
+use super::{
+    check_test_mode, read_mode, read_test_mode, read_xfail_value, Flags, ParseOptions, Warning,
+};
+use crate::normalized::{Mode, Table, Test, TestSuite};
+
+/// The top-level keys of a regular file, in the order they have to come in
+const KEYS: &[&str] = &["display", "table", "flags", "tests"];
+
+/// A scalar as it is written. Unlike an `Option<String>` it is the text
+/// also if it spells null, as the event parser reads it.
+#[derive(Debug, Default)]
+struct Verbatim(Option<String>);
+
+impl<'de> Deserialize<'de> for Verbatim {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|value| Verbatim(Some(value)))
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegularFile {
+    #[serde(default)]
+    display: Verbatim,
+    table: RegularTable,
+    flags: Option<RegularFlags>,
+    tests: Vec<RegularTest>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RegularTable {
+    Single(String),
+    List(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegularFlags {
+    #[serde(default)]
+    testmode: Verbatim,
+    mode: Option<RegularModes>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum RegularModes {
+    Single(String),
+    List(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RegularOptions {
+    #[serde(default)]
+    name: Verbatim,
+    #[serde(default)]
+    xfail: Verbatim,
+    mode: Option<RegularModes>,
+}
+
+/// A test given as a sequence of input, expected output and optionally
+/// its options
+#[derive(Debug)]
+struct RegularTest {
+    input: String,
+    expected: String,
+    options: Option<RegularOptions>,
+}
+
+impl<'de> Deserialize<'de> for RegularTest {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct TestVisitor;
+
+        impl<'de> Visitor<'de> for TestVisitor {
+            type Value = RegularTest;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a sequence of input, expected output and options")
+            }
+
+            fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<RegularTest, A::Error> {
+                let input = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+                let expected = seq
+                    .next_element()?
+                    .ok_or_else(|| de::Error::invalid_length(1, &self))?;
+                let options = seq.next_element()?;
+                if seq.next_element::<IgnoredAny>()?.is_some() {
+                    return Err(de::Error::invalid_length(4, &self));
+                }
+                Ok(RegularTest {
+                    input,
+                    expected,
+                    options,
+                })
+            }
+        }
+
+        deserializer.deserialize_seq(TestVisitor)
+    }
+}
+
+fn modes(modes: Option<RegularModes>) -> Result<BTreeSet<Mode>> {
+    match modes {
+        None => Ok(BTreeSet::new()),
+        Some(RegularModes::Single(mode)) => Ok(BTreeSet::from([read_mode(&mode)?])),
+        Some(RegularModes::List(modes)) => modes.iter().map(|mode| read_mode(mode)).collect(),
+    }
+}
+
+/// Whether the top-level keys of `source` are all known and in order, each
+/// given once and with `tests` last, so that every setting applies to the
+/// tests. A table written as a literal or folded block is left to the
+/// event parser too, serde does not tell it from a table name.
+fn has_regular_keys(source: &str) -> bool {
+    let mut expected = KEYS.iter();
+    for line in source.lines() {
+        if line.is_empty() || line.starts_with(['#', ' ', '\t']) {
+            continue;
+        }
+        let Some((key, value)) = line.split_once(':') else {
+            return false;
+        };
+        if !expected.any(|known| *known == key) {
+            return false;
+        }
+        if key == "table" && value.trim_start().starts_with(['|', '>']) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Parse `source` into its one suite and the warnings about it, if the
+/// file is regular.
+pub(super) fn parse(source: &str, options: &ParseOptions) -> Result<(TestSuite, Vec<Warning>)> {
+    if !has_regular_keys(source) {
+        bail!("the top-level keys are not `display`, `table`, `flags` and `tests` in this order");
+    }
+    let file: RegularFile = serde_yaml::from_str(source)?;
+    if file.tests.is_empty() {
+        bail!("there are no tests");
+    }
+    let table = match file.table {
+        RegularTable::Single(table) => Table::Single(table.into()),
+        RegularTable::List(tables) => Table::List(tables.into_iter().map(Into::into).collect()),
+    };
+    let mut flags = Flags::default();
+    if let Some(regular) = file.flags {
+        if let Verbatim(Some(test_mode)) = regular.testmode {
+            flags.test_mode = read_test_mode(&test_mode)?;
+        }
+        flags.modes = modes(regular.mode)?;
+    }
+    let mut warnings = Vec::new();
+    let mut tests = Vec::with_capacity(file.tests.len());
+    for regular in file.tests {
+        let mut test = Test {
+            input: regular.input,
+            expected: regular.expected,
+            ..Test::default()
+        };
+        if let Some(regular) = regular.options {
+            test.name = regular.name.0;
+            if let Verbatim(Some(xfail)) = regular.xfail {
+                test.xfail = read_xfail_value(xfail, options)?;
+            }
+            test.mode = modes(regular.mode)?;
+            test.mode.retain(|mode| !flags.modes.contains(mode));
+        }
+        if let Some(message) = check_test_mode(&test, &flags.test_mode) {
+            warnings.push(Warning {
+                code: "mode-mismatch",
+                message,
+                loss: None,
+            });
+        }
+        tests.push(test);
+    }
+    let suite = TestSuite {
+        name: None,
+        comments: Vec::new(),
+        tags: Default::default(),
+        display_table: file.display.0.map(Into::into),
+        table,
+        mode: flags.test_mode,
+        translation_modes: flags.modes,
+        variables: Default::default(),
+        source: None,
+        tests,
+    };
+    Ok((suite, warnings))
+}
@@ -0,0 +1,105 @@
+//! Consistency of the character and cursor positions of the tests.
+//!
+//! The liblouis harness compares `outputPos` with the output position of
+//! every input character and `inputPos` with the input position of every
+//! output character, so `outputPos` needs one entry per character of the
+//! input and `inputPos` one per character of the expected output. A wrong
+//! count only shows up as a confusing failure when the test is run, so it
+//! is reported when converting. So is a `cursorPos` outside the input or a
+//! `brlCursorPos` outside the expected output.
+
+use std::fmt;
+
+use louis_migrate_yaml::normalized::{Test, TestSuite};
+
+use crate::diagnostics::Position;
+
+/// A position array or cursor position that does not fit the string it
+/// refers to.
+#[derive(Debug)]
+pub struct PositionMismatch {
+    /// 1-based index of the suite in the file
+    pub suite: usize,
+    /// 1-based index of the test in the suite
+    pub test: usize,
+    /// The code of the warning, `position-count` or `cursor-out-of-range`
+    pub code: &'static str,
+    pub message: String,
+    /// Where the test starts in the legacy file, if it is known
+    pub position: Option<Position>,
+}
+
+impl fmt::Display for PositionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "suite {}, test {}: {}",
+            self.suite, self.test, self.message
+        )
+    }
+}
+
+/// What is wrong with the positions of `test`, as warning code and
+/// message.
+fn check(test: &Test) -> Vec<(&'static str, String)> {
+    let mut problems = Vec::new();
+    let input = test.input.chars().count();
+    let expected = test.expected.chars().count();
+    let arrays = [
+        ("outputPos", &test.output_pos, "input", input),
+        ("inputPos", &test.input_pos, "expected output", expected),
+    ];
+    for (key, positions, described, characters) in arrays {
+        if !positions.is_empty() && positions.len() != characters {
+            let message = format!(
+                "{} has {} entries but the {} has {} characters",
+                key,
+                positions.len(),
+                described,
+                characters
+            );
+            problems.push(("position-count", message));
+        }
+    }
+    let cursors = [
+        ("cursorPos", test.cursor_pos, "input", input),
+        (
+            "brlCursorPos",
+            test.brl_cursor_pos,
+            "expected output",
+            expected,
+        ),
+    ];
+    for (key, cursor, described, characters) in cursors {
+        if let Some(cursor) = cursor.filter(|cursor| usize::from(*cursor) >= characters) {
+            let message = format!(
+                "{} {} is outside the {}, which has {} characters",
+                key, cursor, described, characters
+            );
+            problems.push(("cursor-out-of-range", message));
+        }
+    }
+    problems
+}
+
+/// Check that the `outputPos` of every test has an entry per character of
+/// its input and the `inputPos` an entry per character of its expected
+/// output, and that its cursor positions are within the input and the
+/// expected output. Tests without them are not checked.
+pub fn validate(test_suites: &[TestSuite]) -> Vec<PositionMismatch> {
+    let mut mismatches = Vec::new();
+    for (i, suite) in test_suites.iter().enumerate() {
+        for (j, test) in suite.tests.iter().enumerate() {
+            for (code, message) in check(test) {
+                mismatches.push(PositionMismatch {
+                    suite: i + 1,
+                    test: j + 1,
+                    code,
+                    message,
+                    position: test.source.map(Position::from),
+                });
+            }
+        }
+    }
+    mismatches
+}
@@ -0,0 +1,119 @@
+//! Progress reporting for long runs.
+
+use std::{
+    cell::Cell,
+    fmt::Display,
+    io::{self, IsTerminal},
+    path::Path,
+};
+
+use indicatif::{ProgressBar, ProgressStyle};
+
+use crate::diagnostics::{self, Position};
+
+/// A progress bar counting processed files, converted tests and warnings.
+/// Warnings are printed through it so they do not garble the bar.
+pub struct Progress {
+    bar: ProgressBar,
+    quiet: bool,
+    tests: Cell<usize>,
+    warnings: Cell<usize>,
+}
+
+impl Progress {
+    /// A progress bar over `files` files. It is hidden if `quiet` is set,
+    /// stdout is not a terminal, e.g. when output is piped to a file, or
+    /// verbose logging would interleave with it.
+    pub fn new(files: usize, quiet: bool) -> Self {
+        let bar = if quiet || !io::stdout().is_terminal() || log::log_enabled!(log::Level::Info) {
+            ProgressBar::hidden()
+        } else {
+            ProgressBar::new(files as u64)
+        };
+        bar.set_style(
+            ProgressStyle::with_template("{bar:30} {pos}/{len} files {msg}")
+                .expect("progress template is valid"),
+        );
+        Progress {
+            bar,
+            quiet,
+            tests: Cell::new(0),
+            warnings: Cell::new(0),
+        }
+    }
+
+    fn update(&self) {
+        let tests = match self.tests.get() {
+            0 => String::new(),
+            tests => format!("{} tests, ", tests),
+        };
+        self.bar
+            .set_message(format!("{}{} warnings", tests, self.warnings.get()));
+    }
+
+    pub fn add_tests(&self, tests: usize) {
+        self.tests.set(self.tests.get() + tests);
+        self.update();
+    }
+
+    pub fn add_warnings(&self, warnings: usize) {
+        self.warnings.set(self.warnings.get() + warnings);
+        self.update();
+    }
+
+    /// Run `f`, which prints to the terminal, with the bar hidden.
+    pub fn suspend<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.bar.suspend(f)
+    }
+
+    /// Print a message about `file`, unless `quiet` is set.
+    pub fn note(&self, file: &Path, message: impl Display) {
+        if !self.quiet {
+            self.suspend(|| eprintln!("{}: {}", file.display(), message));
+        }
+    }
+
+    /// Report a warning about `file` and count it.
+    pub fn warn(&self, file: &Path, code: &'static str, message: impl Display) {
+        self.suspend(|| diagnostics::warn(file, code, message));
+        self.add_warnings(1);
+    }
+
+    /// Report a warning about `file` at `position`, if it is known, and
+    /// count it.
+    pub fn warn_at(
+        &self,
+        file: &Path,
+        position: Option<Position>,
+        code: &'static str,
+        message: impl Display,
+    ) {
+        self.suspend(|| diagnostics::warn_at(file, position, code, message));
+        self.add_warnings(1);
+    }
+
+    /// Report an error about `file`.
+    pub fn error(&self, file: &Path, code: &'static str, message: impl Display) {
+        self.suspend(|| diagnostics::error(file, code, message));
+    }
+
+    /// Report an error at `position` in `source`, the contents of `file`.
+    pub fn error_at(
+        &self,
+        file: &Path,
+        source: &str,
+        position: Position,
+        code: &'static str,
+        message: impl Display,
+    ) {
+        self.suspend(|| diagnostics::error_at(file, source, position, code, message));
+    }
+
+    pub fn file_done(&self) {
+        self.bar.inc(1);
+    }
+
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
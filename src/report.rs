@@ -0,0 +1,152 @@
+//! Reports of conversion and lint results for consumption by CI.
+
+use std::{fmt::Write as _, fs, path::PathBuf, str::FromStr};
+
+use anyhow::Result;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportFormat {
+    Junit,
+    Tap,
+}
+
+/// A report requested on the command line as `FORMAT[=PATH]`. Without a
+/// path the report is written to stdout.
+#[derive(Debug, Clone)]
+pub struct ReportSpec {
+    format: ReportFormat,
+    path: Option<PathBuf>,
+}
+
+impl FromStr for ReportSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (format, path) = match s.split_once('=') {
+            Some((format, path)) => (format, Some(PathBuf::from(path))),
+            None => (s, None),
+        };
+        let format = match format {
+            "junit" => ReportFormat::Junit,
+            "tap" => ReportFormat::Tap,
+            other => return Err(format!("unknown report format {:?}", other)),
+        };
+        Ok(ReportSpec { format, path })
+    }
+}
+
+/// The result of processing one file.
+#[derive(Debug)]
+pub struct Case {
+    pub name: String,
+    /// `None` if the file was processed successfully, otherwise a short
+    /// message and the full details of what went wrong.
+    pub failure: Option<(String, String)>,
+}
+
+impl Case {
+    pub fn new<T>(name: impl Into<String>, result: &Result<T>) -> Self {
+        Case {
+            name: name.into(),
+            failure: result
+                .as_ref()
+                .err()
+                .map(|e| (e.to_string(), format!("{:?}", e))),
+        }
+    }
+}
+
+pub fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn junit(suite: &str, cases: &[Case]) -> String {
+    let failures = cases.iter().filter(|case| case.failure.is_some()).count();
+    let mut xml = String::new();
+    writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#).unwrap();
+    writeln!(xml, "<testsuites>").unwrap();
+    writeln!(
+        xml,
+        r#"  <testsuite name="{}" tests="{}" failures="{}">"#,
+        escape_xml(suite),
+        cases.len(),
+        failures
+    )
+    .unwrap();
+    for case in cases {
+        let name = escape_xml(&case.name);
+        match &case.failure {
+            None => {
+                writeln!(
+                    xml,
+                    r#"    <testcase name="{}" classname="{}"/>"#,
+                    name, suite
+                )
+                .unwrap();
+            }
+            Some((message, details)) => {
+                writeln!(
+                    xml,
+                    r#"    <testcase name="{}" classname="{}">"#,
+                    name, suite
+                )
+                .unwrap();
+                writeln!(
+                    xml,
+                    r#"      <failure message="{}">{}</failure>"#,
+                    escape_xml(message),
+                    escape_xml(details)
+                )
+                .unwrap();
+                writeln!(xml, "    </testcase>").unwrap();
+            }
+        }
+    }
+    writeln!(xml, "  </testsuite>").unwrap();
+    writeln!(xml, "</testsuites>").unwrap();
+    xml
+}
+
+/// A Test Anything Protocol report with the failure details as diagnostics.
+fn tap(cases: &[Case]) -> String {
+    let mut tap = String::new();
+    writeln!(tap, "TAP version 13").unwrap();
+    writeln!(tap, "1..{}", cases.len()).unwrap();
+    for (i, case) in cases.iter().enumerate() {
+        match &case.failure {
+            None => writeln!(tap, "ok {} - {}", i + 1, case.name).unwrap(),
+            Some((_, details)) => {
+                writeln!(tap, "not ok {} - {}", i + 1, case.name).unwrap();
+                for line in details.lines() {
+                    writeln!(tap, "# {}", line).unwrap();
+                }
+            }
+        }
+    }
+    tap
+}
+
+/// Write a report on `cases` in the requested format. `suite` names the
+/// kind of run, e.g. `convert` or `lint`.
+pub fn write(spec: &ReportSpec, suite: &str, cases: &[Case]) -> Result<()> {
+    let report = match spec.format {
+        ReportFormat::Junit => junit(suite, cases),
+        ReportFormat::Tap => tap(cases),
+    };
+    match &spec.path {
+        Some(path) => fs::write(path, report)?,
+        None => print!("{}", report),
+    }
+    Ok(())
+}
@@ -0,0 +1,197 @@
+//! A static HTML page for reviewing the tests in a browser.
+//!
+//! Every suite is a table with one row per test. Braille is rendered in a
+//! braille font, each cell with its dot numbers as a tooltip, so that both
+//! sighted reviewers and braille readers can check the expected output.
+
+use std::fmt::Write;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use louis_migrate_yaml::{
+    format::{Formatted, Formatter},
+    normalized::{DirectionXfail, Meta, Table, Test, TestSuite, Xfail},
+};
+
+use crate::{braille, report::escape_xml};
+
+const STYLE: &str = "\
+body { font-family: sans-serif; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: left; vertical-align: top; }
+td { white-space: pre-wrap; }
+.braille { font-family: \"Apple Braille\", \"Segoe UI Symbol\", \"DejaVu Sans\", sans-serif; font-size: 1.5em; }
+.braille span:hover { background: #ffd; }
+tr.xfail { background: #fee; }";
+
+/// `s` with every braille cell in a span whose title gives its dots.
+fn braille_html(s: &str) -> String {
+    let mut html = String::new();
+    for c in s.chars() {
+        match braille::cell_to_dots(c) {
+            Some(dots) => write!(html, "<span title=\"dots {}\">{}</span>", dots, c).unwrap(),
+            None => html.push_str(&escape_xml(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+    html
+}
+
+fn table_html(table: &Table) -> String {
+    match table {
+        Table::Single(path) => escape_xml(&path.display().to_string()),
+        Table::List(paths) => paths
+            .iter()
+            .map(|path| escape_xml(&path.display().to_string()))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Table::MetaData(query) => query
+            .iter()
+            .map(|(key, value)| format!("{}: {}", escape_xml(key), escape_xml(value)))
+            .collect::<Vec<_>>()
+            .join(", "),
+        Table::Inline(_) => "inline table".to_string(),
+    }
+}
+
+fn direction_xfail_text(direction: &str, xfail: &DirectionXfail) -> Option<String> {
+    match xfail {
+        DirectionXfail::Scalar(false) => None,
+        DirectionXfail::Scalar(true) => Some(direction.to_string()),
+        DirectionXfail::Reason(reason) => Some(format!("{}: {}", direction, reason)),
+    }
+}
+
+fn xfail_text(xfail: &Xfail) -> String {
+    match xfail {
+        Xfail::Scalar(false) => String::new(),
+        Xfail::Scalar(true) => "yes".to_string(),
+        Xfail::Reason(reason) => format!("yes: {}", reason),
+        Xfail::Map { forward, backward } => [
+            direction_xfail_text("forward", forward),
+            direction_xfail_text("backward", backward),
+        ]
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>()
+        .join("; "),
+    }
+}
+
+fn test_row(html: &mut String, number: usize, test: &Test) {
+    let id = match &test.id {
+        Some(id) => format!(" id=\"{}\"", escape_xml(id)),
+        None => String::new(),
+    };
+    let class = if test.xfail.is_false() {
+        ""
+    } else {
+        " class=\"xfail\""
+    };
+    writeln!(
+        html,
+        "<tr{}{}><td>{}</td><td>{}</td><td class=\"braille\">{}</td>\
+         <td class=\"braille\">{}</td><td>{}</td></tr>",
+        id,
+        class,
+        number,
+        escape_xml(test.name.as_deref().unwrap_or_default()),
+        braille_html(&test.input),
+        braille_html(&test.expected),
+        escape_xml(&xfail_text(&test.xfail))
+    )
+    .unwrap();
+}
+
+/// Write `test_suites` as an HTML page, titled after the source recorded
+/// in `meta`.
+fn to_string(test_suites: &[TestSuite], meta: &Meta) -> String {
+    let title = match &meta.meta {
+        Some(provenance) => format!("Tests of {}", provenance.source.display()),
+        None => "Tests".to_string(),
+    };
+    let title = escape_xml(&title);
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>").unwrap();
+    writeln!(html, "<html>\n<head>\n<meta charset=\"utf-8\">").unwrap();
+    writeln!(html, "<title>{}</title>", title).unwrap();
+    writeln!(html, "<style>\n{}\n</style>\n</head>\n<body>", STYLE).unwrap();
+    writeln!(html, "<h1>{}</h1>", title).unwrap();
+    for (i, suite) in test_suites.iter().enumerate() {
+        writeln!(
+            html,
+            "<h2>Suite {}: {} ({})</h2>",
+            i + 1,
+            table_html(&suite.table),
+            suite
+                .mode
+                .to_possible_value()
+                .map(|value| value.get_name().to_string())
+                .unwrap_or_default()
+        )
+        .unwrap();
+        if let Some(display_table) = &suite.display_table {
+            writeln!(
+                html,
+                "<p>Display table: {}</p>",
+                escape_xml(&display_table.display().to_string())
+            )
+            .unwrap();
+        }
+        if !suite.translation_modes.is_empty() {
+            let modes: Vec<_> = suite
+                .translation_modes
+                .iter()
+                .map(|mode| serde_json::to_string(mode).unwrap_or_default())
+                .collect();
+            writeln!(
+                html,
+                "<p>Translation modes: {}</p>",
+                escape_xml(&modes.join(", ").replace('"', ""))
+            )
+            .unwrap();
+        }
+        writeln!(html, "<table>").unwrap();
+        writeln!(
+            html,
+            "<tr><th>#</th><th>Name</th><th>Input</th><th>{}</th><th>Xfail</th></tr>",
+            heading(suite.mode.expected_key())
+        )
+        .unwrap();
+        for (j, test) in suite.tests.iter().enumerate() {
+            test_row(&mut html, j + 1, test);
+        }
+        writeln!(html, "</table>").unwrap();
+    }
+    writeln!(html, "</body>\n</html>").unwrap();
+    html
+}
+
+/// `key` capitalized, as a column heading.
+fn heading(key: &str) -> String {
+    let mut chars = key.chars();
+    chars
+        .next()
+        .map(|first| first.to_uppercase().chain(chars).collect())
+        .unwrap_or_default()
+}
+
+/// A page for reviewing the tests in a browser, with braille rendered in a
+/// braille font
+pub struct ReviewPage;
+
+impl Formatter for ReviewPage {
+    fn name(&self) -> &'static str {
+        "html"
+    }
+
+    fn extension(&self) -> Option<&'static str> {
+        Some("html")
+    }
+
+    fn format(&self, test_suites: &[TestSuite], meta: &Meta) -> Result<Formatted> {
+        Ok(Formatted {
+            output: to_string(test_suites, meta),
+            warnings: Vec::new(),
+        })
+    }
+}
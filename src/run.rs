@@ -0,0 +1,464 @@
+//! Execute converted tests through liblouis.
+//!
+//! This is a sanity check for the migration: the tests are parsed from the
+//! legacy file and run against liblouis using the `louis` bindings, so a
+//! change in semantics shows up as a change in the pass/fail summary.
+//! `verify-equivalence` goes further and runs both the legacy file, read
+//! the way the yaml test harness of liblouis reads it (see
+//! [`louis_migrate_yaml::checkyaml`]), and its normalized conversion,
+//! comparing the result of every test.
+
+use std::{collections::BTreeMap, fmt, fs, mem, path::PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use clap::Args;
+use louis::{modes, API};
+use serde::Serialize;
+
+use louis_migrate_yaml::{
+    checkyaml,
+    normalized::{DirectionXfail, Mode, Table, Test, TestMode, TestSuite, Xfail},
+    parse::ParseOptions,
+};
+
+use crate::{parse_file, validate::validate_file};
+
+/// The reason recorded by `--auto-xfail` for tests that fail.
+const AUTO_XFAIL_REASON: &str = "auto: differs since migration";
+
+#[derive(Args, Debug)]
+pub struct RunArgs {
+    /// The yaml file to run
+    yaml: PathBuf,
+    /// Print the result of every single test
+    #[arg(short, long)]
+    verbose: bool,
+    /// Only run tests with the tag TAG, given by themselves or their suite
+    /// (repeatable, a test needs one of the tags)
+    #[arg(long, value_name = "TAG")]
+    tag: Vec<String>,
+}
+
+#[derive(Args, Debug)]
+pub struct VerifyEquivalenceArgs {
+    /// The legacy yaml file
+    legacy: PathBuf,
+    /// Its conversion in the normalized format
+    normalized: PathBuf,
+    /// Write the equivalence report to PATH instead of stdout
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum Direction {
+    Forward,
+    Backward,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Outcome {
+    Pass,
+    Fail,
+    ExpectedFail,
+    UnexpectedPass,
+}
+
+#[derive(Debug, Default)]
+pub struct Summary {
+    pub passed: usize,
+    pub failed: usize,
+    pub expected_failures: usize,
+    pub unexpected_passes: usize,
+    pub skipped: usize,
+}
+
+impl Summary {
+    fn record(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Pass => self.passed += 1,
+            Outcome::Fail => self.failed += 1,
+            Outcome::ExpectedFail => self.expected_failures += 1,
+            Outcome::UnexpectedPass => self.unexpected_passes += 1,
+        }
+    }
+
+    pub fn is_success(&self) -> bool {
+        self.failed == 0 && self.unexpected_passes == 0
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} passed, {} failed, {} expected failures, {} unexpected passes, {} skipped",
+            self.passed, self.failed, self.expected_failures, self.unexpected_passes, self.skipped
+        )
+    }
+}
+
+/// The table list as liblouis expects it, i.e. comma separated with the
+/// display table first. Tables given as metadata queries or inline are not
+/// supported by the bindings.
+fn table_list(suite: &TestSuite) -> Option<String> {
+    let mut tables: Vec<String> = suite
+        .display_table
+        .iter()
+        .map(|path| path.display().to_string())
+        .collect();
+    match &suite.table {
+        Table::Single(path) => tables.push(path.display().to_string()),
+        Table::List(paths) => tables.extend(paths.iter().map(|path| path.display().to_string())),
+        Table::MetaData(..) | Table::Inline(..) => return None,
+    }
+    Some(tables.join(","))
+}
+
+/// The directions in which the tests of a suite are run. Display and
+/// hyphenation tests are not supported by the bindings.
+fn directions(mode: &TestMode) -> &'static [Direction] {
+    match mode {
+        TestMode::Forward => &[Direction::Forward],
+        TestMode::Backward => &[Direction::Backward],
+        TestMode::BothDirections => &[Direction::Forward, Direction::Backward],
+        TestMode::Display | TestMode::Hyphenate | TestMode::HyphenateBraille => &[],
+    }
+}
+
+/// The translation modes `test` of `suite` is run with, as flags of the
+/// bindings.
+fn translation_modes(suite: &TestSuite, test: &Test) -> modes::TranslationModes {
+    suite
+        .test_modes(test)
+        .iter()
+        .map(|mode| match mode {
+            Mode::NoContractions => modes::NO_CONTRACTIONS,
+            Mode::CompbrlAtCursor => modes::COMPBRL_AT_CURSOR,
+            Mode::DotsIo => modes::DOTS_IO,
+            Mode::CompbrlLeftCursor => modes::COMPBRL_LEFT_CURSOR,
+            Mode::UcBrl => modes::UC_BRL,
+            Mode::NoUndefined => modes::NO_UNDEFINED,
+            Mode::PartialTrans => modes::PARTIAL_TRANS,
+        })
+        .fold(0, |acc, mode| acc | mode)
+}
+
+/// Run `test` of a suite in `mode` in `direction`. The input of a test of
+/// a backward suite is braille, a `bothDirections` test is run backward
+/// from its expected output.
+fn run_test(
+    louis: &API,
+    tables: &str,
+    modes: modes::TranslationModes,
+    test: &Test,
+    mode: &TestMode,
+    direction: Direction,
+) -> Outcome {
+    let (input, expected) = match (mode, direction) {
+        (TestMode::BothDirections, Direction::Backward) => (
+            &test.expected,
+            test.expected_backward.as_ref().unwrap_or(&test.input),
+        ),
+        _ => (&test.input, &test.expected),
+    };
+    let actual = louis.translate_simple(tables, input, direction == Direction::Backward, modes);
+    let xfail = match direction {
+        Direction::Forward => test.xfail.fails_forward(),
+        Direction::Backward => test.xfail.fails_backward(),
+    };
+    match (actual == *expected, xfail) {
+        (true, false) => Outcome::Pass,
+        (false, false) => Outcome::Fail,
+        (false, true) => Outcome::ExpectedFail,
+        (true, true) => Outcome::UnexpectedPass,
+    }
+}
+
+/// Run all tests of `test_suites` and return the results. `report` is
+/// called for every test that was run.
+pub fn run_suites(
+    louis: &API,
+    test_suites: &[TestSuite],
+    mut report: impl FnMut(&Test, Outcome),
+) -> Summary {
+    let mut summary = Summary::default();
+    for suite in test_suites {
+        let directions = directions(&suite.mode);
+        let tables = table_list(suite);
+        for test in &suite.tests {
+            match &tables {
+                Some(tables) if !directions.is_empty() => {
+                    for direction in directions {
+                        let modes = translation_modes(suite, test);
+                        let outcome = run_test(louis, tables, modes, test, &suite.mode, *direction);
+                        report(test, outcome);
+                        summary.record(outcome);
+                    }
+                }
+                _ => summary.skipped += 1,
+            }
+        }
+    }
+    summary
+}
+
+/// Run all tests and mark the ones that fail unexpectedly as xfail. If
+/// `clear` is set, the xfail of tests that pass in all directions is
+/// removed. Returns the number of marked and cleared tests.
+pub fn auto_xfail(test_suites: &mut [TestSuite], clear: bool) -> Result<(usize, usize)> {
+    let louis = API::new().map_err(|e| anyhow!("Could not initialize liblouis: {:?}", e))?;
+    let mut marked = 0;
+    let mut cleared = 0;
+    for suite in test_suites.iter_mut() {
+        let mode = suite.mode.clone();
+        let directions = directions(&mode);
+        let Some(tables) = table_list(suite) else {
+            continue;
+        };
+        let modes: Vec<_> = suite
+            .tests
+            .iter()
+            .map(|test| translation_modes(suite, test))
+            .collect();
+        for (test, modes) in suite.tests.iter_mut().zip(modes) {
+            let outcomes: Vec<(Direction, Outcome)> = directions
+                .iter()
+                .map(|direction| {
+                    let outcome = run_test(&louis, &tables, modes, test, &mode, *direction);
+                    (*direction, outcome)
+                })
+                .collect();
+            let failing: Vec<Direction> = outcomes
+                .iter()
+                .filter(|(_, outcome)| *outcome == Outcome::Fail)
+                .map(|(direction, _)| *direction)
+                .collect();
+            if !failing.is_empty() {
+                match &mut test.xfail {
+                    Xfail::Map { forward, backward } => {
+                        for (direction, xfail) in [
+                            (Direction::Forward, forward),
+                            (Direction::Backward, backward),
+                        ] {
+                            if failing.contains(&direction) && xfail.is_false() {
+                                *xfail = DirectionXfail::Reason(AUTO_XFAIL_REASON.to_string());
+                            }
+                        }
+                    }
+                    xfail => *xfail = Xfail::Reason(AUTO_XFAIL_REASON.to_string()),
+                }
+                marked += 1;
+            } else if clear
+                && outcomes
+                    .iter()
+                    .any(|(_, outcome)| *outcome == Outcome::UnexpectedPass)
+                && outcomes
+                    .iter()
+                    .all(|(_, outcome)| matches!(outcome, Outcome::Pass | Outcome::UnexpectedPass))
+            {
+                test.xfail = Xfail::default();
+                cleared += 1;
+            }
+        }
+    }
+    Ok((marked, cleared))
+}
+
+/// Give the tests of `bothDirections` suites whose expected output does
+/// not translate back to their input what it does translate back to as
+/// `expected_backward`. Tests that have one already or are expected to
+/// fail backward are left alone. Returns the number of tests given one.
+pub fn synthesize_backward(test_suites: &mut [TestSuite]) -> Result<usize> {
+    let louis = API::new().map_err(|e| anyhow!("Could not initialize liblouis: {:?}", e))?;
+    let mut synthesized = 0;
+    for suite in test_suites.iter_mut() {
+        if suite.mode != TestMode::BothDirections {
+            continue;
+        }
+        let Some(tables) = table_list(suite) else {
+            continue;
+        };
+        let modes: Vec<_> = suite
+            .tests
+            .iter()
+            .map(|test| translation_modes(suite, test))
+            .collect();
+        for (test, modes) in suite.tests.iter_mut().zip(modes) {
+            if test.expected_backward.is_some() || test.xfail.fails_backward() {
+                continue;
+            }
+            let backward = louis.translate_simple(&tables, &test.expected, true, modes);
+            if backward != test.input {
+                test.expected_backward = Some(backward);
+                synthesized += 1;
+            }
+        }
+    }
+    Ok(synthesized)
+}
+
+/// The outcomes of a test by direction, empty if it cannot be run
+type Outcomes = BTreeMap<Direction, Outcome>;
+
+/// The outcomes of every test of `test_suites`, suite by suite.
+fn test_outcomes(louis: &API, test_suites: &[TestSuite]) -> Vec<Vec<Outcomes>> {
+    test_suites
+        .iter()
+        .map(|suite| {
+            let directions = directions(&suite.mode);
+            let tables = table_list(suite);
+            suite
+                .tests
+                .iter()
+                .map(|test| match &tables {
+                    Some(tables) => directions
+                        .iter()
+                        .map(|direction| {
+                            let modes = translation_modes(suite, test);
+                            (
+                                *direction,
+                                run_test(louis, tables, modes, test, &suite.mode, *direction),
+                            )
+                        })
+                        .collect(),
+                    None => Outcomes::new(),
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// A difference between the results of the legacy and the normalized tests
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+enum Mismatch {
+    SuiteCount {
+        legacy: usize,
+        normalized: usize,
+    },
+    TestCount {
+        suite: usize,
+        legacy: usize,
+        normalized: usize,
+    },
+    Outcome {
+        suite: usize,
+        test: usize,
+        input: String,
+        legacy: Outcomes,
+        normalized: Outcomes,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct EquivalenceReport<'a> {
+    legacy: &'a PathBuf,
+    normalized: &'a PathBuf,
+    equivalent: bool,
+    /// The number of tests compared
+    tests: usize,
+    mismatches: Vec<Mismatch>,
+}
+
+/// Compare the results of `legacy` with those of `normalized`, pairing
+/// suites and tests by position. Suites and tests are counted from 1.
+fn compare(
+    legacy: &[TestSuite],
+    legacy_outcomes: &[Vec<Outcomes>],
+    normalized_outcomes: &[Vec<Outcomes>],
+) -> (usize, Vec<Mismatch>) {
+    let mut tests = 0;
+    let mut mismatches = Vec::new();
+    if legacy_outcomes.len() != normalized_outcomes.len() {
+        mismatches.push(Mismatch::SuiteCount {
+            legacy: legacy_outcomes.len(),
+            normalized: normalized_outcomes.len(),
+        });
+    }
+    for (i, (suite, normalized_suite)) in legacy.iter().zip(normalized_outcomes).enumerate() {
+        let legacy_suite = &legacy_outcomes[i];
+        if legacy_suite.len() != normalized_suite.len() {
+            mismatches.push(Mismatch::TestCount {
+                suite: i + 1,
+                legacy: legacy_suite.len(),
+                normalized: normalized_suite.len(),
+            });
+        }
+        for (j, (legacy_test, normalized_test)) in
+            legacy_suite.iter().zip(normalized_suite).enumerate()
+        {
+            tests += 1;
+            if legacy_test != normalized_test {
+                mismatches.push(Mismatch::Outcome {
+                    suite: i + 1,
+                    test: j + 1,
+                    input: suite.tests[j].input.clone(),
+                    legacy: legacy_test.clone(),
+                    normalized: normalized_test.clone(),
+                });
+            }
+        }
+    }
+    (tests, mismatches)
+}
+
+pub fn verify_equivalence(args: &VerifyEquivalenceArgs) -> Result<()> {
+    let legacy = checkyaml::read_file(&args.legacy)?;
+    let normalized = validate_file(&args.normalized)?;
+    let louis = API::new().map_err(|e| anyhow!("Could not initialize liblouis: {:?}", e))?;
+
+    let legacy_outcomes = test_outcomes(&louis, &legacy);
+    let normalized_outcomes = test_outcomes(&louis, &normalized);
+    let (tests, mismatches) = compare(&legacy, &legacy_outcomes, &normalized_outcomes);
+    let report = EquivalenceReport {
+        legacy: &args.legacy,
+        normalized: &args.normalized,
+        equivalent: mismatches.is_empty(),
+        tests,
+        mismatches,
+    };
+    let json = serde_json::to_string_pretty(&report)? + "\n";
+    match &args.output {
+        Some(path) => fs::write(path, json)?,
+        None => print!("{}", json),
+    }
+    if !report.equivalent {
+        bail!(
+            "{} mismatch(es) between the legacy and the normalized tests",
+            report.mismatches.len()
+        );
+    }
+    Ok(())
+}
+
+pub fn run(args: &RunArgs) -> Result<()> {
+    let mut test_suites = parse_file(&args.yaml, &ParseOptions::default())?;
+    if !args.tag.is_empty() {
+        for suite in &mut test_suites {
+            let tests = mem::take(&mut suite.tests);
+            suite.tests = tests
+                .into_iter()
+                .filter(|test| args.tag.iter().any(|tag| suite.test_has_tag(test, tag)))
+                .collect();
+        }
+    }
+    let louis = API::new().map_err(|e| anyhow!("Could not initialize liblouis: {:?}", e))?;
+
+    let summary = run_suites(&louis, &test_suites, |test, outcome| {
+        if args.verbose || matches!(outcome, Outcome::Fail | Outcome::UnexpectedPass) {
+            eprintln!("{:?}: {:?} -> {:?}", outcome, test.input, test.expected);
+        }
+    });
+
+    println!("{}: {}", args.yaml.display(), summary);
+    if !summary.is_success() {
+        bail!(
+            "{} test(s) did not behave as expected",
+            summary.failed + summary.unexpected_passes
+        );
+    }
+    Ok(())
+}
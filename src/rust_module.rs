@@ -0,0 +1,336 @@
+//! The test suites as a Rust module, for a test harness written in Rust.
+//!
+//! The generated module defines the types of the suites and a `SUITES`
+//! constant with all suites of the file as literals, so the harness can
+//! embed the tests without parsing yaml at runtime, e.g.
+//!
+//! ```ignore
+//! mod en_us_g2 {
+//!     include!(concat!(env!("OUT_DIR"), "/en-us-g2.rs"));
+//! }
+//! ```
+//!
+//! Comments are kept as Rust comments. Legacy options kept verbatim, like
+//! `variables` or unknown test options, have no Rust equivalent and are
+//! left out.
+
+use std::{collections::BTreeSet, fmt::Write, path::Path};
+
+use anyhow::Result;
+use louis_migrate_yaml::{
+    format::{Formatted, Formatter},
+    normalized::{DirectionXfail, Meta, Table, Test, TestSuite, Xfail},
+};
+
+/// The types the data of the module is made of.
+const TYPES: &str = "\
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Table {
+    Files(&'static [&'static str]),
+    Query(&'static [(&'static str, &'static str)]),
+    Inline(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TestMode {
+    Forward,
+    Backward,
+    BothDirections,
+    Display,
+    Hyphenate,
+    HyphenateBraille,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    NoContractions,
+    CompbrlAtCursor,
+    DotsIo,
+    CompbrlLeftCursor,
+    UcBrl,
+    NoUndefined,
+    PartialTrans,
+}
+
+/// Whether a test is expected to fail in one direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Xfail {
+    No,
+    Yes,
+    Because(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Test {
+    pub id: Option<&'static str>,
+    pub name: Option<&'static str>,
+    pub tags: &'static [&'static str],
+    pub input: &'static str,
+    pub expected: &'static str,
+    pub expected_backward: Option<&'static str>,
+    pub xfail_forward: Xfail,
+    pub xfail_backward: Xfail,
+    pub input_pos: &'static [u16],
+    pub output_pos: &'static [u16],
+    pub cursor_pos: Option<u16>,
+    pub brl_cursor_pos: Option<u16>,
+    pub input_class: &'static [(&'static str, &'static str)],
+    pub output_class: &'static [(&'static str, &'static str)],
+    pub modes: &'static [Mode],
+    pub max_output_length: Option<u16>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TestSuite {
+    pub name: Option<&'static str>,
+    pub tags: &'static [&'static str],
+    pub display_table: Option<&'static str>,
+    pub table: Table,
+    pub mode: TestMode,
+    pub translation_modes: &'static [Mode],
+    pub tests: &'static [Test],
+}
+";
+
+/// A string literal, or `None` in case of `None`.
+fn option_literal(s: Option<&str>) -> String {
+    match s {
+        Some(s) => format!("Some({:?})", s),
+        None => "None".to_string(),
+    }
+}
+
+fn path_literal(path: &Path) -> String {
+    format!("{:?}", path.to_string_lossy())
+}
+
+fn pairs_literal<'a>(pairs: impl IntoIterator<Item = (&'a String, &'a String)>) -> String {
+    let pairs: Vec<String> = pairs
+        .into_iter()
+        .map(|(key, value)| format!("({:?}, {:?})", key, value))
+        .collect();
+    format!("&[{}]", pairs.join(", "))
+}
+
+fn table_literal(table: &Table) -> String {
+    match table {
+        Table::Single(path) => format!("Table::Files(&[{}])", path_literal(path)),
+        Table::List(paths) => {
+            let paths: Vec<String> = paths.iter().map(|path| path_literal(path)).collect();
+            format!("Table::Files(&[{}])", paths.join(", "))
+        }
+        Table::MetaData(query) => format!("Table::Query({})", pairs_literal(query)),
+        Table::Inline(contents) => format!("Table::Inline({:?})", contents),
+    }
+}
+
+fn direction_xfail_literal(xfail: &DirectionXfail) -> String {
+    match xfail {
+        DirectionXfail::Scalar(false) => "Xfail::No".to_string(),
+        DirectionXfail::Scalar(true) => "Xfail::Yes".to_string(),
+        DirectionXfail::Reason(reason) => format!("Xfail::Because({:?})", reason),
+    }
+}
+
+/// The forward and backward xfail of `xfail`.
+fn xfail_literals(xfail: &Xfail) -> (String, String) {
+    let (forward, backward) = match xfail {
+        Xfail::Scalar(xfail) => (
+            DirectionXfail::Scalar(*xfail),
+            DirectionXfail::Scalar(*xfail),
+        ),
+        Xfail::Reason(reason) => (
+            DirectionXfail::Reason(reason.clone()),
+            DirectionXfail::Reason(reason.clone()),
+        ),
+        Xfail::Map { forward, backward } => (forward.clone(), backward.clone()),
+    };
+    (
+        direction_xfail_literal(&forward),
+        direction_xfail_literal(&backward),
+    )
+}
+
+fn list_literal<T: ToString>(items: impl IntoIterator<Item = T>) -> String {
+    let items: Vec<String> = items.into_iter().map(|item| item.to_string()).collect();
+    format!("&[{}]", items.join(", "))
+}
+
+fn tags_literal(tags: &BTreeSet<String>) -> String {
+    list_literal(tags.iter().map(|tag| format!("{:?}", tag)))
+}
+
+fn option_number(n: Option<u16>) -> String {
+    match n {
+        Some(n) => format!("Some({})", n),
+        None => "None".to_string(),
+    }
+}
+
+struct Module {
+    out: String,
+}
+
+impl Module {
+    fn line(&mut self, indent: usize, line: impl AsRef<str>) {
+        let _ = writeln!(self.out, "{}{}", " ".repeat(indent), line.as_ref());
+    }
+
+    fn comments(&mut self, indent: usize, comments: &[String]) {
+        for line in comments.iter().flat_map(|comment| comment.lines()) {
+            self.line(indent, format!("// {}", line).trim_end());
+        }
+    }
+
+    fn test(&mut self, test: &Test) {
+        let (xfail_forward, xfail_backward) = xfail_literals(&test.xfail);
+        self.comments(12, &test.comments);
+        self.line(12, "Test {");
+        self.line(16, format!("id: {},", option_literal(test.id.as_deref())));
+        self.line(
+            16,
+            format!("name: {},", option_literal(test.name.as_deref())),
+        );
+        self.line(16, format!("tags: {},", tags_literal(&test.tags)));
+        self.line(16, format!("input: {:?},", test.input));
+        self.line(16, format!("expected: {:?},", test.expected));
+        self.line(
+            16,
+            format!(
+                "expected_backward: {},",
+                option_literal(test.expected_backward.as_deref())
+            ),
+        );
+        self.line(16, format!("xfail_forward: {},", xfail_forward));
+        self.line(16, format!("xfail_backward: {},", xfail_backward));
+        self.line(16, format!("input_pos: {},", list_literal(&test.input_pos)));
+        self.line(
+            16,
+            format!("output_pos: {},", list_literal(&test.output_pos)),
+        );
+        self.line(
+            16,
+            format!("cursor_pos: {},", option_number(test.cursor_pos)),
+        );
+        self.line(
+            16,
+            format!("brl_cursor_pos: {},", option_number(test.brl_cursor_pos)),
+        );
+        self.line(
+            16,
+            format!("input_class: {},", pairs_literal(&test.input_class)),
+        );
+        self.line(
+            16,
+            format!("output_class: {},", pairs_literal(&test.output_class)),
+        );
+        let modes = test.mode.iter().map(|mode| format!("Mode::{:?}", mode));
+        self.line(16, format!("modes: {},", list_literal(modes)));
+        self.line(
+            16,
+            format!(
+                "max_output_length: {},",
+                option_number(test.max_output_length)
+            ),
+        );
+        self.line(12, "},");
+    }
+
+    fn suite(&mut self, suite: &TestSuite) {
+        self.comments(4, &suite.comments);
+        self.line(4, "TestSuite {");
+        self.line(
+            8,
+            format!("name: {},", option_literal(suite.name.as_deref())),
+        );
+        self.line(8, format!("tags: {},", tags_literal(&suite.tags)));
+        let display_table = suite.display_table.as_deref().map(Path::to_string_lossy);
+        self.line(
+            8,
+            format!(
+                "display_table: {},",
+                option_literal(display_table.as_deref())
+            ),
+        );
+        self.line(8, format!("table: {},", table_literal(&suite.table)));
+        self.line(8, format!("mode: TestMode::{:?},", suite.mode));
+        let modes = suite
+            .translation_modes
+            .iter()
+            .map(|mode| format!("Mode::{:?}", mode));
+        self.line(8, format!("translation_modes: {},", list_literal(modes)));
+        self.line(8, "tests: &[");
+        for test in &suite.tests {
+            self.test(test);
+        }
+        self.line(8, "],");
+        self.line(4, "},");
+    }
+}
+
+/// Write `test_suites` as a Rust module. `meta` is recorded in the header
+/// comment. Returns the module and a warning about every value that was
+/// left out.
+fn to_string(test_suites: &[TestSuite], meta: &Meta) -> (String, Vec<String>) {
+    let mut module = Module { out: String::new() };
+    let mut warnings = Vec::new();
+    match &meta.meta {
+        Some(provenance) => {
+            module.line(
+                0,
+                format!(
+                    "// Converted from {} (sha256 {}) by {}.",
+                    provenance.source.display(),
+                    provenance.sha256,
+                    provenance.tool
+                ),
+            );
+        }
+        None => module.line(0, "// Converted from a liblouis yaml test file."),
+    }
+    module.line(0, "// Generated, do not edit.");
+    module.line(0, "");
+    module.out.push_str(TYPES);
+    module.line(0, "");
+    module.line(0, "pub const SUITES: &[TestSuite] = &[");
+    for (i, suite) in test_suites.iter().enumerate() {
+        if !suite.variables.is_empty() {
+            warnings.push(format!(
+                "suite {}: variables cannot be written in Rust, left out",
+                i + 1
+            ));
+        }
+        for (j, test) in suite.tests.iter().enumerate() {
+            if !test.extra.is_empty() {
+                let keys: Vec<&str> = test.extra.keys().map(String::as_str).collect();
+                warnings.push(format!(
+                    "suite {}, test {}: {} cannot be written in Rust, left out",
+                    i + 1,
+                    j + 1,
+                    keys.join(", ")
+                ));
+            }
+        }
+        module.suite(suite);
+    }
+    module.line(0, "];");
+    (module.out, warnings)
+}
+
+/// A Rust module with the suites as constants, for a Rust test harness
+pub struct RustModule;
+
+impl Formatter for RustModule {
+    fn name(&self) -> &'static str {
+        "rust"
+    }
+
+    fn extension(&self) -> Option<&'static str> {
+        Some("rs")
+    }
+
+    fn format(&self, test_suites: &[TestSuite], meta: &Meta) -> Result<Formatted> {
+        let (output, warnings) = to_string(test_suites, meta);
+        Ok(Formatted { output, warnings })
+    }
+}
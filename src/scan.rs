@@ -0,0 +1,77 @@
+//! A line-based scan of legacy files for what libyaml does not report.
+//!
+//! The events of libyaml carry neither comments nor positions, so both
+//! are recovered from the source line by line. The scan recognizes the
+//! same suites and tests as the event parser: a top-level key, and in a
+//! `tests` block every item of its block sequence. Tests written in flow
+//! style are not found, only their block is.
+
+use louis_migrate_yaml::parse::key_alias;
+
+use crate::diagnostics::Position;
+
+/// What a line of the source holds, as far as the scan is concerned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Line<'a> {
+    /// A full-line comment, without the `#` and a space after it
+    Comment {
+        text: &'a str,
+        /// Whether the comment is nested deeper than the items of the
+        /// current `tests` block, i.e. inside its last test
+        in_test: bool,
+    },
+    /// A top-level key, in its canonical spelling
+    Key {
+        key: &'a str,
+        /// Whether the key has an empty value on its line, in which case
+        /// the parser skips a `tests` block unless it is followed by block
+        /// items
+        empty: bool,
+    },
+    /// An item of the block sequence of the current `tests` block
+    Test,
+}
+
+/// The lines of `source` the scan recognizes, with the position of their
+/// first character.
+pub fn scan(source: &str) -> impl Iterator<Item = (Position, Line<'_>)> {
+    let mut in_tests = false;
+    let mut item_indent = None;
+    source.lines().enumerate().filter_map(move |(i, line)| {
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        if trimmed.is_empty() {
+            return None;
+        }
+        let position = Position {
+            line: i + 1,
+            column: line[..indent].chars().count() + 1,
+        };
+        if let Some(comment) = trimmed.strip_prefix('#') {
+            let text = comment.strip_prefix(' ').unwrap_or(comment);
+            let in_test = in_tests && item_indent.is_some_and(|item| indent > item);
+            return Some((position, Line::Comment { text, in_test }));
+        }
+        if indent == 0 {
+            in_tests = false;
+            let (key, value) = trimmed.split_once(':')?;
+            let key = key.trim().trim_matches(['"', '\'']);
+            let key = key_alias(key).unwrap_or(key);
+            if key == "tests" {
+                in_tests = true;
+                item_indent = None;
+            }
+            let value = value.trim();
+            let empty = matches!(value, "" | "[]" | "~" | "null" | "Null" | "NULL")
+                || value.starts_with('#');
+            return Some((position, Line::Key { key, empty }));
+        }
+        if in_tests && (trimmed == "-" || trimmed.starts_with("- ")) {
+            item_indent.get_or_insert(indent);
+            if item_indent == Some(indent) {
+                return Some((position, Line::Test));
+            }
+        }
+        None
+    })
+}
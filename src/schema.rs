@@ -0,0 +1,13 @@
+//! JSON Schema of the normalized format.
+
+use anyhow::Result;
+use schemars::schema_for;
+
+use louis_migrate_yaml::normalized::TestSuite;
+
+pub fn run() -> Result<()> {
+    // a normalized file is a sequence of test suites
+    let schema = schema_for!(Vec<TestSuite>);
+    println!("{}", serde_json::to_string_pretty(&schema)?);
+    Ok(())
+}
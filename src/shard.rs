@@ -0,0 +1,135 @@
+//! Splitting converted files into shards for parallel test runs.
+//!
+//! A CI job per shard should take about as long as any other, so the files
+//! are balanced by the number of tests they contain rather than by their
+//! number. Files are assigned one by one, the largest first, to the shard
+//! with the fewest tests so far. Ties are broken by path and shard number,
+//! so the same files always give the same shards.
+
+use std::{
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use clap::Args;
+
+use crate::{diff::load_file, inputs, progress::Progress};
+
+#[derive(Args, Debug)]
+pub struct ShardArgs {
+    /// The converted files to split, directories to search for them or
+    /// glob patterns
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    #[command(flatten)]
+    inputs: inputs::InputOptions,
+    /// The number of shards
+    #[arg(long, value_name = "N")]
+    shards: usize,
+    /// Write the manifests `shard-1.txt`, `shard-2.txt`, ... listing the
+    /// files of each shard to DIR
+    #[arg(long, value_name = "DIR", default_value = ".")]
+    output_dir: PathBuf,
+    /// Copy the files of each shard into a directory `shard-1`, `shard-2`,
+    /// ... in the output directory instead of writing manifests. The
+    /// directories must not exist yet
+    #[arg(long)]
+    directories: bool,
+}
+
+#[derive(Debug, Default)]
+struct Shard {
+    files: Vec<PathBuf>,
+    tests: usize,
+}
+
+/// Distribute `files`, given with their number of tests, over `count`
+/// shards with about the same number of tests each.
+fn distribute(mut files: Vec<(PathBuf, usize)>, count: usize) -> Vec<Shard> {
+    files.sort_by(|(a, a_tests), (b, b_tests)| b_tests.cmp(a_tests).then_with(|| a.cmp(b)));
+    let mut shards: Vec<Shard> = (0..count).map(|_| Shard::default()).collect();
+    for (file, tests) in files {
+        // the first of the smallest shards
+        let shard = shards
+            .iter_mut()
+            .min_by_key(|shard| shard.tests)
+            .expect("there is at least one shard");
+        shard.files.push(file);
+        shard.tests += tests;
+    }
+    for shard in &mut shards {
+        shard.files.sort();
+    }
+    shards
+}
+
+/// Copy `files` into the new directory `dir`, keeping the file names.
+/// Files with the same name would overwrite each other, so they are
+/// rejected, as is an existing directory, which might hold files of
+/// another sharding.
+fn copy_files(files: &[PathBuf], dir: &Path) -> Result<()> {
+    if dir.exists() {
+        bail!(
+            "{} exists already, remove it or choose another output directory",
+            dir.display()
+        );
+    }
+    fs::create_dir_all(dir)?;
+    for file in files {
+        let Some(name) = file.file_name() else {
+            bail!("{} is not a file", file.display());
+        };
+        let target = dir.join(name);
+        if target.exists() {
+            bail!(
+                "{} and another file of the shard are both named {}",
+                file.display(),
+                name.to_string_lossy()
+            );
+        }
+        fs::copy(file, target)?;
+    }
+    Ok(())
+}
+
+pub fn run(args: &ShardArgs, quiet: bool) -> Result<()> {
+    if args.shards == 0 {
+        bail!("the number of shards has to be at least 1");
+    }
+    let files = inputs::expand(&args.files, &args.inputs, &["yaml", "json"])?;
+    let progress = Progress::new(files.len(), quiet);
+    let mut counted = Vec::new();
+    for file in files {
+        let tests = load_file(&file)?
+            .iter()
+            .map(|suite| suite.tests.len())
+            .sum();
+        progress.add_tests(tests);
+        progress.file_done();
+        counted.push((file, tests));
+    }
+    progress.finish();
+
+    fs::create_dir_all(&args.output_dir)?;
+    for (i, shard) in distribute(counted, args.shards).iter().enumerate() {
+        let name = format!("shard-{}", i + 1);
+        if args.directories {
+            copy_files(&shard.files, &args.output_dir.join(&name))?;
+        } else {
+            let mut manifest = String::new();
+            for file in &shard.files {
+                writeln!(manifest, "{}", file.display()).unwrap();
+            }
+            fs::write(args.output_dir.join(name.clone() + ".txt"), manifest)?;
+        }
+        println!(
+            "{}: {} file(s), {} test(s)",
+            name,
+            shard.files.len(),
+            shard.tests
+        );
+    }
+    Ok(())
+}
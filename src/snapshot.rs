@@ -0,0 +1,134 @@
+//! Snapshots of the conversion of a whole corpus.
+//!
+//! A snapshot is a manifest mapping every legacy file below a directory to
+//! the SHA-256 of its conversion. Comparing the snapshot taken after a
+//! change to the converter with one taken before shows which files convert
+//! differently, and that a refactoring changed nothing.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use louis_migrate_yaml::parse::{parse_file, ParseOptions};
+
+use crate::{
+    cache::{self, Cache},
+    inputs,
+    progress::Progress,
+    tables,
+};
+
+#[derive(Args, Debug)]
+pub struct SnapshotArgs {
+    /// The directory with the legacy yaml files, searched recursively
+    dir: PathBuf,
+    /// Write the manifest to PATH instead of stdout
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Compare with the manifest at PATH and list the files whose
+    /// conversion changed
+    #[arg(long, value_name = "PATH")]
+    compare: Option<PathBuf>,
+    #[command(flatten)]
+    inputs: inputs::InputOptions,
+    /// Keep the hashes of the conversions in the cache at PATH and only
+    /// convert the files whose contents are not in it
+    #[arg(long, value_name = "PATH", default_value = cache::DEFAULT_PATH)]
+    cache: PathBuf,
+    /// Convert every file, without reading or writing the cache
+    #[arg(long)]
+    no_cache: bool,
+}
+
+/// The hash of the conversion of every file, by its path relative to the
+/// snapshot directory. Files that fail to convert have no hash.
+type Manifest = BTreeMap<String, Option<String>>;
+
+/// The SHA-256 of the conversion of `path` with the default options and
+/// without provenance, taken from `cache` if the contents of `path` have
+/// been converted before.
+fn conversion_hash(path: &Path, cache: Option<&mut Cache>) -> Result<String> {
+    let input = cache::hash(fs::read(path)?);
+    if let Some(hash) = cache.as_ref().and_then(|cache| cache.get(&input)) {
+        log::info!(
+            "{}: unchanged, conversion taken from the cache",
+            path.display()
+        );
+        return Ok(hash.to_string());
+    }
+    let (mut test_suites, _) = parse_file(path, &ParseOptions::default())?;
+    tables::canonicalize(&mut test_suites);
+    let hash = cache::hash(serde_yaml::to_string(&test_suites)?);
+    if let Some(cache) = cache {
+        cache.insert(input, hash.clone());
+    }
+    Ok(hash)
+}
+
+/// Print the files whose hash differs between `old` and `new` and return
+/// their number.
+fn compare(old: &Manifest, new: &Manifest) -> usize {
+    let mut changes = 0;
+    for (file, hash) in new {
+        match old.get(file) {
+            Some(old_hash) if old_hash == hash => continue,
+            Some(_) => println!("changed: {}", file),
+            None => println!("added: {}", file),
+        }
+        changes += 1;
+    }
+    for file in old.keys().filter(|file| !new.contains_key(*file)) {
+        println!("removed: {}", file);
+        changes += 1;
+    }
+    changes
+}
+
+pub fn run(args: &SnapshotArgs, quiet: bool) -> Result<()> {
+    let files = inputs::expand(std::slice::from_ref(&args.dir), &args.inputs, &["yaml"])?;
+
+    let mut cache = if args.no_cache {
+        None
+    } else {
+        Some(Cache::load(&args.cache)?)
+    };
+    let progress = Progress::new(files.len(), quiet);
+    let mut manifest = Manifest::new();
+    for file in &files {
+        let hash = match conversion_hash(file, cache.as_mut()) {
+            Ok(hash) => Some(hash),
+            Err(e) => {
+                progress.error(file, "conversion-failed", format!("{:#}", e));
+                None
+            }
+        };
+        // the same keys on every platform
+        let name = file.strip_prefix(&args.dir).unwrap_or(file);
+        manifest.insert(name.to_string_lossy().replace('\\', "/"), hash);
+        progress.file_done();
+    }
+    progress.finish();
+    if let Some(cache) = &cache {
+        cache.save()?;
+    }
+
+    let json = serde_json::to_string_pretty(&manifest)? + "\n";
+    match (&args.output, &args.compare) {
+        (Some(path), _) => fs::write(path, json)?,
+        (None, None) => print!("{}", json),
+        (None, Some(_)) => {}
+    }
+
+    if let Some(path) = &args.compare {
+        let old: Manifest = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let changes = compare(&old, &manifest);
+        if changes > 0 {
+            bail!("the conversion of {} file(s) changed", changes);
+        }
+    }
+    Ok(())
+}
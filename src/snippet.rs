@@ -0,0 +1,76 @@
+//! Annotated source snippets for errors whose position is known.
+//!
+//! The offending line is shown with the bad token underlined. A message of
+//! the form "what went wrong, expected something" is split so that the
+//! expectation becomes the label of the underline.
+
+use std::{
+    io::{self, IsTerminal},
+    ops::Range,
+    path::Path,
+};
+
+use ariadne::{Config, IndexType, Label, Report, ReportKind, Source};
+
+use crate::diagnostics::Position;
+
+/// The byte offset of `position` in `source`, clamped to its end.
+fn offset(source: &str, position: Position) -> usize {
+    let line_start: usize = source
+        .split_inclusive('\n')
+        .take(position.line.saturating_sub(1))
+        .map(str::len)
+        .sum();
+    let line = &source[line_start..];
+    let column = line
+        .char_indices()
+        .nth(position.column.saturating_sub(1))
+        .map_or(line.len(), |(i, _)| i);
+    (line_start + column).min(source.len())
+}
+
+/// The span of the token starting at `offset`, at least one character
+/// unless the source ends there.
+fn token(source: &str, offset: usize) -> Range<usize> {
+    let rest = &source[offset..];
+    let len = rest
+        .find(|c: char| c.is_whitespace() || ",:[]{}".contains(c))
+        .filter(|&len| len > 0)
+        .or_else(|| rest.chars().next().map(char::len_utf8))
+        .unwrap_or(0);
+    offset..offset + len
+}
+
+/// Render `message` about `position` in the file at `path`, with colors if
+/// stderr is a terminal.
+pub fn render(path: &Path, source: &str, position: Position, message: &str) -> String {
+    let name = path.display().to_string();
+    let span = token(source, offset(source, position));
+    // the position is shown in the snippet already
+    let message = match message.rsplit_once(" at line ") {
+        Some((message, position))
+            if position
+                .split(" column ")
+                .all(|n| !n.is_empty() && n.bytes().all(|b| b.is_ascii_digit())) =>
+        {
+            message
+        }
+        _ => message,
+    };
+    let (message, hint) = match message.split_once(", expected ") {
+        Some((message, expected)) => (message.to_string(), format!("expected {}", expected)),
+        None => (message.to_string(), "here".to_string()),
+    };
+    let config = Config::default()
+        .with_index_type(IndexType::Byte)
+        .with_color(io::stderr().is_terminal());
+    let mut out = Vec::new();
+    Report::build(ReportKind::Error, (name.as_str(), span.clone()))
+        .with_config(config)
+        .with_message(message)
+        .with_label(Label::new((name.as_str(), span)).with_message(hint))
+        .finish()
+        .write((name.as_str(), Source::from(source)), &mut out)
+        .expect("writing to a Vec does not fail");
+    String::from_utf8_lossy(&out).into_owned()
+}
@@ -0,0 +1,127 @@
+//! Positions of the top-level keys and tests in a legacy file.
+//!
+//! Like the comments, positions are not reported by libyaml, so they are
+//! recovered with the line-based scan of the source in [`crate::scan`].
+//! Positions can then be looked up the way the linter locates its
+//! findings, as the n-th occurrence of a key and the n-th test of a block,
+//! or attached to the suites and tests parsed from the file.
+
+use std::collections::HashMap;
+
+use louis_migrate_yaml::{normalized::TestSuite, parse::key_alias};
+
+use crate::{
+    diagnostics::Position,
+    scan::{scan, Line},
+};
+
+#[derive(Debug)]
+struct TestsBlock {
+    tests: Vec<Position>,
+    /// Whether the `tests:` line has an empty value, see [`Line::Key`]
+    empty: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct SourceMap {
+    /// The positions of each top-level key, by its canonical spelling
+    keys: HashMap<String, Vec<Position>>,
+    /// The `tests` blocks in the order of the `tests` keys
+    blocks: Vec<TestsBlock>,
+}
+
+impl SourceMap {
+    pub fn new(source: &str) -> Self {
+        let mut map = SourceMap::default();
+        for (position, line) in scan(source) {
+            match line {
+                Line::Comment { .. } => (),
+                Line::Key { key, empty } => {
+                    map.keys.entry(key.to_string()).or_default().push(position);
+                    if key == "tests" {
+                        map.blocks.push(TestsBlock {
+                            tests: Vec::new(),
+                            empty,
+                        });
+                    }
+                }
+                Line::Test => {
+                    if let Some(block) = map.blocks.last_mut() {
+                        block.tests.push(position);
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    /// The position of the `occurrence`-th `key`, counting from 1.
+    pub fn key(&self, key: &str, occurrence: usize) -> Option<Position> {
+        self.keys.get(key)?.get(occurrence.checked_sub(1)?).copied()
+    }
+
+    /// The position of the `test`-th test of the `occurrence`-th `tests`
+    /// block, both counting from 1.
+    pub fn test(&self, occurrence: usize, test: usize) -> Option<Position> {
+        let block = self.blocks.get(occurrence.checked_sub(1)?)?;
+        block.tests.get(test.checked_sub(1)?).copied()
+    }
+
+    /// The position of the node at `path`, a path of the legacy parser such
+    /// as `tests#2[3][2].xfail`, as far as the scan found it: the test for a
+    /// path into a test, otherwise the top-level key the path starts with.
+    pub fn node(&self, path: &str) -> Option<Position> {
+        let end = path.find(['.', '[', '#']).unwrap_or(path.len());
+        let (key, rest) = path.split_at(end);
+        let key = key_alias(key).unwrap_or(key);
+        let (occurrence, rest) = match rest.strip_prefix('#') {
+            Some(rest) => {
+                let end = rest
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(rest.len());
+                (rest[..end].parse().ok()?, &rest[end..])
+            }
+            None => (1, rest),
+        };
+        let test = rest
+            .strip_prefix('[')
+            .and_then(|rest| rest.split_once(']'))
+            .and_then(|(index, _)| index.parse::<usize>().ok());
+        match test {
+            Some(index) if key == "tests" => self
+                .test(occurrence, index + 1)
+                .or_else(|| self.key(key, occurrence)),
+            _ => self.key(key, occurrence),
+        }
+    }
+
+    /// Record the positions of the `tests` blocks and tests in the suites
+    /// parsed from the source. The tests of a suite are only located if
+    /// all of them were found.
+    pub fn locate(&self, test_suites: &mut [TestSuite]) {
+        let positions = self.keys.get("tests").map(Vec::as_slice);
+        let blocks = self
+            .blocks
+            .iter()
+            .zip(positions.unwrap_or_default())
+            .filter(|(block, _)| !(block.empty && block.tests.is_empty()));
+        for (suite, (block, position)) in test_suites.iter_mut().zip(blocks) {
+            suite.source = Some((*position).into());
+            if block.tests.len() == suite.tests.len() {
+                for (test, position) in suite.tests.iter_mut().zip(&block.tests) {
+                    test.source = Some((*position).into());
+                }
+            }
+        }
+    }
+}
+
+/// Remove the positions recorded by [`SourceMap::locate`].
+pub fn forget(test_suites: &mut [TestSuite]) {
+    for suite in test_suites {
+        suite.source = None;
+        for test in &mut suite.tests {
+            test.source = None;
+        }
+    }
+}
@@ -0,0 +1,299 @@
+//! The tests as a table for spreadsheets, one row per test.
+//!
+//! Expected braille is easiest to review in a spreadsheet, so the tests can
+//! be written as CSV (or TSV) with the columns
+//! `table, direction, input, expected, xfail, reason`. The direction is the
+//! test mode of the suite, `xfail` is `true`, `false`, `forward` or
+//! `backward`. Fields are quoted where needed, so inputs with delimiters,
+//! quotes or line breaks survive the round trip through LibreOffice.
+//!
+//! `import-csv` reads such a table back after review. Consecutive rows with
+//! the same table and direction form a suite.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, bail, Context, Result};
+use clap::{Args, ValueEnum};
+use louis_migrate_yaml::{
+    format::{Formatted, Formatter},
+    normalized::{DirectionXfail, Meta, Table, Test, TestMode, TestSuite, Xfail},
+};
+
+use crate::emit::{self, Quote, TestStyle};
+
+#[derive(Args, Debug)]
+pub struct ImportCsvArgs {
+    /// The CSV file, with the columns written by --format csv
+    file: PathBuf,
+    /// Write the normalized yaml to FILE instead of stdout
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+    /// The field delimiter, by default a tab for .tsv files and a comma
+    /// otherwise
+    #[arg(long, value_name = "CHAR")]
+    delimiter: Option<char>,
+}
+
+pub const HEADER: [&str; 6] = ["table", "direction", "input", "expected", "xfail", "reason"];
+
+/// The delimiter byte for `delimiter`, which has to be ASCII.
+fn delimiter_byte(delimiter: char) -> Result<u8> {
+    match u8::try_from(delimiter) {
+        Ok(byte) if delimiter.is_ascii() => Ok(byte),
+        _ => bail!("the delimiter {:?} is not an ASCII character", delimiter),
+    }
+}
+
+/// A table as a single field: the file names separated by commas, the
+/// `key=value` pairs of a metadata query separated by commas, or the
+/// contents of an inline table.
+fn table_field(table: &Table) -> String {
+    match table {
+        Table::Single(path) => path.display().to_string(),
+        Table::List(paths) => paths
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(","),
+        Table::MetaData(query) => query
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(","),
+        Table::Inline(contents) => contents.clone(),
+    }
+}
+
+fn reason(xfail: &DirectionXfail) -> Option<&str> {
+    match xfail {
+        DirectionXfail::Reason(reason) => Some(reason),
+        DirectionXfail::Scalar(_) => None,
+    }
+}
+
+/// The `xfail` and `reason` fields of `xfail`. Different reasons for the
+/// two directions are both given, prefixed with their direction.
+fn xfail_fields(xfail: &Xfail) -> (&'static str, String) {
+    match xfail {
+        Xfail::Scalar(false) => ("false", String::new()),
+        Xfail::Scalar(true) => ("true", String::new()),
+        Xfail::Reason(reason) => ("true", reason.clone()),
+        Xfail::Map { forward, backward } => {
+            let field = match (forward.is_false(), backward.is_false()) {
+                (true, true) => "false",
+                (false, true) => "forward",
+                (true, false) => "backward",
+                (false, false) => "true",
+            };
+            let reason = match (reason(forward), reason(backward)) {
+                (Some(forward), Some(backward)) if forward != backward => {
+                    format!("forward: {}; backward: {}", forward, backward)
+                }
+                (Some(reason), _) | (None, Some(reason)) => reason.to_string(),
+                (None, None) => String::new(),
+            };
+            (field, reason)
+        }
+    }
+}
+
+/// Write the tests of `test_suites` as CSV with the given delimiter.
+fn to_string(test_suites: &[TestSuite], delimiter: u8) -> Result<String> {
+    let mut writer = csv::WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(Vec::new());
+    writer.write_record(HEADER)?;
+    for suite in test_suites {
+        let table = table_field(&suite.table);
+        let direction = suite
+            .mode
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default();
+        for test in &suite.tests {
+            let (xfail, reason) = xfail_fields(&test.xfail);
+            writer.write_record([
+                table.as_str(),
+                &direction,
+                &test.input,
+                &test.expected,
+                xfail,
+                &reason,
+            ])?;
+        }
+    }
+    Ok(String::from_utf8(writer.into_inner()?)?)
+}
+
+/// One row per test with the columns table, direction, input, expected,
+/// xfail and reason, for spreadsheets
+pub struct Csv {
+    pub delimiter: char,
+}
+
+impl Formatter for Csv {
+    fn name(&self) -> &'static str {
+        "csv"
+    }
+
+    fn extension(&self) -> Option<&'static str> {
+        match self.delimiter {
+            '\t' => Some("tsv"),
+            _ => Some("csv"),
+        }
+    }
+
+    fn format(&self, test_suites: &[TestSuite], _meta: &Meta) -> Result<Formatted> {
+        Ok(Formatted {
+            output: to_string(test_suites, delimiter_byte(self.delimiter)?)?,
+            warnings: Vec::new(),
+        })
+    }
+}
+
+/// Read a table field back, see [`table_field`]. Lists and metadata
+/// queries are told apart from single files by their separators.
+fn parse_table(field: &str) -> Table {
+    if field.contains('\n') {
+        Table::Inline(field.to_string())
+    } else if field.contains('=') {
+        let query = field
+            .split(',')
+            .map(|pair| match pair.split_once('=') {
+                Some((key, value)) => (key.trim().to_string(), value.trim().to_string()),
+                None => (pair.trim().to_string(), String::new()),
+            })
+            .collect();
+        Table::MetaData(query)
+    } else if field.contains(',') {
+        Table::List(field.split(',').map(|path| path.trim().into()).collect())
+    } else {
+        Table::Single(field.trim().into())
+    }
+}
+
+fn direction_xfail(fails: bool, reason: Option<&str>) -> DirectionXfail {
+    match reason {
+        Some(reason) if fails => DirectionXfail::Reason(reason.to_string()),
+        _ => DirectionXfail::Scalar(fails),
+    }
+}
+
+/// Read the `xfail` and `reason` fields back, see [`xfail_fields`].
+/// Spreadsheets tend to capitalize booleans, so case does not matter.
+fn parse_xfail(field: &str, reason: &str) -> Result<Xfail> {
+    let reason = (!reason.is_empty()).then_some(reason);
+    let xfail = match (field.trim().to_lowercase().as_str(), reason) {
+        ("" | "false", None) => Xfail::Scalar(false),
+        ("true", None) => Xfail::Scalar(true),
+        ("true", Some(reason)) => match reason
+            .strip_prefix("forward: ")
+            .and_then(|reasons| reasons.split_once("; backward: "))
+        {
+            Some((forward, backward)) => Xfail::Map {
+                forward: direction_xfail(true, Some(forward)),
+                backward: direction_xfail(true, Some(backward)),
+            },
+            None => Xfail::Reason(reason.to_string()),
+        },
+        ("forward", reason) => Xfail::Map {
+            forward: direction_xfail(true, reason),
+            backward: DirectionXfail::Scalar(false),
+        },
+        ("backward", reason) => Xfail::Map {
+            forward: DirectionXfail::Scalar(false),
+            backward: direction_xfail(true, reason),
+        },
+        ("" | "false", Some(_)) => bail!("a reason is given but xfail is {:?}", field),
+        _ => bail!(
+            "xfail {:?} is none of true, false, forward and backward",
+            field
+        ),
+    };
+    Ok(xfail)
+}
+
+fn delimiter_for(path: &Path, delimiter: Option<char>) -> Result<u8> {
+    match delimiter {
+        Some(delimiter) => delimiter_byte(delimiter),
+        None if path.extension().is_some_and(|ext| ext == "tsv") => Ok(b'\t'),
+        None => Ok(b','),
+    }
+}
+
+/// Read the suites from the CSV file at `path`.
+pub fn from_path(path: &Path, delimiter: u8) -> Result<Vec<TestSuite>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        // spreadsheets drop empty trailing fields
+        .flexible(true)
+        .from_reader(fs::File::open(path)?);
+    let header: Vec<String> = reader
+        .headers()?
+        .iter()
+        .map(|name| name.trim().to_lowercase())
+        .collect();
+    if header.iter().take(HEADER.len()).ne(HEADER.iter()) {
+        bail!(
+            "expected the columns {}, found {}",
+            HEADER.join(", "),
+            header.join(", ")
+        );
+    }
+
+    let mut test_suites: Vec<TestSuite> = Vec::new();
+    let mut current = None;
+    for (i, record) in reader.records().enumerate() {
+        let record = record?;
+        let field = |column| record.get(column).unwrap_or_default();
+        if record.iter().all(|field| field.trim().is_empty()) {
+            continue;
+        }
+        // the header is row 1
+        let row = i + 2;
+        let mode = TestMode::from_str(field(1).trim(), true)
+            .map_err(|e| anyhow!("row {}: unknown direction: {}", row, e))?;
+        let xfail = parse_xfail(field(4), field(5)).with_context(|| format!("row {}", row))?;
+        let test = Test {
+            input: field(2).to_string(),
+            expected: field(3).to_string(),
+            xfail,
+            ..Default::default()
+        };
+        let key = (field(0).to_string(), mode.clone());
+        match test_suites.last_mut() {
+            Some(suite) if current.as_ref() == Some(&key) => suite.tests.push(test),
+            _ => {
+                test_suites.push(TestSuite {
+                    name: None,
+                    comments: Vec::new(),
+                    tags: Default::default(),
+                    display_table: None,
+                    table: parse_table(&key.0),
+                    mode,
+                    translation_modes: Default::default(),
+                    variables: Default::default(),
+                    source: None,
+                    tests: vec![test],
+                });
+                current = Some(key);
+            }
+        }
+    }
+    Ok(test_suites)
+}
+
+pub fn import(args: &ImportCsvArgs) -> Result<()> {
+    let delimiter = delimiter_for(&args.file, args.delimiter)?;
+    let test_suites = from_path(&args.file, delimiter)
+        .with_context(|| format!("importing {}", args.file.display()))?;
+    let yaml = emit::to_string(&test_suites, TestStyle::Block, Quote::Minimal, false)?;
+    match &args.output {
+        Some(path) => fs::write(path, yaml)?,
+        None => print!("{}", yaml),
+    }
+    Ok(())
+}
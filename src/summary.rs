@@ -0,0 +1,430 @@
+//! A summary of the migration of a batch of legacy files.
+//!
+//! For every file the summary counts suites, tests and xfails, lists the
+//! test options it uses and reports what the conversion warned about,
+//! dropped or kept verbatim in `extra`. It is written as Markdown, e.g. for
+//! a tracking issue, or as HTML with a collapsible section per file.
+//!
+//! Over the whole corpus, `--group-by-locale` sorts the files by locale and
+//! adds a table with the counts of every locale, so language maintainers
+//! find their slice at a glance. The locale is the `language` or `locale`
+//! a suite queries its table by, or else the first word of the name of its
+//! first translation table, e.g. `en` for `en-ueb-g2.ctb` and `nemeth` for
+//! `nemeth.ctb`. Files without a table name get that of their own name.
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Result;
+use clap::{Args, ValueEnum};
+use louis_migrate_yaml::{
+    normalized::{Table, TestSuite},
+    parse::{parse_file, ParseOptions, UnknownKeys, Warning},
+};
+
+use crate::{inputs, progress::Progress, report::escape_xml};
+
+#[derive(Args, Debug)]
+pub struct SummaryArgs {
+    /// The legacy yaml files to summarize, directories to search for them
+    /// or glob patterns
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    #[command(flatten)]
+    inputs: inputs::InputOptions,
+    /// Format of the summary
+    #[arg(long, value_enum, default_value_t)]
+    format: SummaryFormat,
+    /// Write the summary to PATH instead of stdout
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// What to do with per-test options the converter does not know about
+    #[arg(long, value_enum, default_value_t = UnknownKeys::Warn)]
+    unknown_keys: UnknownKeys,
+    /// Sort the files by the locale of their tables and count suites,
+    /// tests and problems per locale
+    #[arg(long)]
+    group_by_locale: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SummaryFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+/// What the conversion of one file found.
+#[derive(Debug, Default)]
+struct FileSummary {
+    name: String,
+    locale: String,
+    suites: usize,
+    tests: usize,
+    xfails: usize,
+    /// How many tests use each option
+    options: BTreeMap<String, usize>,
+    /// How many tests keep each unknown option in `extra`
+    extra: BTreeMap<String, usize>,
+    /// Warnings about input that was left out of the conversion
+    dropped: Vec<String>,
+    warnings: Vec<String>,
+    error: Option<String>,
+}
+
+impl FileSummary {
+    /// Whether there is nothing to say about the file beyond its counts.
+    fn is_plain(&self) -> bool {
+        self.options.is_empty() && self.dropped.is_empty() && self.warnings.is_empty()
+    }
+
+    /// The number of warnings, dropped values and failed conversions.
+    fn problems(&self) -> usize {
+        self.dropped.len() + self.warnings.len() + usize::from(self.error.is_some())
+    }
+
+    fn new(path: &Path, result: Result<(Vec<TestSuite>, Vec<Warning>)>) -> Self {
+        let mut summary = FileSummary {
+            name: path.display().to_string(),
+            ..Default::default()
+        };
+        let (test_suites, warnings) = match result {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                summary.locale = name_locale(path);
+                summary.error = Some(format!("{:#}", e));
+                return summary;
+            }
+        };
+        summary.locale = test_suites
+            .iter()
+            .find_map(table_locale)
+            .unwrap_or_else(|| name_locale(path));
+        summary.suites = test_suites.len();
+        for test in test_suites.iter().flat_map(|suite| &suite.tests) {
+            summary.tests += 1;
+            let options = [
+                ("name", test.name.is_some()),
+                ("xfail", !test.xfail.is_false()),
+                ("mode", !test.mode.is_empty()),
+                ("inputClass", !test.input_class.is_empty()),
+                ("outputClass", !test.output_class.is_empty()),
+            ];
+            for (option, _) in options.iter().filter(|(_, used)| *used) {
+                *summary.options.entry(option.to_string()).or_default() += 1;
+            }
+            for key in test.extra.keys() {
+                *summary.options.entry(key.clone()).or_default() += 1;
+                *summary.extra.entry(key.clone()).or_default() += 1;
+            }
+            if !test.xfail.is_false() {
+                summary.xfails += 1;
+            }
+        }
+        for warning in warnings {
+            match warning.loss {
+                Some(_) => summary.dropped.push(warning.message),
+                None => summary.warnings.push(warning.message),
+            }
+        }
+        summary
+    }
+}
+
+/// The first word of the file name `name`, lowercased, without the `hyph_`
+/// prefix of hyphenation dictionaries.
+fn name_word(name: &str) -> Option<String> {
+    let name = name.strip_prefix("hyph_").unwrap_or(name);
+    let word = name
+        .split(['-', '_', '.'])
+        .next()
+        .filter(|word| !word.is_empty())?;
+    Some(word.to_lowercase())
+}
+
+/// The locale of the file at `path`, from its name.
+fn name_locale(path: &Path) -> String {
+    path.file_name()
+        .and_then(|name| name_word(&name.to_string_lossy()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// The locale of the tables of `suite`, if it can be told.
+fn table_locale(suite: &TestSuite) -> Option<String> {
+    let names: Vec<String> = match &suite.table {
+        Table::MetaData(query) => {
+            return query
+                .get("language")
+                .or_else(|| query.get("locale"))
+                .and_then(|language| name_word(language));
+        }
+        // a single table may be several names separated by whitespace
+        Table::Single(path) => path
+            .to_string_lossy()
+            .split_whitespace()
+            .map(str::to_string)
+            .collect(),
+        Table::List(paths) => paths
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect(),
+        Table::Inline(..) => return None,
+    };
+    names
+        .iter()
+        .map(Path::new)
+        .find(|path| path.extension().is_none_or(|extension| extension != "dis"))
+        .and_then(|path| path.file_name())
+        .and_then(|name| name_word(&name.to_string_lossy()))
+}
+
+/// The counts of all files of one locale.
+#[derive(Debug, Default)]
+struct LocaleSummary {
+    files: usize,
+    suites: usize,
+    tests: usize,
+    xfails: usize,
+    problems: usize,
+}
+
+/// The counts of the files of `summaries` by locale.
+fn by_locale(summaries: &[FileSummary]) -> BTreeMap<&str, LocaleSummary> {
+    let mut locales: BTreeMap<&str, LocaleSummary> = BTreeMap::new();
+    for s in summaries {
+        let locale = locales.entry(&s.locale).or_default();
+        locale.files += 1;
+        locale.suites += s.suites;
+        locale.tests += s.tests;
+        locale.xfails += s.xfails;
+        locale.problems += s.problems();
+    }
+    locales
+}
+
+/// `counts` as a comma separated list of code spans with their counts.
+fn list_counts(counts: &BTreeMap<String, usize>, code: impl Fn(&str) -> String) -> String {
+    counts
+        .iter()
+        .map(|(key, count)| format!("{} ({})", code(key), count))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn markdown(summaries: &[FileSummary], group_by_locale: bool) -> String {
+    let code = |s: &str| format!("`{}`", s);
+    let mut md = String::new();
+    writeln!(md, "# Migration summary\n").unwrap();
+    if group_by_locale {
+        writeln!(
+            md,
+            "| Locale | Files | Suites | Tests | Xfails | Problems |"
+        )
+        .unwrap();
+        writeln!(
+            md,
+            "|--------|------:|-------:|------:|-------:|---------:|"
+        )
+        .unwrap();
+        for (locale, l) in by_locale(summaries) {
+            writeln!(
+                md,
+                "| {} | {} | {} | {} | {} | {} |",
+                locale, l.files, l.suites, l.tests, l.xfails, l.problems
+            )
+            .unwrap();
+        }
+        writeln!(md).unwrap();
+    }
+    writeln!(
+        md,
+        "| File | Suites | Tests | Xfails | Dropped | Warnings |"
+    )
+    .unwrap();
+    writeln!(
+        md,
+        "|------|-------:|------:|-------:|--------:|---------:|"
+    )
+    .unwrap();
+    for s in summaries {
+        match &s.error {
+            Some(_) => writeln!(md, "| {} | failed | | | | |", s.name).unwrap(),
+            None => writeln!(
+                md,
+                "| {} | {} | {} | {} | {} | {} |",
+                s.name,
+                s.suites,
+                s.tests,
+                s.xfails,
+                s.dropped.len(),
+                s.warnings.len()
+            )
+            .unwrap(),
+        }
+    }
+    for s in summaries {
+        writeln!(md, "\n## {}\n", s.name).unwrap();
+        if let Some(error) = &s.error {
+            writeln!(md, "Conversion failed: {}", error).unwrap();
+            continue;
+        }
+        if s.is_plain() {
+            writeln!(md, "No test options, nothing dropped.").unwrap();
+        }
+        if !s.options.is_empty() {
+            writeln!(md, "- Options: {}", list_counts(&s.options, code)).unwrap();
+        }
+        if !s.extra.is_empty() {
+            writeln!(
+                md,
+                "- Preserved in `extra`: {}",
+                list_counts(&s.extra, code)
+            )
+            .unwrap();
+        }
+        for (heading, messages) in [("Dropped", &s.dropped), ("Warnings", &s.warnings)] {
+            if !messages.is_empty() {
+                writeln!(md, "- {}:", heading).unwrap();
+                for message in messages {
+                    writeln!(md, "  - {}", message).unwrap();
+                }
+            }
+        }
+    }
+    md
+}
+
+fn html(summaries: &[FileSummary], group_by_locale: bool) -> String {
+    let code = |s: &str| format!("<code>{}</code>", escape_xml(s));
+    let mut html = String::new();
+    writeln!(html, "<!DOCTYPE html>").unwrap();
+    writeln!(html, "<html>\n<head>\n<meta charset=\"utf-8\">").unwrap();
+    writeln!(html, "<title>Migration summary</title>\n</head>\n<body>").unwrap();
+    writeln!(html, "<h1>Migration summary</h1>").unwrap();
+    if group_by_locale {
+        writeln!(html, "<table>").unwrap();
+        writeln!(
+            html,
+            "<tr><th>Locale</th><th>Files</th><th>Suites</th><th>Tests</th>\
+             <th>Xfails</th><th>Problems</th></tr>"
+        )
+        .unwrap();
+        for (locale, l) in by_locale(summaries) {
+            writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape_xml(locale),
+                l.files,
+                l.suites,
+                l.tests,
+                l.xfails,
+                l.problems
+            )
+            .unwrap();
+        }
+        writeln!(html, "</table>").unwrap();
+    }
+    writeln!(html, "<table>").unwrap();
+    writeln!(
+        html,
+        "<tr><th>File</th><th>Suites</th><th>Tests</th><th>Xfails</th>\
+         <th>Dropped</th><th>Warnings</th></tr>"
+    )
+    .unwrap();
+    for s in summaries {
+        let name = escape_xml(&s.name);
+        match &s.error {
+            Some(_) => writeln!(
+                html,
+                "<tr><td>{}</td><td colspan=\"5\">failed</td></tr>",
+                name
+            ),
+            None => writeln!(
+                html,
+                "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                name,
+                s.suites,
+                s.tests,
+                s.xfails,
+                s.dropped.len(),
+                s.warnings.len()
+            ),
+        }
+        .unwrap();
+    }
+    writeln!(html, "</table>").unwrap();
+    for s in summaries {
+        writeln!(
+            html,
+            "<details>\n<summary>{}</summary>",
+            escape_xml(&s.name)
+        )
+        .unwrap();
+        if let Some(error) = &s.error {
+            writeln!(html, "<p>Conversion failed: {}</p>", escape_xml(error)).unwrap();
+        } else if s.is_plain() {
+            writeln!(html, "<p>No test options, nothing dropped.</p>").unwrap();
+        } else {
+            writeln!(html, "<ul>").unwrap();
+            if !s.options.is_empty() {
+                writeln!(html, "<li>Options: {}</li>", list_counts(&s.options, code)).unwrap();
+            }
+            if !s.extra.is_empty() {
+                writeln!(
+                    html,
+                    "<li>Preserved in <code>extra</code>: {}</li>",
+                    list_counts(&s.extra, code)
+                )
+                .unwrap();
+            }
+            for (heading, messages) in [("Dropped", &s.dropped), ("Warnings", &s.warnings)] {
+                if !messages.is_empty() {
+                    writeln!(html, "<li>{}:\n<ul>", heading).unwrap();
+                    for message in messages {
+                        writeln!(html, "<li>{}</li>", escape_xml(message)).unwrap();
+                    }
+                    writeln!(html, "</ul>\n</li>").unwrap();
+                }
+            }
+            writeln!(html, "</ul>").unwrap();
+        }
+        writeln!(html, "</details>").unwrap();
+    }
+    writeln!(html, "</body>\n</html>").unwrap();
+    html
+}
+
+pub fn run(args: &SummaryArgs, quiet: bool) -> Result<()> {
+    let options = ParseOptions {
+        unknown_keys: args.unknown_keys,
+        ..Default::default()
+    };
+    let files = inputs::expand(&args.files, &args.inputs, &["yaml"])?;
+    let progress = Progress::new(files.len(), quiet);
+    let mut summaries = Vec::new();
+    for file in &files {
+        let summary = FileSummary::new(file, parse_file(file, &options));
+        progress.add_tests(summary.tests);
+        progress.add_warnings(summary.dropped.len() + summary.warnings.len());
+        progress.file_done();
+        summaries.push(summary);
+    }
+    progress.finish();
+    if args.group_by_locale {
+        summaries.sort_by(|a, b| a.locale.cmp(&b.locale).then_with(|| a.name.cmp(&b.name)));
+    }
+
+    let report = match args.format {
+        SummaryFormat::Markdown => markdown(&summaries, args.group_by_locale),
+        SummaryFormat::Html => html(&summaries, args.group_by_locale),
+    };
+    match &args.output {
+        Some(path) => fs::write(path, report)?,
+        None => print!("{}", report),
+    }
+    Ok(())
+}
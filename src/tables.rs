@@ -0,0 +1,201 @@
+//! Transformations of the table references of test suites.
+
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Component, Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, Result};
+
+use louis_migrate_yaml::normalized::{Table, TestSuite};
+
+/// A table path prefix mapping given on the command line as `OLD=NEW`.
+#[derive(Debug, Clone)]
+pub struct PrefixMapping {
+    old: PathBuf,
+    new: PathBuf,
+}
+
+impl FromStr for PrefixMapping {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once('=') {
+            Some((old, new)) => Ok(PrefixMapping {
+                old: canonical_path(Path::new(old)),
+                new: PathBuf::from(new),
+            }),
+            None => Err(format!("expected OLD=NEW, got {:?}", s)),
+        }
+    }
+}
+
+/// Lexically canonicalize `path`, i.e. drop `.` components and resolve
+/// `..` against preceding components where possible. The file system is
+/// not consulted, so symbolic links are not resolved.
+pub fn canonical_path(path: &Path) -> PathBuf {
+    let mut canonical = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir
+                if matches!(
+                    canonical.components().next_back(),
+                    Some(Component::Normal(..))
+                ) =>
+            {
+                canonical.pop();
+            }
+            component => canonical.push(component),
+        }
+    }
+    if canonical.as_os_str().is_empty() {
+        canonical.push(".");
+    }
+    canonical
+}
+
+/// Canonicalize the table and display table paths of all suites and remove
+/// duplicates from table lists, keeping the first occurrence. Returns a
+/// warning for every table that was listed more than once.
+pub fn canonicalize(test_suites: &mut [TestSuite]) -> Vec<String> {
+    let mut warnings = Vec::new();
+    for (i, suite) in test_suites.iter_mut().enumerate() {
+        if let Some(display_table) = &mut suite.display_table {
+            *display_table = canonical_path(display_table);
+        }
+        match &mut suite.table {
+            Table::Single(path) => *path = canonical_path(path),
+            Table::List(paths) => {
+                let mut seen = BTreeSet::new();
+                let mut duplicates = BTreeSet::new();
+                paths.retain_mut(|path| {
+                    *path = canonical_path(path);
+                    if seen.insert(path.clone()) {
+                        true
+                    } else {
+                        duplicates.insert(path.clone());
+                        false
+                    }
+                });
+                for duplicate in duplicates {
+                    warnings.push(format!(
+                        "suite {}: table {} is listed more than once",
+                        i + 1,
+                        duplicate.display()
+                    ));
+                }
+            }
+            Table::MetaData(..) | Table::Inline(..) => {}
+        }
+    }
+    warnings
+}
+
+/// Split table paths naming several tables separated by whitespace, the
+/// way older test files listed tables, into lists of tables. Returns the
+/// number of suites whose tables were split.
+pub fn split_scalars(test_suites: &mut [TestSuite]) -> usize {
+    let mut split = 0;
+    for suite in test_suites.iter_mut() {
+        let paths = match &suite.table {
+            Table::Single(path) => std::slice::from_ref(path),
+            Table::List(paths) => paths.as_slice(),
+            Table::MetaData(..) | Table::Inline(..) => continue,
+        };
+        let tables: Vec<PathBuf> = paths
+            .iter()
+            .flat_map(|path| {
+                let path = path.to_string_lossy();
+                path.split_whitespace().map(PathBuf::from).collect::<Vec<_>>()
+            })
+            .collect();
+        if tables.len() > paths.len() {
+            suite.table = Table::List(tables);
+            split += 1;
+        }
+    }
+    split
+}
+
+/// Rewrite `path` with the first mapping whose prefix matches. Prefixes
+/// match whole path components only.
+fn map_path(path: &Path, mappings: &[PrefixMapping]) -> Option<PathBuf> {
+    mappings.iter().find_map(|mapping| {
+        let rest = path.strip_prefix(&mapping.old).ok()?;
+        // joining an empty path would add a trailing separator
+        if rest.as_os_str().is_empty() {
+            Some(mapping.new.clone())
+        } else {
+            Some(mapping.new.join(rest))
+        }
+    })
+}
+
+/// Rewrite the table and display table paths of all suites. Returns the
+/// distinct rewrites that were performed.
+pub fn map_prefixes(
+    test_suites: &mut [TestSuite],
+    mappings: &[PrefixMapping],
+) -> BTreeSet<(PathBuf, PathBuf)> {
+    let mut rewrites = BTreeSet::new();
+    let mut rewrite = |path: &mut PathBuf| {
+        if let Some(mapped) = map_path(path, mappings) {
+            rewrites.insert((path.clone(), mapped.clone()));
+            *path = mapped;
+        }
+    };
+    for suite in test_suites.iter_mut() {
+        if let Some(display_table) = &mut suite.display_table {
+            rewrite(display_table);
+        }
+        match &mut suite.table {
+            Table::Single(path) => rewrite(path),
+            Table::List(paths) => paths.iter_mut().for_each(&mut rewrite),
+            Table::MetaData(..) | Table::Inline(..) => {}
+        }
+    }
+    rewrites
+}
+
+/// The contents of the table at `path` in `tables_dir`, or `None` if it is
+/// larger than `max_size` bytes or includes other tables, as the inlined
+/// table would not be self-contained.
+fn read_small_table(tables_dir: &Path, path: &Path, max_size: u64) -> Result<Option<String>> {
+    let file = tables_dir.join(path);
+    let context = || format!("Could not read table {}", file.display());
+    if fs::metadata(&file).with_context(context)?.len() > max_size {
+        return Ok(None);
+    }
+    let contents = fs::read_to_string(&file).with_context(context)?;
+    let includes = contents
+        .lines()
+        .any(|line| line.trim_start().starts_with("include"));
+    Ok((!includes).then_some(contents))
+}
+
+/// Replace the table references of all suites whose tables are at most
+/// `max_size` bytes by the contents of the tables. A list of tables is
+/// only inlined if all of them are small, and is inlined as their
+/// concatenation. Returns the number of suites whose tables were inlined.
+pub fn inline(test_suites: &mut [TestSuite], tables_dir: &Path, max_size: u64) -> Result<usize> {
+    let mut inlined = 0;
+    for suite in test_suites.iter_mut() {
+        let paths = match &suite.table {
+            Table::Single(path) => vec![path.clone()],
+            Table::List(paths) => paths.clone(),
+            Table::MetaData(..) | Table::Inline(..) => continue,
+        };
+        let contents = paths
+            .iter()
+            .map(|path| read_small_table(tables_dir, path, max_size))
+            .collect::<Result<Option<Vec<String>>>>()?;
+        if let Some(contents) = contents {
+            suite.table = Table::Inline(contents.concat());
+            inlined += 1;
+        }
+    }
+    Ok(inlined)
+}
@@ -0,0 +1,87 @@
+//! Tags of the suites and tests, for the harness to select tests by.
+//!
+//! `--tag` gives every suite of the converted file a tag, `--tag-file-name`
+//! the name of the file without its extension. With `--comment-tags`, a
+//! comment like `# tags: regression, issue-1234` before a suite or test
+//! gives it the listed tags instead of being kept as a comment. The tags of
+//! a suite apply to all of its tests, so they are not repeated there.
+//!
+//! A tag is a word without whitespace or commas, so that a list of tags can
+//! be written in a comment or on the command line without quoting.
+
+use std::{collections::BTreeSet, path::Path};
+
+use louis_migrate_yaml::normalized::TestSuite;
+
+fn is_tag_separator(c: char) -> bool {
+    c == ',' || c.is_whitespace()
+}
+
+/// Check a tag given on the command line.
+pub fn parse_tag(tag: &str) -> Result<String, String> {
+    if tag.is_empty() {
+        Err("a tag cannot be empty".to_string())
+    } else if tag.contains(is_tag_separator) {
+        Err(format!("{:?} contains whitespace or a comma", tag))
+    } else {
+        Ok(tag.to_string())
+    }
+}
+
+/// The tag for the file at `path`: its name without the extension, with
+/// whitespace and commas replaced by dashes.
+pub fn file_name_tag(path: &Path) -> Option<String> {
+    let stem = path.file_stem()?.to_string_lossy();
+    let tag: String = stem
+        .chars()
+        .map(|c| if is_tag_separator(c) { '-' } else { c })
+        .collect();
+    (!tag.is_empty()).then_some(tag)
+}
+
+/// Add `tags` to every suite of `test_suites`.
+pub fn add(test_suites: &mut [TestSuite], tags: &[String]) {
+    for suite in test_suites {
+        suite.tags.extend(tags.iter().cloned());
+    }
+}
+
+/// The tags listed in `comment`, if it is a `tags:` comment. The list may
+/// be separated by commas or whitespace and enclosed in brackets.
+fn comment_tags(comment: &str) -> Option<impl Iterator<Item = &str>> {
+    let list = comment.trim_start().strip_prefix("tags:")?.trim();
+    let list = list
+        .strip_prefix('[')
+        .and_then(|list| list.strip_suffix(']'))
+        .unwrap_or(list);
+    Some(list.split(is_tag_separator).filter(|tag| !tag.is_empty()))
+}
+
+/// Move the tags listed in `comments` into `tags`, leaving the other
+/// comments.
+fn take_comment_tags(comments: &mut Vec<String>, tags: &mut BTreeSet<String>) {
+    comments.retain(|comment| match comment_tags(comment) {
+        Some(listed) => {
+            tags.extend(listed.map(str::to_string));
+            false
+        }
+        None => true,
+    });
+}
+
+/// Turn the `tags:` comments attached to the suites and tests of
+/// `test_suites` into their tags. Returns the number of tags found.
+pub fn from_comments(test_suites: &mut [TestSuite]) -> usize {
+    let mut found = 0;
+    for suite in test_suites {
+        let before = suite.tags.len();
+        take_comment_tags(&mut suite.comments, &mut suite.tags);
+        found += suite.tags.len() - before;
+        for test in &mut suite.tests {
+            let before = test.tags.len();
+            take_comment_tags(&mut test.comments, &mut test.tags);
+            found += test.tags.len() - before;
+        }
+    }
+    found
+}
@@ -0,0 +1,163 @@
+//! Templates for the paths of converted files.
+//!
+//! Batch conversions often have to follow a naming convention of the
+//! project they write into, e.g. `{stem}.norm.{ext}` next to the legacy
+//! file or `{table_stem}/{mode}/{stem}.{ext}`. `--output-template` takes
+//! such a template, relative to the output directory if one is given. The
+//! placeholders are
+//!
+//! - `{dir}`: the directory of the legacy file
+//! - `{stem}`: the name of the legacy file without its extension
+//! - `{ext}`: the extension of the output format, that of the legacy file
+//!   for yaml
+//! - `{mode}`: the test mode of the suites, `mixed` if they differ
+//! - `{table_stem}`: the name of the first table of the first suite without
+//!   its extension, `inline` or `query` for tables given otherwise
+//!
+//! Both are `empty` for a file without suites.
+//!
+//! `{mode}` and `{table_stem}` depend on the suites, so they are not known
+//! when streaming.
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{anyhow, Result};
+use clap::ValueEnum;
+use louis_migrate_yaml::normalized::{Table, TestSuite};
+
+const PLACEHOLDERS: [&str; 5] = ["dir", "stem", "ext", "mode", "table_stem"];
+
+/// A part of a template
+#[derive(Debug, Clone)]
+enum Part {
+    Text(String),
+    Placeholder(&'static str),
+}
+
+#[derive(Debug, Clone)]
+pub struct OutputTemplate(Vec<Part>);
+
+impl FromStr for OutputTemplate {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let Some(end) = rest[start..].find('}') else {
+                return Err(format!("unclosed {{ in {:?}", s));
+            };
+            let name = &rest[start + 1..start + end];
+            let placeholder = PLACEHOLDERS
+                .into_iter()
+                .find(|placeholder| *placeholder == name)
+                .ok_or_else(|| {
+                    format!(
+                        "unknown placeholder {{{}}}, expected one of {}",
+                        name,
+                        PLACEHOLDERS.map(|name| format!("{{{}}}", name)).join(", ")
+                    )
+                })?;
+            parts.push(Part::Text(rest[..start].to_string()));
+            parts.push(Part::Placeholder(placeholder));
+            rest = &rest[start + end + 1..];
+        }
+        if rest.contains('}') {
+            return Err(format!("unopened }} in {:?}", s));
+        }
+        parts.push(Part::Text(rest.to_string()));
+        Ok(OutputTemplate(parts))
+    }
+}
+
+/// The common test mode of `test_suites`.
+fn mode(test_suites: &[TestSuite]) -> String {
+    let mut modes = test_suites.iter().map(|suite| &suite.mode);
+    match modes.next() {
+        Some(first) if modes.all(|mode| mode == first) => first
+            .to_possible_value()
+            .map(|value| value.get_name().to_string())
+            .unwrap_or_default(),
+        Some(_) => "mixed".to_string(),
+        None => "empty".to_string(),
+    }
+}
+
+/// The stem of the first table of `test_suites`.
+fn table_stem(test_suites: &[TestSuite]) -> String {
+    let path = match test_suites.first().map(|suite| &suite.table) {
+        Some(Table::Single(path)) => path,
+        Some(Table::List(paths)) if !paths.is_empty() => &paths[0],
+        Some(Table::MetaData(..)) => return "query".to_string(),
+        Some(Table::Inline(..)) => return "inline".to_string(),
+        Some(Table::List(..)) | None => return "empty".to_string(),
+    };
+    path.file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+impl OutputTemplate {
+    /// Whether the template has placeholders that depend on the suites.
+    pub fn needs_suites(&self) -> bool {
+        self.0.iter().any(|part| {
+            matches!(
+                part,
+                Part::Placeholder(placeholder) if ["mode", "table_stem"].contains(placeholder)
+            )
+        })
+    }
+
+    /// The path of the conversion of `yaml` to `extension`, or to yaml if
+    /// it is `None`. `test_suites` may only be `None` if the template does
+    /// not [need them](Self::needs_suites).
+    pub fn render(
+        &self,
+        yaml: &Path,
+        extension: Option<&str>,
+        test_suites: Option<&[TestSuite]>,
+    ) -> Result<PathBuf> {
+        let mut path = String::new();
+        for part in &self.0 {
+            match part {
+                Part::Text(text) => path.push_str(text),
+                Part::Placeholder("dir") => {
+                    let dir = yaml.parent().unwrap_or(Path::new(""));
+                    match dir.to_str() {
+                        Some("") => path.push('.'),
+                        _ => path.push_str(&dir.to_string_lossy()),
+                    }
+                }
+                Part::Placeholder("stem") => {
+                    let stem = yaml
+                        .file_stem()
+                        .ok_or_else(|| anyhow!("{} is not a file", yaml.display()))?;
+                    path.push_str(&stem.to_string_lossy());
+                }
+                Part::Placeholder("ext") => match extension {
+                    Some(extension) => path.push_str(extension),
+                    None => match yaml.extension() {
+                        Some(extension) => path.push_str(&extension.to_string_lossy()),
+                        None => path.push_str("yaml"),
+                    },
+                },
+                Part::Placeholder(placeholder) => {
+                    let test_suites = test_suites.ok_or_else(|| {
+                        anyhow!(
+                            "{{{}}} is not known before the suites are parsed",
+                            placeholder
+                        )
+                    })?;
+                    match *placeholder {
+                        "mode" => path.push_str(&mode(test_suites)),
+                        _ => path.push_str(&table_stem(test_suites)),
+                    }
+                }
+            }
+        }
+        Ok(PathBuf::from(path))
+    }
+}
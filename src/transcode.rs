@@ -0,0 +1,141 @@
+//! Reading legacy files that are not plain UTF-8.
+//!
+//! The parser only accepts UTF-8, but a few old test files are UTF-16 or
+//! start with a byte order mark. Such files are decoded to UTF-8 before
+//! they are parsed.
+//!
+//! Big UTF-8 files can be mapped into memory instead, so that the parser
+//! reads them where they are rather than from a copy in a buffer. Files
+//! that may change while they are in use must not be mapped, as the map
+//! would change with them.
+
+use std::{fs, ops::Deref, path::Path};
+
+use anyhow::{anyhow, Result};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, UTF_8};
+use memmap2::Mmap;
+use sha2::{Digest, Sha256};
+
+/// The size from which on files are mapped into memory even if that was
+/// not asked for
+pub const MMAP_THRESHOLD: u64 = 64 * 1024 * 1024;
+
+/// Which files are mapped into memory rather than read
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mapping {
+    /// Files of at least [`MMAP_THRESHOLD`] bytes
+    #[default]
+    Large,
+    /// Every file that is not empty
+    Always,
+    /// No file, for files that may change while they are in use
+    Never,
+}
+
+/// The encoding of `bytes` and the length of its byte order mark
+fn detect(bytes: &[u8]) -> (&'static Encoding, usize) {
+    match Encoding::for_bom(bytes) {
+        Some(found) => found,
+        None => match bytes {
+            [0, b, ..] if *b != 0 => (UTF_16BE, 0),
+            [b, 0, ..] if *b != 0 => (UTF_16LE, 0),
+            _ => (UTF_8, 0),
+        },
+    }
+}
+
+/// Decode `bytes` to UTF-8. The encoding is taken from the byte order mark
+/// or, lacking one, guessed from the null bytes around the first character
+/// as the YAML spec describes. Returns the text and, if it had to be
+/// transcoded, the name of the original encoding.
+pub fn decode(bytes: &[u8]) -> Result<(String, Option<&'static str>)> {
+    let (encoding, bom_length) = detect(bytes);
+    let text = encoding
+        .decode_without_bom_handling_and_without_replacement(&bytes[bom_length..])
+        .ok_or_else(|| anyhow!("file is not valid {}", encoding.name()))?;
+    let transcoded_from = (encoding != UTF_8).then(|| encoding.name());
+    Ok((text.into_owned(), transcoded_from))
+}
+
+/// Read the file at `path` as UTF-8, see [`decode`].
+pub fn read_to_string(path: &Path) -> Result<(String, Option<&'static str>)> {
+    decode(&fs::read(path)?)
+}
+
+/// The contents of a file as UTF-8, either mapped into memory or decoded
+/// into a string
+pub enum Source {
+    /// A mapped file that is valid UTF-8 without a byte order mark
+    Mapped(Mmap),
+    Decoded(String),
+}
+
+impl Deref for Source {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            // SAFETY: only maps that are valid UTF-8 are wrapped, see `read`
+            Source::Mapped(map) => unsafe { std::str::from_utf8_unchecked(map) },
+            Source::Decoded(text) => text,
+        }
+    }
+}
+
+/// Read the file at `path` as UTF-8 like [`read_to_string`]. Depending on
+/// `mapping` it is mapped into memory instead of read, unless it has to be
+/// transcoded. A mapped file must not be changed while the source is in
+/// use.
+pub fn read(path: &Path, mapping: Mapping) -> Result<(Source, Option<&'static str>)> {
+    read_with(path, mapping, |_| ())
+}
+
+/// Read the file at `path` like [`read`] and also return the SHA-256 of its
+/// bytes, hex encoded, computed from the same read.
+pub fn read_hashed(
+    path: &Path,
+    mapping: Mapping,
+) -> Result<(Source, Option<&'static str>, String)> {
+    let mut sha256 = String::new();
+    let (source, transcoded_from) = read_with(path, mapping, |bytes| {
+        sha256 = format!("{:x}", Sha256::digest(bytes));
+    })?;
+    Ok((source, transcoded_from, sha256))
+}
+
+/// Read the file at `path` like [`read`], passing its bytes to `raw` before
+/// they are decoded.
+fn read_with(
+    path: &Path,
+    mapping: Mapping,
+    raw: impl FnOnce(&[u8]),
+) -> Result<(Source, Option<&'static str>)> {
+    let file = fs::File::open(path)?;
+    let length = file.metadata()?.len();
+    let map = match mapping {
+        Mapping::Always => true,
+        Mapping::Large => length >= MMAP_THRESHOLD,
+        Mapping::Never => false,
+    };
+    // an empty file cannot be mapped everywhere
+    if length == 0 || !map {
+        let bytes = fs::read(path)?;
+        raw(&bytes);
+        let (text, transcoded_from) = decode(&bytes)?;
+        return Ok((Source::Decoded(text), transcoded_from));
+    }
+    // SAFETY: the map is only read, and the file is expected not to change
+    // while it is converted, see above
+    let map = unsafe { Mmap::map(&file)? };
+    raw(&map);
+    match detect(&map) {
+        (encoding, 0) if encoding == UTF_8 => {
+            std::str::from_utf8(&map).map_err(|_| anyhow!("file is not valid UTF-8"))?;
+            Ok((Source::Mapped(map), None))
+        }
+        _ => {
+            let (text, transcoded_from) = decode(&map)?;
+            Ok((Source::Decoded(text), transcoded_from))
+        }
+    }
+}
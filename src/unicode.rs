@@ -0,0 +1,126 @@
+//! Unicode normalization of test strings, and the characters that make
+//! them hard to review.
+
+use clap::ValueEnum;
+use unicode_normalization::UnicodeNormalization;
+
+use louis_migrate_yaml::normalized::TestSuite;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Normalization {
+    /// Canonical composition
+    Nfc,
+    /// Canonical decomposition
+    Nfd,
+    /// Leave strings as they are
+    #[default]
+    None,
+}
+
+impl Normalization {
+    fn apply(self, s: &str) -> String {
+        match self {
+            Normalization::Nfc => s.nfc().collect(),
+            Normalization::Nfd => s.nfd().collect(),
+            Normalization::None => s.to_string(),
+        }
+    }
+}
+
+/// Characters that are invisible or change the direction of the text
+/// around them, so that a string does not look like what it is
+const INVISIBLE: &[(char, &str)] = &[
+    ('\u{00AD}', "SOFT HYPHEN"),
+    ('\u{034F}', "COMBINING GRAPHEME JOINER"),
+    ('\u{061C}', "ARABIC LETTER MARK"),
+    ('\u{180E}', "MONGOLIAN VOWEL SEPARATOR"),
+    ('\u{200B}', "ZERO WIDTH SPACE"),
+    ('\u{200C}', "ZERO WIDTH NON-JOINER"),
+    ('\u{200D}', "ZERO WIDTH JOINER"),
+    ('\u{200E}', "LEFT-TO-RIGHT MARK"),
+    ('\u{200F}', "RIGHT-TO-LEFT MARK"),
+    ('\u{202A}', "LEFT-TO-RIGHT EMBEDDING"),
+    ('\u{202B}', "RIGHT-TO-LEFT EMBEDDING"),
+    ('\u{202C}', "POP DIRECTIONAL FORMATTING"),
+    ('\u{202D}', "LEFT-TO-RIGHT OVERRIDE"),
+    ('\u{202E}', "RIGHT-TO-LEFT OVERRIDE"),
+    ('\u{2060}', "WORD JOINER"),
+    ('\u{2061}', "FUNCTION APPLICATION"),
+    ('\u{2062}', "INVISIBLE TIMES"),
+    ('\u{2063}', "INVISIBLE SEPARATOR"),
+    ('\u{2064}', "INVISIBLE PLUS"),
+    ('\u{2066}', "LEFT-TO-RIGHT ISOLATE"),
+    ('\u{2067}', "RIGHT-TO-LEFT ISOLATE"),
+    ('\u{2068}', "FIRST STRONG ISOLATE"),
+    ('\u{2069}', "POP DIRECTIONAL ISOLATE"),
+    ('\u{FEFF}', "ZERO WIDTH NO-BREAK SPACE"),
+];
+
+/// The name of `c` if it is a control character or one of the invisible
+/// and bidi control characters, `None` otherwise.
+pub fn special_name(c: char) -> Option<&'static str> {
+    if c.is_control() {
+        return Some("control character");
+    }
+    INVISIBLE
+        .iter()
+        .find(|(invisible, _)| *invisible == c)
+        .map(|(_, name)| *name)
+}
+
+/// Whether `c` is a control character or an invisible or bidi control
+/// character.
+pub fn is_special(c: char) -> bool {
+    special_name(c).is_some()
+}
+
+/// The special characters of `s`, each once, as code point and name, e.g.
+/// `U+200D ZERO WIDTH JOINER`.
+pub fn describe_special(s: &str) -> Vec<String> {
+    let mut described = Vec::new();
+    for c in s.chars() {
+        if let Some(name) = special_name(c) {
+            let description = format!("U+{:04X} {}", c as u32, name);
+            if !described.contains(&description) {
+                described.push(description);
+            }
+        }
+    }
+    described
+}
+
+/// Normalize `s` in place and return whether it changed.
+fn normalize_string(s: &mut String, normalization: Normalization) -> bool {
+    let normalized = normalization.apply(s);
+    if normalized != *s {
+        *s = normalized;
+        true
+    } else {
+        false
+    }
+}
+
+/// Normalize the input (and, if `expected` is set, the expected output) of
+/// all tests. Returns the number of strings that were changed.
+pub fn normalize(
+    test_suites: &mut [TestSuite],
+    normalization: Normalization,
+    expected: bool,
+) -> usize {
+    if normalization == Normalization::None {
+        return 0;
+    }
+    let mut changed = 0;
+    for test in test_suites
+        .iter_mut()
+        .flat_map(|suite| suite.tests.iter_mut())
+    {
+        if normalize_string(&mut test.input, normalization) {
+            changed += 1;
+        }
+        if expected && normalize_string(&mut test.expected, normalization) {
+            changed += 1;
+        }
+    }
+    changed
+}
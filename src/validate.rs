@@ -0,0 +1,145 @@
+//! Validation of normalized files.
+//!
+//! A normalized file is checked by deserializing it into the model the
+//! converter writes. Unknown fields, wrong types and out-of-range values
+//! are reported with the path to the offending value and, where the
+//! deserializer knows it, its line and column. Position arrays with the
+//! wrong number of entries are warned about, see [`crate::positions`], as
+//! is an `expected_backward` in a suite that is not run backward after
+//! forward.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+use clap::Args;
+use louis_migrate_yaml::normalized::{self, TestMode, TestSuite};
+
+use crate::{
+    diagnostics::Position,
+    inputs, positions,
+    progress::Progress,
+    report::{self, Case, ReportSpec},
+};
+
+#[derive(Args, Debug)]
+pub struct ValidateArgs {
+    /// The normalized files to check, yaml or json (by extension),
+    /// directories to search for them or glob patterns
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+    #[command(flatten)]
+    inputs: inputs::InputOptions,
+    /// Write a report with one case per file, given as FORMAT[=PATH], e.g. junit=validate.xml or tap
+    #[arg(long, value_name = "FORMAT[=PATH]")]
+    report: Vec<ReportSpec>,
+    /// Stop at the first invalid file instead of checking all files and
+    /// counting the invalid ones at the end
+    #[arg(long)]
+    fail_fast: bool,
+}
+
+pub fn validate_file(path: &Path) -> Result<Vec<TestSuite>> {
+    normalized::from_source(path, &fs::read_to_string(path)?)
+}
+
+/// Where in the source the deserializer found `error`, if it knows.
+fn error_position(error: &anyhow::Error) -> Option<Position> {
+    if let Some(error) = error.downcast_ref::<serde_yaml::Error>() {
+        let location = error.location()?;
+        return Some(Position {
+            line: location.line(),
+            column: location.column(),
+        });
+    }
+    let error = error.downcast_ref::<serde_json::Error>()?;
+    (error.line() > 0).then(|| Position {
+        line: error.line(),
+        column: error.column(),
+    })
+}
+
+/// The tests with an `expected_backward` that is never used because their
+/// suite is not a `bothDirections` suite, as suite and test counted from 1.
+fn unused_expected_backward(test_suites: &[TestSuite]) -> Vec<(usize, usize)> {
+    let mut unused = Vec::new();
+    for (i, suite) in test_suites.iter().enumerate() {
+        if suite.mode == TestMode::BothDirections {
+            continue;
+        }
+        for (j, test) in suite.tests.iter().enumerate() {
+            if test.expected_backward.is_some() {
+                unused.push((i + 1, j + 1));
+            }
+        }
+    }
+    unused
+}
+
+pub fn run(args: &ValidateArgs, quiet: bool) -> Result<()> {
+    let mut cases = Vec::new();
+    let files = inputs::expand(&args.files, &args.inputs, &["yaml", "json"])?;
+    let progress = Progress::new(files.len(), quiet);
+    // the files left unchecked by --fail-fast
+    let mut skipped = 0;
+    for (i, file) in files.iter().enumerate() {
+        let (source, result) = match fs::read_to_string(file) {
+            Ok(source) => {
+                let result = normalized::from_source(file, &source);
+                (source, result)
+            }
+            Err(e) => (String::new(), Err(e.into())),
+        };
+        match &result {
+            Ok(test_suites) => {
+                progress.add_tests(test_suites.iter().map(|suite| suite.tests.len()).sum());
+                // the positions of the tests are those in the legacy file
+                for mismatch in positions::validate(test_suites) {
+                    progress.warn(file, mismatch.code, mismatch);
+                }
+                for (suite, test) in unused_expected_backward(test_suites) {
+                    progress.warn(
+                        file,
+                        "unused-expected-backward",
+                        format!(
+                            "suite {}, test {}: expected_backward is only used by \
+                             bothDirections suites",
+                            suite, test
+                        ),
+                    );
+                }
+            }
+            Err(e) => match error_position(e) {
+                Some(position) => {
+                    progress.error_at(file, &source, position, "invalid-file", format!("{:#}", e))
+                }
+                None => progress.error(file, "invalid-file", format!("{:#}", e)),
+            },
+        }
+        progress.file_done();
+        cases.push(Case::new(file.display().to_string(), &result));
+        if result.is_err() && args.fail_fast {
+            skipped = files.len() - i - 1;
+            break;
+        }
+    }
+    progress.finish();
+
+    for spec in &args.report {
+        report::write(spec, "validate", &cases)?;
+    }
+
+    let invalid = cases.iter().filter(|case| case.failure.is_some()).count();
+    if invalid > 0 && skipped > 0 {
+        bail!(
+            "{} invalid file(s) found, {} remaining file(s) not checked because of --fail-fast",
+            invalid,
+            skipped
+        );
+    } else if invalid > 0 {
+        bail!("{} invalid file(s) found", invalid);
+    }
+    Ok(())
+}
@@ -0,0 +1,84 @@
+//! Callbacks over the contents of a test file as it is parsed.
+//!
+//! A [`TestFileVisitor`] is handed the tables, flags, suites and tests of a
+//! file in the order the parser comes across them, see
+//! [`crate::parse::visit_file`]. A legacy file is parsed test by test, so
+//! a tool that only looks at each test once, like a coverage analyzer,
+//! need not hold even a single suite of a huge file in memory. Building
+//! the suites themselves is just one such visitor, [`SuiteBuilder`].
+
+use anyhow::{bail, Result};
+
+use crate::{
+    normalized::{Table, Test, TestSuite},
+    parse::Flags,
+};
+
+/// Callbacks driven by the parser, all of which do nothing by default.
+/// An error returned by a callback stops the parsing and is returned by
+/// the driver.
+pub trait TestFileVisitor {
+    /// The file sets the `table` of the suites that follow. A file that is
+    /// read whole, see [`crate::parse::TestSuiteIter`], has this called
+    /// before each of its suites.
+    fn on_table(&mut self, _table: &Table) -> Result<()> {
+        Ok(())
+    }
+
+    /// The file sets the `flags` of the suites that follow, read whole
+    /// files have this called before each of their suites.
+    fn on_flags(&mut self, _flags: &Flags) -> Result<()> {
+        Ok(())
+    }
+
+    /// A suite starts, given with its settings but without its tests,
+    /// which follow through [`TestFileVisitor::on_test`]. A `tests` block
+    /// without any tests starts no suite.
+    fn on_suite_start(&mut self, _suite: &TestSuite) -> Result<()> {
+        Ok(())
+    }
+
+    /// A test of the suite started last.
+    fn on_test(&mut self, _test: Test) -> Result<()> {
+        Ok(())
+    }
+
+    /// The suite started last has no more tests.
+    fn on_suite_end(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// The visitor that builds the suites of a file, as
+/// [`crate::parse::parse_file`] returns them.
+#[derive(Debug, Default)]
+pub struct SuiteBuilder {
+    suites: Vec<TestSuite>,
+}
+
+impl SuiteBuilder {
+    /// The suites built so far.
+    pub fn into_suites(self) -> Vec<TestSuite> {
+        self.suites
+    }
+
+    /// Take the last suite built so far, if any.
+    pub(crate) fn pop(&mut self) -> Option<TestSuite> {
+        self.suites.pop()
+    }
+}
+
+impl TestFileVisitor for SuiteBuilder {
+    fn on_suite_start(&mut self, suite: &TestSuite) -> Result<()> {
+        self.suites.push(suite.clone());
+        Ok(())
+    }
+
+    fn on_test(&mut self, test: Test) -> Result<()> {
+        match self.suites.last_mut() {
+            Some(suite) => suite.tests.push(test),
+            None => bail!("test outside of a suite"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+//! Re-conversion of the input file whenever it changes.
+
+use std::{fs, path::Path, sync::mpsc, time::Duration};
+
+use anyhow::{anyhow, Result};
+use notify::{RecursiveMode, Watcher};
+
+use crate::{cache, convert, Args};
+
+/// Events arriving within this time after a change are considered part of
+/// the same change, as editors often write a file in several steps.
+const DEBOUNCE: Duration = Duration::from_millis(100);
+
+/// Convert `yaml` and convert it again after every change of its contents
+/// until the process is terminated. Conversion errors are printed instead
+/// of ending the watch.
+pub fn run(args: &Args, yaml: &Path) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    // editors often replace the file instead of writing to it, so the
+    // directory is watched rather than the file itself
+    let dir = match yaml.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    let file_name = yaml
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file", yaml.display()))?;
+
+    let mut converted = None;
+    loop {
+        // saving a file without changing it, or touching it, leaves
+        // nothing to convert
+        let contents = fs::read(yaml).ok().map(cache::hash);
+        if contents.is_none() || contents != converted {
+            if let Err(e) = convert(args, yaml) {
+                eprintln!("{}: error: {:#}", yaml.display(), e);
+            }
+            eprintln!("watching {} for changes", yaml.display());
+            converted = contents;
+        }
+
+        loop {
+            let event = rx.recv()??;
+            if event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == Some(file_name))
+            {
+                break;
+            }
+        }
+        while rx.recv_timeout(DEBOUNCE).is_ok() {}
+    }
+}
@@ -0,0 +1,483 @@
+//! A typed cursor over the events of a yaml stream.
+//!
+//! The liblouis test files repeat their top-level keys, which rules out
+//! deserializing them into a map, so they are parsed event by event.
+//! [`YamlCursor`] wraps a yaml parser for this: it can peek at the next
+//! event, expect a particular one and skip whole nodes. It also keeps track
+//! of where in the document it is, as a path of keys and indices such as
+//! `tests#2[2][2].xfail`, so that errors can say where they occurred even
+//! when the events carry no line numbers, as those of libyaml do not.
+//!
+//! The events come from libyaml by default. With the `pure-rust` feature
+//! they come from yaml-rust2 instead, which has no unsafe code and tells
+//! where its events are.
+
+use std::fmt::{self, Write as _};
+
+use anyhow::Result;
+
+#[cfg(not(feature = "pure-rust"))]
+mod libyaml;
+#[cfg(not(feature = "pure-rust"))]
+use self::libyaml as backend;
+#[cfg(feature = "pure-rust")]
+mod yaml_rust2;
+#[cfg(feature = "pure-rust")]
+use self::yaml_rust2 as backend;
+
+#[cfg(not(any(feature = "libyaml", feature = "pure-rust")))]
+compile_error!("either the `libyaml` or the `pure-rust` feature is required");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScalarStyle {
+    Plain,
+    SingleQuoted,
+    DoubleQuoted,
+    Literal,
+    Folded,
+}
+
+/// An event of the yaml stream, independent of the parser backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    StreamStart,
+    StreamEnd,
+    DocumentStart,
+    DocumentEnd,
+    Alias { anchor: String },
+    Scalar { value: String, style: ScalarStyle },
+    SequenceStart,
+    SequenceEnd,
+    MappingStart,
+    MappingEnd,
+}
+
+impl Event {
+    /// Whether the event is a plain scalar spelling null, including an
+    /// empty value such as in `tests:` with nothing after it.
+    pub fn is_null(&self) -> bool {
+        matches!(
+            self,
+            Event::Scalar { value, style: ScalarStyle::Plain }
+                if matches!(value.as_str(), "" | "~" | "null" | "Null" | "NULL")
+        )
+    }
+}
+
+/// A position in the source. Lines and columns count from 1, columns in
+/// characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// An error reported by the parser backend, with the position it occurred
+/// at if the backend knows it.
+#[derive(Debug)]
+pub struct ParserError {
+    message: String,
+    mark: Option<Mark>,
+}
+
+impl ParserError {
+    /// Where in the source the parser ran into the error.
+    pub fn mark(&self) -> Option<Mark> {
+        self.mark
+    }
+}
+
+impl fmt::Display for ParserError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(mark) = self.mark {
+            write!(f, " at line {} column {}", mark.line, mark.column)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParserError {}
+
+/// An event as returned by the parser, or the error it ran into.
+pub type ParserResult = Result<Event, ParserError>;
+
+/// An error at a known place in the document.
+#[derive(Debug)]
+pub struct YamlError {
+    message: String,
+    path: String,
+    /// Where the parser ran into the error
+    mark: Option<Mark>,
+    /// Where the node the error is about starts
+    node: Option<Mark>,
+}
+
+impl YamlError {
+    /// The path of keys and indices leading to the node the error is about.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// The position in the source: where the parser ran into the error, or
+    /// where the node the error is about starts if the backend tells where
+    /// its events are.
+    pub fn mark(&self) -> Option<Mark> {
+        self.mark.or(self.node)
+    }
+}
+
+impl fmt::Display for YamlError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.mark {
+            Some(mark) => write!(
+                f,
+                "{} at line {} column {}",
+                self.message, mark.line, mark.column
+            ),
+            None if self.path.is_empty() => write!(f, "{}", self.message),
+            None => write!(f, "{} at {}", self.message, self.path),
+        }
+    }
+}
+
+impl std::error::Error for YamlError {}
+
+/// An open collection and how far into it the cursor is.
+enum Frame {
+    /// A sequence with the number of its items seen so far
+    Sequence(usize),
+    /// A mapping with the keys seen so far and how often each occurred,
+    /// the index of its most recent key among them and whether the cursor
+    /// is inside the value of that key. Mappings have a handful of keys, so
+    /// they are searched rather than hashed, and a mapping without keys
+    /// allocates nothing.
+    Mapping {
+        keys: Vec<(String, usize)>,
+        key: Option<usize>,
+        in_value: bool,
+    },
+}
+
+/// How deeply collections may nest before the cursor gives up. The test
+/// files nest a handful of levels, anything deeper is malformed.
+pub const MAX_DEPTH: usize = 64;
+
+/// A cursor over the events of a yaml stream. Every consumed event is
+/// logged at debug level.
+pub struct YamlCursor<'a> {
+    events: backend::Events<'a>,
+    peeked: Option<Option<ParserResult>>,
+    /// Where the peeked event starts
+    peeked_mark: Option<Mark>,
+    /// Where the most recently consumed event starts
+    mark: Option<Mark>,
+    frames: Vec<Frame>,
+    /// Whether the parser failed, after which the cursor yields nothing
+    failed: bool,
+}
+
+impl YamlCursor<'static> {
+    /// A cursor over the events of `source`, which it takes over.
+    pub fn from_string(source: String) -> Result<Self> {
+        Ok(YamlCursor {
+            events: backend::Events::from_string(source)?,
+            peeked: None,
+            peeked_mark: None,
+            mark: None,
+            frames: Vec::new(),
+            failed: false,
+        })
+    }
+}
+
+impl<'a> YamlCursor<'a> {
+    /// A cursor over the events of `source`.
+    pub fn new(source: &'a str) -> Result<Self> {
+        Ok(YamlCursor {
+            events: backend::Events::new(source)?,
+            peeked: None,
+            peeked_mark: None,
+            mark: None,
+            frames: Vec::new(),
+            failed: false,
+        })
+    }
+
+    /// The next event without consuming it.
+    pub fn peek(&mut self) -> Option<&ParserResult> {
+        if self.failed {
+            return None;
+        }
+        if self.peeked.is_none() {
+            self.peeked = Some(self.events.next());
+            self.peeked_mark = self.events.mark();
+        }
+        self.peeked.as_ref().and_then(Option::as_ref)
+    }
+
+    /// Consume the next event, turning a parser error into an error at the
+    /// current node that keeps the message and position of the parser. `None` marks the end of the events.
+    pub fn next_event(&mut self) -> Result<Option<Event>> {
+        match self.next() {
+            Some(Ok(event)) => Ok(Some(event)),
+            Some(Err(e)) => Err(YamlError {
+                message: e.message,
+                path: self.path(),
+                mark: e.mark,
+                node: None,
+            }
+            .into()),
+            None => Ok(None),
+        }
+    }
+
+    /// The path of keys and indices leading to the current node, e.g.
+    /// `tests[2][0]`. Indices count from 0. A key that occurs more than once
+    /// in its mapping, as the top-level keys of legacy files do, is counted
+    /// from its second occurrence on, e.g. `tests#2[0]`.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        for frame in &self.frames {
+            match frame {
+                Frame::Sequence(items) if *items > 0 => {
+                    write!(path, "[{}]", items - 1).unwrap();
+                }
+                Frame::Mapping {
+                    keys,
+                    key: Some(index),
+                    ..
+                } => {
+                    let (key, occurrence) = &keys[*index];
+                    if !path.is_empty() {
+                        path.push('.');
+                    }
+                    path.push_str(key);
+                    if *occurrence > 1 {
+                        write!(path, "#{}", occurrence).unwrap();
+                    }
+                }
+                _ => (),
+            }
+        }
+        path
+    }
+
+    /// An error about the current node, positioned at the most recently
+    /// consumed event.
+    pub fn error(&self, message: impl fmt::Display) -> anyhow::Error {
+        YamlError {
+            message: message.to_string(),
+            path: self.path(),
+            mark: None,
+            node: self.mark,
+        }
+        .into()
+    }
+
+    /// Attach the current position to `error` unless it already has one.
+    pub fn locate(&self, error: anyhow::Error) -> anyhow::Error {
+        if error.is::<YamlError>() {
+            error
+        } else {
+            self.error(format!("{:#}", error))
+        }
+    }
+
+    /// Consume the next event, which has to satisfy `matches`. `expected`
+    /// describes the event for the error message otherwise.
+    pub fn expect(
+        &mut self,
+        expected: &str,
+        matches: impl FnOnce(&Event) -> bool,
+    ) -> Result<Event> {
+        match self.next_event()? {
+            Some(event) if matches(&event) => Ok(event),
+            Some(event) => Err(self.error(format!("Expected {}, got {:?}", expected, event))),
+            None => Err(self.error(format!("Expected {}, got the end of the stream", expected))),
+        }
+    }
+
+    pub fn read_stream_start(&mut self) -> Result<()> {
+        self.expect("StreamStart", |event| matches!(event, Event::StreamStart))?;
+        Ok(())
+    }
+
+    pub fn read_stream_end(&mut self) -> Result<()> {
+        self.expect("StreamEnd", |event| matches!(event, Event::StreamEnd))?;
+        Ok(())
+    }
+
+    pub fn read_document_start(&mut self) -> Result<()> {
+        self.expect("DocumentStart", |event| {
+            matches!(event, Event::DocumentStart)
+        })?;
+        Ok(())
+    }
+
+    pub fn read_document_end(&mut self) -> Result<()> {
+        self.expect("DocumentEnd", |event| matches!(event, Event::DocumentEnd))?;
+        Ok(())
+    }
+
+    pub fn read_mapping_start(&mut self) -> Result<()> {
+        self.expect("MappingStart", |event| matches!(event, Event::MappingStart))?;
+        Ok(())
+    }
+
+    pub fn read_mapping_end(&mut self) -> Result<()> {
+        self.expect("MappingEnd", |event| matches!(event, Event::MappingEnd))?;
+        Ok(())
+    }
+
+    pub fn read_sequence_start(&mut self) -> Result<()> {
+        self.expect("SequenceStart", |event| {
+            matches!(event, Event::SequenceStart)
+        })?;
+        Ok(())
+    }
+
+    pub fn read_sequence_end(&mut self) -> Result<()> {
+        self.expect("SequenceEnd", |event| matches!(event, Event::SequenceEnd))?;
+        Ok(())
+    }
+
+    /// Consume a scalar and return its value.
+    pub fn read_scalar(&mut self) -> Result<String> {
+        match self.expect("Scalar", |event| matches!(event, Event::Scalar { .. }))? {
+            Event::Scalar { value, .. } => Ok(value),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Consume the next node, a scalar or a whole collection with all its
+    /// nested nodes.
+    pub fn skip_value(&mut self) -> Result<()> {
+        let first = self.expect("a node", |event| {
+            matches!(
+                event,
+                Event::Scalar { .. }
+                    | Event::Alias { .. }
+                    | Event::SequenceStart
+                    | Event::MappingStart
+            )
+        })?;
+        self.skip_subtree(&first)
+    }
+
+    /// Consume the rest of a node whose first event has already been read,
+    /// i.e. nothing for a scalar and everything up to the matching end
+    /// event for a collection.
+    pub fn skip_subtree(&mut self, first: &Event) -> Result<()> {
+        let mut depth = match first {
+            Event::SequenceStart | Event::MappingStart => 1,
+            _ => 0,
+        };
+        while depth > 0 {
+            match self.next_event()? {
+                Some(Event::SequenceStart | Event::MappingStart) => depth += 1,
+                Some(Event::SequenceEnd | Event::MappingEnd) => depth -= 1,
+                Some(_) => (),
+                None => return Err(self.error("Unexpected end of the stream")),
+            }
+        }
+        Ok(())
+    }
+
+    /// A node starts in the innermost collection.
+    fn enter_node(&mut self, scalar: Option<&str>) {
+        match self.frames.last_mut() {
+            Some(Frame::Sequence(items)) => *items += 1,
+            Some(Frame::Mapping {
+                keys,
+                key,
+                in_value: false,
+            }) => {
+                let name = scalar.unwrap_or("?");
+                match keys.iter().position(|(seen, _)| seen == name) {
+                    Some(index) => {
+                        keys[index].1 += 1;
+                        *key = Some(index);
+                    }
+                    None => {
+                        keys.push((name.to_string(), 1));
+                        *key = Some(keys.len() - 1);
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// A node in the innermost collection is complete.
+    fn leave_node(&mut self) {
+        if let Some(Frame::Mapping { in_value, .. }) = self.frames.last_mut() {
+            *in_value = !*in_value;
+        }
+    }
+
+    fn track(&mut self, event: &Event) {
+        match event {
+            Event::Scalar { value, .. } => {
+                self.enter_node(Some(value));
+                self.leave_node();
+            }
+            Event::Alias { .. } => {
+                self.enter_node(None);
+                self.leave_node();
+            }
+            Event::SequenceStart => {
+                self.enter_node(None);
+                self.frames.push(Frame::Sequence(0));
+            }
+            Event::MappingStart => {
+                self.enter_node(None);
+                self.frames.push(Frame::Mapping {
+                    keys: Vec::new(),
+                    key: None,
+                    in_value: false,
+                });
+            }
+            Event::SequenceEnd | Event::MappingEnd => {
+                self.frames.pop();
+                self.leave_node();
+            }
+            _ => (),
+        }
+    }
+}
+
+impl<'a> Iterator for YamlCursor<'a> {
+    type Item = ParserResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let (mut next, mark) = match self.peeked.take() {
+            Some(peeked) => (peeked, self.peeked_mark),
+            None => (self.events.next(), self.events.mark()),
+        };
+        if let Some(Ok(Event::SequenceStart | Event::MappingStart)) = next {
+            if self.frames.len() >= MAX_DEPTH {
+                next = Some(Err(ParserError {
+                    message: format!("collections nested deeper than {} levels", MAX_DEPTH),
+                    mark: None,
+                }));
+            }
+        }
+        match &next {
+            Some(Ok(event)) => {
+                log::debug!("event {:?}", event);
+                self.mark = mark;
+                self.track(event);
+            }
+            Some(Err(e)) => {
+                log::debug!("parser error {}", e);
+                self.failed = true;
+            }
+            None => (),
+        }
+        next
+    }
+}
@@ -0,0 +1,70 @@
+//! Events from libyaml, through the bindings to its translation to Rust.
+
+use std::io;
+
+use anyhow::Result;
+use libyaml::ParserIter;
+
+use super::{Event, Mark, ParserError, ParserResult, ScalarStyle};
+
+pub struct Events<'a>(ParserIter<'a>);
+
+impl<'a> Events<'a> {
+    pub fn new(source: &'a str) -> Result<Self> {
+        Ok(Events(libyaml::Parser::new(source.as_bytes())?.into_iter()))
+    }
+
+    /// Where the most recent event starts, which the bindings do not tell.
+    pub fn mark(&self) -> Option<Mark> {
+        None
+    }
+}
+
+impl Events<'static> {
+    pub fn from_string(source: String) -> Result<Self> {
+        let reader = io::Cursor::new(source.into_bytes());
+        Ok(Events(libyaml::Parser::new(reader)?.into_iter()))
+    }
+}
+
+fn scalar_style(style: Option<libyaml::ScalarStyle>) -> ScalarStyle {
+    match style {
+        Some(libyaml::ScalarStyle::SingleQuoted) => ScalarStyle::SingleQuoted,
+        Some(libyaml::ScalarStyle::DoubleQuoted) => ScalarStyle::DoubleQuoted,
+        Some(libyaml::ScalarStyle::Literal) => ScalarStyle::Literal,
+        Some(libyaml::ScalarStyle::Folded) => ScalarStyle::Folded,
+        _ => ScalarStyle::Plain,
+    }
+}
+
+impl Iterator for Events<'_> {
+    type Item = ParserResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let event = match self.0.next()? {
+            Ok(event) => event,
+            // the bindings do not tell where the error occurred
+            Err(e) => {
+                return Some(Err(ParserError {
+                    message: e.to_string(),
+                    mark: None,
+                }))
+            }
+        };
+        Some(Ok(match event {
+            libyaml::Event::StreamStart { .. } => Event::StreamStart,
+            libyaml::Event::StreamEnd => Event::StreamEnd,
+            libyaml::Event::DocumentStart { .. } => Event::DocumentStart,
+            libyaml::Event::DocumentEnd { .. } => Event::DocumentEnd,
+            libyaml::Event::Alias { anchor } => Event::Alias { anchor },
+            libyaml::Event::Scalar { value, style, .. } => Event::Scalar {
+                value,
+                style: scalar_style(style),
+            },
+            libyaml::Event::SequenceStart { .. } => Event::SequenceStart,
+            libyaml::Event::SequenceEnd => Event::SequenceEnd,
+            libyaml::Event::MappingStart { .. } => Event::MappingStart,
+            libyaml::Event::MappingEnd => Event::MappingEnd,
+        }))
+    }
+}
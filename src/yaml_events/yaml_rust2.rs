@@ -0,0 +1,127 @@
+//! Events from yaml-rust2, a parser written in Rust.
+
+use std::str::Chars;
+
+use anyhow::Result;
+use yaml_rust2::{parser::Parser, scanner::TScalarStyle};
+
+use super::{Event, Mark, ParserError, ParserResult, ScalarStyle};
+
+/// The characters of the source, which the events either borrow or own
+enum Input<'a> {
+    Borrowed(Chars<'a>),
+    /// The source and the byte offset of the next character
+    Owned(String, usize),
+}
+
+impl Iterator for Input<'_> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        match self {
+            Input::Borrowed(chars) => chars.next(),
+            Input::Owned(source, offset) => {
+                let c = source[*offset..].chars().next()?;
+                *offset += c.len_utf8();
+                Some(c)
+            }
+        }
+    }
+}
+
+pub struct Events<'a> {
+    parser: Parser<Input<'a>>,
+    done: bool,
+    /// Where the most recent event starts
+    mark: Option<Mark>,
+}
+
+impl<'a> Events<'a> {
+    pub fn new(source: &'a str) -> Result<Self> {
+        Ok(Events {
+            parser: Parser::new(Input::Borrowed(source.chars())),
+            done: false,
+            mark: None,
+        })
+    }
+
+    /// Where the most recent event starts.
+    pub fn mark(&self) -> Option<Mark> {
+        self.mark
+    }
+}
+
+impl Events<'static> {
+    pub fn from_string(source: String) -> Result<Self> {
+        Ok(Events {
+            parser: Parser::new(Input::Owned(source, 0)),
+            done: false,
+            mark: None,
+        })
+    }
+}
+
+fn scalar_style(style: TScalarStyle) -> ScalarStyle {
+    match style {
+        TScalarStyle::Plain => ScalarStyle::Plain,
+        TScalarStyle::SingleQuoted => ScalarStyle::SingleQuoted,
+        TScalarStyle::DoubleQuoted => ScalarStyle::DoubleQuoted,
+        TScalarStyle::Literal => ScalarStyle::Literal,
+        TScalarStyle::Folded => ScalarStyle::Folded,
+    }
+}
+
+impl Iterator for Events<'_> {
+    type Item = ParserResult;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        use yaml_rust2::Event as YamlEvent;
+
+        while !self.done {
+            let event = match self.parser.next_token() {
+                Ok((event, marker)) => {
+                    self.mark = Some(Mark {
+                        line: marker.line(),
+                        column: marker.col() + 1,
+                    });
+                    event
+                }
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(ParserError {
+                        message: e.info().to_string(),
+                        // yaml-rust2 counts columns from 0
+                        mark: Some(Mark {
+                            line: e.marker().line(),
+                            column: e.marker().col() + 1,
+                        }),
+                    }));
+                }
+            };
+            let event = match event {
+                YamlEvent::Nothing => continue,
+                YamlEvent::StreamStart => Event::StreamStart,
+                YamlEvent::StreamEnd => {
+                    self.done = true;
+                    Event::StreamEnd
+                }
+                YamlEvent::DocumentStart => Event::DocumentStart,
+                YamlEvent::DocumentEnd => Event::DocumentEnd,
+                // yaml-rust2 identifies anchors by number rather than name
+                YamlEvent::Alias(id) => Event::Alias {
+                    anchor: id.to_string(),
+                },
+                YamlEvent::Scalar(value, style, ..) => Event::Scalar {
+                    value,
+                    style: scalar_style(style),
+                },
+                YamlEvent::SequenceStart(..) => Event::SequenceStart,
+                YamlEvent::SequenceEnd => Event::SequenceEnd,
+                YamlEvent::MappingStart(..) => Event::MappingStart,
+                YamlEvent::MappingEnd => Event::MappingEnd,
+            };
+            return Some(Ok(event));
+        }
+        None
+    }
+}
@@ -0,0 +1,69 @@
+mod common;
+
+use std::fs;
+
+use common::{command, fresh_dir, run, stdout};
+
+#[test]
+fn includes_are_followed_to_missing_tables_and_cycles() {
+    let dir = fresh_dir("analyze-tables");
+    fs::create_dir_all(dir.join("tables/sub")).unwrap();
+    fs::write(
+        dir.join("tables/main.ctb"),
+        "# main table\ninclude sub/chars.cti\ninclude missing.cti\n",
+    )
+    .unwrap();
+    // found beside the including table
+    fs::write(dir.join("tables/sub/chars.cti"), "include digits.cti\n").unwrap();
+    fs::write(dir.join("tables/sub/digits.cti"), "include ../main.ctb\n").unwrap();
+    fs::write(
+        dir.join("tests.yaml"),
+        "table: main.ctb\ntests:\n  - [a, ⠁]\n",
+    )
+    .unwrap();
+
+    let output = run(command(["analyze-tables", "--tables-dir"])
+        .arg(dir.join("tables"))
+        .arg(dir.join("tests.yaml")));
+    assert_eq!(output.status.code(), Some(1));
+    let report = stdout(&output);
+    assert!(
+        report.contains(
+            "  suite 1: main.ctb (3 table(s))\n    main.ctb\n    sub/chars.cti\n    sub/digits.cti\n"
+        ),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains("    missing: missing.cti (included by main.ctb)\n"),
+        "{}",
+        report
+    );
+    assert!(
+        report.contains("    cycle: main.ctb -> sub/chars.cti -> sub/digits.cti -> main.ctb\n"),
+        "{}",
+        report
+    );
+    assert!(
+        report.ends_with("3 table(s) needed, 1 missing, 1 include cycle(s)\n"),
+        "{}",
+        report
+    );
+}
+
+#[test]
+fn unreadable_tables_exit_with_3() {
+    let dir = fresh_dir("analyze-unreadable-tables");
+    // a directory where the table should be
+    fs::create_dir_all(dir.join("tables/main.ctb")).unwrap();
+    fs::write(
+        dir.join("tests.yaml"),
+        "table: main.ctb\ntests:\n  - [a, ⠁]\n",
+    )
+    .unwrap();
+
+    let output = run(command(["analyze-tables", "--tables-dir"])
+        .arg(dir.join("tables"))
+        .arg(dir.join("tests.yaml")));
+    assert_eq!(output.status.code(), Some(3));
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{command, convert_with, run, stderr, write_source};
+
+#[test]
+fn dot_notation_is_converted_to_unicode_braille() {
+    let path = write_source(
+        "dot-notation.yaml",
+        "\
+table: a.ctb
+tests:
+  - [ab, 1-12]
+  - [a b, 1 12-0]
+  - [dash, '-']
+  - [trailing, 1-]
+  - [doubled, 1--2]
+",
+    );
+    let yaml = convert_with(&path, &["--no-ids", "--dots-to-unicode"]);
+    let expected: Vec<_> = yaml
+        .lines()
+        .filter_map(|line| line.trim().strip_prefix("expected: "))
+        .collect();
+    // empty cells are not dot notation, so those values are kept
+    assert_eq!(expected, ["⠁⠃", "⠁ ⠃⠀", "'-'", "1-", "1--2"], "{}", yaml);
+}
+
+#[test]
+fn expected_output_is_checked_for_known_ucs_display_tables() {
+    let path = write_source(
+        "display-tables.yaml",
+        "\
+display: braille-patterns.cti
+table: a.ctb
+tests:
+  - [a, a]
+display: en-us-brf.dis
+tests:
+  - [b, b]
+display: mystery.dis
+tests:
+  - [c, c]
+",
+    );
+    let output = run(command(["--no-provenance", "--validate-braille"]).arg(&path));
+    assert_eq!(output.status.code(), Some(1));
+    let stderr = stderr(&output);
+    let warnings: Vec<_> = stderr
+        .lines()
+        .filter_map(|line| line.split_once(": warning: "))
+        .map(|(_, warning)| warning)
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            "suite 3: display table mystery.dis is not known to render Unicode braille, \
+             its expected output is not checked",
+            "suite 1, test 1: expected \"a\" is not Unicode braille",
+        ],
+        "{}",
+        stderr
+    );
+}
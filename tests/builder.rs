@@ -0,0 +1,107 @@
+//! Suites built in code are checked when they are built and serialize
+//! like converted ones.
+
+use louis_migrate_yaml::normalized::{Mode, Test, TestMode, TestSuite, Xfail};
+
+#[test]
+fn builds_a_suite() {
+    let suite = TestSuite::builder()
+        .table("de-g2.ctb")
+        .forward()
+        .translation_mode(Mode::NoContractions)
+        .tag("generated")
+        .test("Haus", "⠓⠁⠥⠎")
+        .test_with(Test {
+            input: "Maus".to_string(),
+            expected: "⠍⠁⠥⠎".to_string(),
+            xfail: Xfail::Reason("not yet".to_string()),
+            ..Default::default()
+        })
+        .build()
+        .unwrap();
+    assert_eq!(suite.mode, TestMode::Forward);
+    assert_eq!(suite.tests.len(), 2);
+    assert_eq!(
+        serde_yaml::to_string(&suite).unwrap(),
+        "\
+tags:
+- generated
+table: de-g2.ctb
+mode: forward
+translation_modes:
+- noContractions
+tests:
+- input: Haus
+  expected: ⠓⠁⠥⠎
+- input: Maus
+  expected: ⠍⠁⠥⠎
+  xfail: not yet
+"
+    );
+}
+
+#[test]
+fn settings_may_come_in_any_order() {
+    let suite = TestSuite::builder()
+        .test("foo-bar", "foo-bar")
+        .hyphenate()
+        .tables(["a.ctb", "b.dic"])
+        .build()
+        .unwrap();
+    assert_eq!(suite.mode, TestMode::Hyphenate);
+    assert_eq!(
+        serde_yaml::to_string(&suite.table).unwrap(),
+        "- a.ctb\n- b.dic\n"
+    );
+}
+
+#[test]
+fn incomplete_suites_are_rejected() {
+    let error = TestSuite::builder().test("a", "b").build().unwrap_err();
+    assert_eq!(error.to_string(), "the suite has no table");
+    let error = TestSuite::builder().table("a.ctb").build().unwrap_err();
+    assert_eq!(error.to_string(), "the suite has no tests");
+}
+
+#[test]
+fn tests_must_fit_the_suite() {
+    let error = TestSuite::builder()
+        .table("a.ctb")
+        .display()
+        .test("ab", "⠁")
+        .build()
+        .unwrap_err();
+    assert!(
+        error.to_string().starts_with("test 1: display test"),
+        "{}",
+        error
+    );
+    let error = TestSuite::builder()
+        .table("a.ctb")
+        .test("a", "b")
+        .test_with(Test {
+            input: "c".to_string(),
+            expected: "d".to_string(),
+            expected_backward: Some("e".to_string()),
+            ..Default::default()
+        })
+        .build()
+        .unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "test 2: expected_backward is only used by bothDirections suites"
+    );
+    let duplicate = || Test {
+        id: Some("x".to_string()),
+        input: "a".to_string(),
+        expected: "b".to_string(),
+        ..Default::default()
+    };
+    let error = TestSuite::builder()
+        .table("a.ctb")
+        .test_with(duplicate())
+        .test_with(duplicate())
+        .build()
+        .unwrap_err();
+    assert_eq!(error.to_string(), "test 2: duplicate id \"x\"");
+}
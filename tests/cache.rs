@@ -0,0 +1,50 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use serde_json::Value;
+
+use common::{command, fresh_dir, run, stderr, stdout};
+
+fn read_json(path: &Path) -> Value {
+    serde_json::from_str(&fs::read_to_string(path).unwrap()).unwrap()
+}
+
+/// Take a snapshot of `corpus` with the cache at `cache` and return the
+/// hash of the conversion of `a.yaml`.
+fn snapshot(corpus: &Path, cache: &Path) -> Value {
+    let output = run(command(["snapshot", "--cache"]).arg(cache).arg(corpus));
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let manifest: Value = serde_json::from_str(&stdout(&output)).unwrap();
+    manifest["a.yaml"].clone()
+}
+
+#[test]
+fn conversions_are_taken_from_the_cache_until_the_input_changes() {
+    let dir = fresh_dir("cache");
+    let corpus = dir.join("corpus");
+    fs::create_dir_all(&corpus).unwrap();
+    fs::write(corpus.join("a.yaml"), "table: a.ctb\ntests:\n  - [a, ⠁]\n").unwrap();
+    let cache = dir.join("cache.json");
+
+    let converted = snapshot(&corpus, &cache);
+    let mut cached = read_json(&cache);
+    let conversions = cached["conversions"].as_object_mut().unwrap();
+    assert_eq!(conversions.values().collect::<Vec<_>>(), [&converted]);
+
+    // a hash only the cache knows shows that the file was not converted
+    for hash in conversions.values_mut() {
+        *hash = "from the cache".into();
+    }
+    fs::write(&cache, serde_json::to_string(&cached).unwrap()).unwrap();
+    assert_eq!(snapshot(&corpus, &cache), "from the cache");
+
+    fs::write(corpus.join("a.yaml"), "table: a.ctb\ntests:\n  - [a, ⠁⠁]\n").unwrap();
+    let changed = snapshot(&corpus, &cache);
+    assert_ne!(changed, "from the cache");
+    assert_ne!(changed, converted);
+    assert_eq!(
+        read_json(&cache)["conversions"].as_object().unwrap().len(),
+        2
+    );
+}
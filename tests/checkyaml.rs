@@ -0,0 +1,89 @@
+//! Legacy files read the way the yaml harness of liblouis reads them.
+
+use std::path::Path;
+
+use louis_migrate_yaml::{
+    checkyaml,
+    normalized::{TestMode, Xfail},
+    parse::{parse_file, ParseOptions},
+};
+
+#[test]
+fn plain_files_read_like_the_conversion_reads_them() {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden/legacy");
+    for name in ["translation.yaml", "test-modes.yaml", "xfail.yaml"] {
+        let path = golden.join(name);
+        let (expected, _) = parse_file(&path, &ParseOptions::default()).unwrap();
+        let suites = checkyaml::read_file(&path).unwrap();
+        assert_eq!(suites.len(), expected.len(), "{}", name);
+        for (suite, expected) in suites.iter().zip(&expected) {
+            assert_eq!(suite.mode, expected.mode, "{}", name);
+            let tests: Vec<_> = suite
+                .tests
+                .iter()
+                .map(|test| (&test.input, &test.expected, test.xfail.is_false()))
+                .collect();
+            let expected_tests: Vec<_> = expected
+                .tests
+                .iter()
+                .map(|test| (&test.input, &test.expected, test.xfail.is_false()))
+                .collect();
+            assert_eq!(tests, expected_tests, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn only_false_and_off_are_not_expected_to_fail() {
+    let source = "\
+table: a.ctb
+tests:
+  - [a, b, {xfail: false}]
+  - [a, b, {xfail: off}]
+  - [a, b, {xfail: no}]
+  - [a, b, {xfail: 0}]
+  - [a, b, {xfail: False}]
+flags: {testmode: bothDirections}
+tests:
+  - [a, b, {xfail: {forward: no, backward: off}}]
+";
+    let suites = checkyaml::read_source(source).unwrap();
+    let xfails: Vec<_> = suites[0]
+        .tests
+        .iter()
+        .map(|test| !test.xfail.is_false())
+        .collect();
+    assert_eq!(xfails, [false, false, true, true, true]);
+    assert_eq!(suites[1].mode, TestMode::BothDirections);
+    match &suites[1].tests[0].xfail {
+        Xfail::Map { forward, backward } => assert!(!forward.is_false() && backward.is_false()),
+        xfail => panic!("{:?}", xfail),
+    }
+}
+
+#[test]
+fn extensions_of_the_conversion_are_rejected() {
+    for (source, message) in [
+        (
+            "table: a.ctb\ndefaults: {mode: dotsIO}\ntests:\n  - [a, b]\n",
+            "Unexpected key \"defaults\"",
+        ),
+        (
+            "table: a.ctb\ntests:\n  - {input: a, expected: b}\n",
+            "Expected SequenceStart or SequenceEnd",
+        ),
+        (
+            "table: a.ctb\ntests:\n  - [a]\n",
+            "Test \"a\" without expected output",
+        ),
+        ("tests:\n  - [a, b]\n", "No table defined for tests"),
+    ] {
+        let error = checkyaml::read_source(source).unwrap_err();
+        assert!(
+            format!("{:#}", error).contains(message),
+            "{}: {:#}",
+            source,
+            error
+        );
+    }
+}
@@ -0,0 +1,110 @@
+//! Helpers shared by the integration tests, each of which uses only some
+//! of them.
+#![allow(dead_code)]
+
+use std::{
+    ffi::OsStr,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Output, Stdio},
+};
+
+/// The path of `name` below `tests`, e.g. `golden/legacy/xfail.yaml`.
+pub fn test_file(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(name)
+}
+
+/// The path of the fixture `name` in `tests/fixtures`.
+pub fn fixture(name: &str) -> PathBuf {
+    test_file("fixtures").join(name)
+}
+
+/// The path of `name` in the temporary directory of the tests.
+pub fn tmp(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_TARGET_TMPDIR")).join(name)
+}
+
+/// Write `source` to `name` in the temporary directory of the tests and
+/// return its path.
+pub fn write_source(name: &str, source: impl AsRef<[u8]>) -> PathBuf {
+    let path = tmp(name);
+    fs::write(&path, source).unwrap();
+    path
+}
+
+/// An empty directory `name` in the temporary directory of the tests,
+/// removing what an earlier run left there.
+pub fn fresh_dir(name: &str) -> PathBuf {
+    let dir = tmp(name);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// louis-migrate-yaml with `args`, to be given more arguments and run
+/// with [`run`].
+pub fn command<S: AsRef<OsStr>>(args: impl IntoIterator<Item = S>) -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_louis-migrate-yaml"));
+    command.args(args);
+    command
+}
+
+/// Run `command` and return what it did.
+pub fn run(command: &mut Command) -> Output {
+    command.output().expect("failed to run louis-migrate-yaml")
+}
+
+/// Run `command` with `input` on its stdin and return what it did.
+pub fn run_with_input(command: &mut Command, input: &str) -> Output {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .expect("failed to run louis-migrate-yaml");
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// What `output` printed to stdout.
+pub fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+/// What `output` printed to stderr.
+pub fn stderr(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+/// Convert `fixture` from `tests/fixtures` and return the emitted yaml,
+/// without the provenance metadata as it differs between runs.
+pub fn convert(fixture: &str) -> String {
+    convert_file(&self::fixture(fixture))
+}
+
+/// Convert the file at `path` like [`convert`].
+pub fn convert_file(path: &Path) -> String {
+    convert_with(path, &[])
+}
+
+/// Convert the file at `path` with the extra `args` and return the
+/// emitted yaml, failing if the conversion fails.
+pub fn convert_with(path: &Path, args: &[&str]) -> String {
+    let output = run(command(["--no-provenance"]).args(args).arg(path));
+    // exit status 1 means converted with warnings
+    assert!(
+        matches!(output.status.code(), Some(0 | 1)),
+        "conversion of {} failed: {}",
+        path.display(),
+        stderr(&output)
+    );
+    stdout(&output)
+}
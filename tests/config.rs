@@ -0,0 +1,52 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::{command, fresh_dir, run};
+
+/// A fresh directory for the test `name` with the config file `config`
+/// and a legacy file `test.yaml` with an unknown test option.
+fn setup(name: &str, config: &str) -> PathBuf {
+    let dir = fresh_dir(name);
+    fs::write(dir.join("louis-migrate.toml"), config).unwrap();
+    fs::write(
+        dir.join("test.yaml"),
+        "table: foo.ctb\ntests:\n  - [a, b, {foo: bar}]\n",
+    )
+    .unwrap();
+    dir
+}
+
+/// Convert `test.yaml` in `dir` with the extra `args` and return the exit
+/// status.
+fn convert_in(dir: &PathBuf, args: &[&str]) -> Option<i32> {
+    run(command(["--no-provenance"])
+        .current_dir(dir)
+        .args(args)
+        .arg("test.yaml"))
+    .status
+    .code()
+}
+
+#[test]
+fn config_in_working_directory_is_used() {
+    let dir = setup(
+        "config-found",
+        "output-dir = \"out\"\nunknown-keys = \"warn\"\n",
+    );
+    assert_eq!(convert_in(&dir, &[]), Some(1));
+    assert!(dir.join("out/test.yaml").is_file());
+}
+
+#[test]
+fn command_line_overrides_config() {
+    let dir = setup("config-overridden", "unknown-keys = \"warn\"\n");
+    assert_eq!(convert_in(&dir, &[]), Some(1));
+    assert_eq!(convert_in(&dir, &["--unknown-keys", "error"]), Some(2));
+}
+
+#[test]
+fn invalid_config_is_rejected() {
+    let dir = setup("config-invalid", "output-directory = \"out\"\n");
+    assert_eq!(convert_in(&dir, &[]), Some(2));
+}
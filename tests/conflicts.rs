@@ -0,0 +1,70 @@
+mod common;
+
+use std::fs;
+
+use common::{command, run_with_input, stderr, stdout, tmp};
+
+const CONFLICTING: &str = "\
+table: a.ctb
+tests:
+  - [foo, bar]
+  - [foo, baz]
+  - [abc, ⠁⠃⠉]
+";
+
+/// Write `yaml` to a file called `name` and convert it with `args`,
+/// answering with `answers`. Returns the exit code, stdout and stderr.
+fn convert(name: &str, yaml: &str, args: &[&str], answers: &str) -> (Option<i32>, String, String) {
+    let dir = tmp("conflicts");
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, yaml).unwrap();
+    let output = run_with_input(command(["--no-provenance"]).args(args).arg(&path), answers);
+    (output.status.code(), stdout(&output), stderr(&output))
+}
+
+#[test]
+fn conflicts_are_warned_about() {
+    let (code, stdout, stderr) = convert("warned.yaml", CONFLICTING, &[], "");
+    assert_eq!(code, Some(1));
+    assert!(stderr.contains("warned.yaml:4:3: warning"), "{}", stderr);
+    assert!(stdout.contains("expected: baz"), "{}", stdout);
+}
+
+#[test]
+fn decisions_are_written_into_the_output_and_log() {
+    let log = tmp("resolutions.jsonl");
+    let _ = fs::remove_file(&log);
+    let args = ["--interactive", "--resolution-log", log.to_str().unwrap()];
+
+    let (code, stdout, stderr) = convert("keep-first.yaml", CONFLICTING, &args, "1\n");
+    assert_eq!(code, Some(0), "{}", stderr);
+    assert!(stderr.contains("      4 |   - [foo, baz]"), "{}", stderr);
+    assert!(!stdout.contains("expected: baz"), "{}", stdout);
+    assert!(
+        stdout.contains("kept over the conflicting expected output \"baz\" of the test on line 4"),
+        "{}",
+        stdout
+    );
+
+    let (_, stdout, _) = convert("xfail-first.yaml", CONFLICTING, &args, "x1\n");
+    assert!(
+        stdout.contains("expected: bar\n    xfail: contradicts the test on line 4"),
+        "{}",
+        stdout
+    );
+
+    let log = fs::read_to_string(&log).unwrap();
+    let decisions: Vec<_> = log
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap()["decision"].clone())
+        .collect();
+    assert_eq!(decisions, ["keep-first", "xfail-first"]);
+}
+
+#[test]
+fn unanswered_conflicts_fail() {
+    let (code, _, stderr) = convert("unanswered.yaml", CONFLICTING, &["--interactive"], "");
+    assert_eq!(code, Some(2));
+    assert!(stderr.contains("input ended"), "{}", stderr);
+}
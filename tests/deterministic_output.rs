@@ -0,0 +1,11 @@
+mod common;
+
+use common::convert;
+
+#[test]
+fn repeated_runs_produce_identical_output() {
+    let first = convert("modes-and-metadata.yaml");
+    for _ in 0..5 {
+        assert_eq!(first, convert("modes-and-metadata.yaml"));
+    }
+}
@@ -0,0 +1,110 @@
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Output,
+};
+
+use serde_json::{json, Value};
+
+use common::{command, run, stderr, tmp, write_source};
+
+/// A source with a test whose expected output is not Unicode braille,
+/// which `--validate-braille` warns about, written for the test `name`.
+fn warning_source(name: &str) -> PathBuf {
+    write_source(
+        &format!("diagnostics-warning-{}.yaml", name),
+        "table: a.ctb\ntests:\n  - [a, ⠁]\n  - [b, b]\n",
+    )
+}
+
+/// A source with an unclosed flow sequence, which fails to parse, written
+/// for the test `name`.
+fn error_source(name: &str) -> PathBuf {
+    write_source(
+        &format!("diagnostics-error-{}.yaml", name),
+        "table: a.ctb\ntests:\n  - [a, ⠁\n",
+    )
+}
+
+fn convert(source: &Path, diagnostics: &str) -> Output {
+    run(command([
+        "--no-provenance",
+        "--validate-braille",
+        "--diagnostics",
+        diagnostics,
+    ])
+    .arg(source))
+}
+
+fn warning(source: &Path) -> Value {
+    json!([{
+        "file": source,
+        "line": 4,
+        "column": 3,
+        "severity": "warning",
+        "code": "not-unicode-braille",
+        "message": "suite 1, test 2: expected \"b\" is not Unicode braille",
+    }])
+}
+
+/// Check the shape of the diagnostics of `error_source`, whose message and
+/// position depend on the parser.
+fn assert_error(diagnostics: &Value, source: &Path) {
+    let [error] = diagnostics.as_array().unwrap().as_slice() else {
+        panic!("one error expected: {}", diagnostics);
+    };
+    let error = error.as_object().unwrap();
+    let mut keys: Vec<_> = error.keys().collect();
+    keys.sort();
+    assert_eq!(
+        keys,
+        ["code", "column", "file", "line", "message", "severity"]
+    );
+    assert_eq!(error["file"], json!(source));
+    assert_eq!(error["severity"], "error");
+    assert_eq!(error["code"], "conversion-failed");
+    assert!(error["message"].is_string(), "{}", diagnostics);
+    for key in ["line", "column"] {
+        assert!(
+            error[key].is_u64() || error[key].is_null(),
+            "{}",
+            diagnostics
+        );
+    }
+}
+
+#[test]
+fn diagnostics_replace_the_messages_on_stderr() {
+    let source = warning_source("stderr");
+    let output = convert(&source, "json");
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    let diagnostics: Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(diagnostics, warning(&source));
+
+    let source = error_source("stderr");
+    let output = convert(&source, "json");
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert_error(&serde_json::from_slice(&output.stderr).unwrap(), &source);
+}
+
+#[test]
+fn diagnostics_are_written_to_a_file_besides_the_messages() {
+    let path = tmp("diagnostics.json");
+    let spec = format!("json={}", path.display());
+
+    let source = warning_source("file");
+    let output = convert(&source, &spec);
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert!(stderr(&output).contains("4:3: warning: suite 1, test 2"));
+    let diagnostics: Value = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(diagnostics, warning(&source));
+
+    let source = error_source("file");
+    let output = convert(&source, &spec);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert!(stderr(&output).contains("error"), "{}", stderr(&output));
+    let diagnostics = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+    assert_error(&diagnostics, &source);
+}
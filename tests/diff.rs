@@ -0,0 +1,42 @@
+mod common;
+
+use common::{command, convert_file, run, stderr, stdout, write_source};
+
+#[test]
+fn added_removed_and_changed_tests_are_listed() {
+    let old = write_source(
+        "diff-old.yaml",
+        "table: a.ctb\ntests:\n  - [a, ⠁]\n  - [b, ⠃]\n  - [c, ⠉]\n",
+    );
+    let new = write_source(
+        "diff-new.yaml",
+        "table: a.ctb\ntests:\n  - [a, ⠁]\n  - [c, ⠉⠉]\n  - [d, ⠙]\n",
+    );
+    let output = run(command(["diff"]).arg(&old).arg(&new));
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "\
+suite 1: test \"b\" (line 4) removed
+suite 1: test \"c\" (line 5): expected changed: \"⠉\" -> \"⠉⠉\"
+suite 1: test \"d\" (line 5) added
+"
+    );
+    assert!(
+        stderr(&output).contains("3 difference(s) found"),
+        "{}",
+        stderr(&output)
+    );
+}
+
+#[test]
+fn a_conversion_does_not_differ_from_its_source() {
+    let legacy = write_source(
+        "diff-legacy.yaml",
+        "table: a.ctb\ntests:\n  - [a, ⠁]\n  - [b, ⠃, {xfail: true}]\n",
+    );
+    let normalized = write_source("diff-normalized.yaml", convert_file(&legacy));
+    let output = run(command(["diff"]).arg(&legacy).arg(&normalized));
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+}
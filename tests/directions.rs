@@ -0,0 +1,91 @@
+mod common;
+
+use std::path::Path;
+
+use common::{command, fixture, run, stderr, stdout};
+use louis_migrate_yaml::normalized::{self, DirectionXfail, TestMode, TestSuite, Xfail};
+
+/// Convert the fixture `name` with `--explode-directions` and read the
+/// suites back.
+fn explode(name: &str) -> Vec<TestSuite> {
+    let output =
+        run(command(["--no-provenance", "--no-ids", "--explode-directions"]).arg(fixture(name)));
+    assert!(output.status.success(), "{:?}", output);
+    let yaml = stdout(&output);
+    normalized::from_source(Path::new("exploded.yaml"), &yaml).unwrap()
+}
+
+#[test]
+fn both_directions_become_a_forward_and_a_backward_suite() {
+    let suites = explode("both-directions.yaml");
+    let modes: Vec<_> = suites.iter().map(|suite| suite.mode.clone()).collect();
+    assert_eq!(modes, [TestMode::Forward, TestMode::Backward]);
+    let [forward, backward] = &suites[..] else {
+        unreachable!()
+    };
+
+    let pairs = |suite: &TestSuite| -> Vec<(String, String)> {
+        suite
+            .tests
+            .iter()
+            .map(|test| (test.input.clone(), test.expected.clone()))
+            .collect()
+    };
+    assert_eq!(
+        pairs(forward),
+        [("abc".into(), "⠁⠃⠉".into()), ("ABC".into(), "⠠⠠⠁⠃⠉".into())]
+    );
+    assert_eq!(
+        pairs(backward),
+        [("⠁⠃⠉".into(), "abc".into()), ("⠠⠠⠁⠃⠉".into(), "ABC".into())]
+    );
+
+    // positions describe the forward translation
+    assert_eq!(forward.tests[0].output_pos, [0, 1, 2]);
+    assert!(backward.tests[0].output_pos.is_empty());
+
+    assert!(forward.tests[1].xfail.is_false());
+    assert!(matches!(
+        &backward.tests[1].xfail,
+        Xfail::Reason(reason) if reason == "capitals are lost"
+    ));
+}
+
+#[test]
+fn mirrored_suites_are_merged() {
+    let output = run(
+        command(["--no-provenance", "--no-ids", "--merge-directions"])
+            .arg(fixture("mirrored-directions.yaml")),
+    );
+    // exit status 1 means converted with warnings
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains(
+            "mirrored-directions.yaml:17:3: warning: suites 3 and 4 not \
+             merged: test 1: the backward input \"⠁⠉\" is not the expected braille \"⠁⠃\""
+        ),
+        "{}",
+        stderr
+    );
+
+    let yaml = stdout(&output);
+    let suites = normalized::from_source(Path::new("merged.yaml"), &yaml).unwrap();
+    let modes: Vec<_> = suites.iter().map(|suite| suite.mode.clone()).collect();
+    assert_eq!(
+        modes,
+        [
+            TestMode::BothDirections,
+            TestMode::Forward,
+            TestMode::Backward
+        ]
+    );
+    let merged = &suites[0].tests;
+    assert_eq!(merged[0].expected_backward, None);
+    assert_eq!(merged[1].expected_backward.as_deref(), Some("abc"));
+    assert!(matches!(
+        &merged[1].xfail,
+        Xfail::Map { forward: DirectionXfail::Reason(reason), backward }
+            if reason == "capitals" && backward.is_false()
+    ));
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::convert;
+use serde_yaml::Value;
+
+/// The `display_table` of each suite converted from `fixture`.
+fn display_tables(fixture: &str) -> Vec<Option<String>> {
+    let suites: Vec<Value> = serde_yaml::from_str(&convert(fixture)).unwrap();
+    suites
+        .iter()
+        .map(|suite| suite["display_table"].as_str().map(String::from))
+        .collect()
+}
+
+#[test]
+fn suites_before_display_have_no_display_table() {
+    assert_eq!(display_tables("display-rebinding.yaml")[0], None);
+}
+
+#[test]
+fn suites_bind_to_most_recent_display() {
+    assert_eq!(
+        display_tables("display-rebinding.yaml")[1..],
+        [
+            Some("first.dis".to_string()),
+            Some("second.dis".to_string()),
+            Some("second.dis".to_string()),
+        ]
+    );
+}
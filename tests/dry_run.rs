@@ -0,0 +1,29 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::{command, fixture, run, stdout, tmp};
+
+/// Convert the `block-style.yaml` fixture into `dir` with the extra `args`
+/// and return what was printed.
+fn convert_into(dir: &PathBuf, args: &[&str]) -> String {
+    let output = run(command(["--no-provenance", "--output-dir"])
+        .arg(dir)
+        .args(args)
+        .arg(fixture("block-style.yaml")));
+    stdout(&output)
+}
+
+#[test]
+fn dry_run_writes_nothing() {
+    let dir = tmp("dry-run");
+    let _ = fs::remove_dir_all(&dir);
+    let preview = convert_into(&dir, &["--dry-run"]);
+    assert!(preview.starts_with("would create "), "{}", preview);
+    assert!(!dir.exists());
+
+    convert_into(&dir, &[]);
+    let preview = convert_into(&dir, &["--dry-run"]);
+    assert!(preview.starts_with("unchanged "), "{}", preview);
+    assert!(preview.contains(" 0 file(s) would change"), "{}", preview);
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{command, fixture, run, stdout};
+
+/// Convert the fixture `invisible.yaml` with `args`.
+fn convert(args: &[&str]) -> String {
+    let output = run(command(["--no-provenance", "--no-ids"])
+        .args(args)
+        .arg(fixture("invisible.yaml")));
+    assert!(output.status.success(), "{:?}", output);
+    stdout(&output)
+}
+
+#[test]
+fn special_characters_are_escaped() {
+    for style in ["block", "flow"] {
+        let yaml = convert(&["--escape-special", "--test-style", style]);
+        assert!(yaml.contains(r#""ا\u200Fب""#), "{}", yaml);
+        assert!(yaml.contains(r#""a\u202Eb""#), "{}", yaml);
+        assert!(yaml.contains(r#""क्\u200Dष""#), "{}", yaml);
+        assert!(yaml.contains("input: abc"), "{}", yaml);
+    }
+}
+
+#[test]
+fn special_characters_are_kept_by_default() {
+    let yaml = convert(&[]);
+    assert!(yaml.contains("input: ا\u{200F}ب\n"), "{}", yaml);
+    assert!(!yaml.contains(r"\u"), "{}", yaml);
+}
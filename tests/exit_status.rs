@@ -0,0 +1,57 @@
+mod common;
+
+use std::path::Path;
+
+use common::{command, run, tmp, write_source};
+
+/// Convert the file at `path` with the extra `args` and return the exit
+/// status.
+fn status(path: &Path, args: &[&str]) -> Option<i32> {
+    run(command(["--no-provenance"]).args(args).arg(path))
+        .status
+        .code()
+}
+
+const WITH_WARNING: &str = "table: foo.ctb\nfoo: bar\ntests:\n  - [a, b]\n";
+
+#[test]
+fn clean_conversion_exits_with_0() {
+    let path = write_source("clean.yaml", "table: foo.ctb\ntests:\n  - [a, b]\n");
+    assert_eq!(status(&path, &[]), Some(0));
+}
+
+#[test]
+fn warnings_exit_with_1() {
+    let path = write_source("with-warning.yaml", WITH_WARNING);
+    assert_eq!(status(&path, &[]), Some(1));
+    assert_eq!(status(&path, &["--max-warnings", "1"]), Some(1));
+}
+
+#[test]
+fn too_many_warnings_exit_with_2() {
+    let path = write_source("too-many-warnings.yaml", WITH_WARNING);
+    assert_eq!(status(&path, &["--max-warnings", "0"]), Some(2));
+}
+
+#[test]
+fn parse_errors_exit_with_2() {
+    let path = write_source("parse-error.yaml", "table: foo.ctb\ntests: [[a\n");
+    assert_eq!(status(&path, &[]), Some(2));
+}
+
+#[test]
+fn missing_files_exit_with_3() {
+    let path = tmp("does-not-exist.yaml");
+    assert_eq!(status(&path, &[]), Some(3));
+}
+
+#[test]
+fn missing_tables_to_inline_exit_with_3() {
+    let path = write_source(
+        "missing-table.yaml",
+        "table: missing.ctb\ntests:\n  - [a, b]\n",
+    );
+    let tables = tmp("").display().to_string();
+    let args = ["--inline-tables", "--tables-dir", &tables];
+    assert_eq!(status(&path, &args), Some(3));
+}
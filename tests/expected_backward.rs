@@ -0,0 +1,50 @@
+mod common;
+
+use common::{command, run, stderr, write_source};
+
+const SUITES: &str = "\
+- table: en-us-g1.ctb
+  mode: bothDirections
+  tests:
+  - input: Abc
+    expected: ⠠⠁⠃⠉
+  - input: ABC
+    expected: ⠠⠠⠁⠃⠉
+    expected_backward: abc
+- table: en-us-g1.ctb
+  mode: forward
+  tests:
+  - input: abc
+    expected: ⠁⠃⠉
+    expected_backward: abc
+";
+
+/// Write `contents` to the normalized file `name` and validate it.
+fn validate(name: &str, contents: &str) -> (Option<i32>, String) {
+    let output = run(command(["validate"]).arg(write_source(name, contents)));
+    (output.status.code(), stderr(&output))
+}
+
+#[test]
+fn expected_backward_outside_both_directions_is_reported() {
+    let (status, stderr) = validate("expected-backward.yaml", SUITES);
+    // exit status 1 means valid with warnings
+    assert_eq!(status, Some(1), "{}", stderr);
+    let warnings: Vec<_> = stderr
+        .lines()
+        .filter(|line| line.contains("expected_backward is only used"))
+        .collect();
+    assert_eq!(warnings.len(), 1, "{}", stderr);
+    assert!(warnings[0].contains("suite 2, test 1"), "{}", warnings[0]);
+}
+
+#[test]
+fn expected_backward_is_part_of_the_model() {
+    let misspelled = SUITES.replace(
+        "expected_backward: abc\n- table",
+        "expected_backwards: abc\n- table",
+    );
+    let (status, stderr) = validate("expected-backwards.yaml", &misspelled);
+    assert_eq!(status, Some(2), "{}", stderr);
+    assert!(stderr.contains("expected_backwards"), "{}", stderr);
+}
@@ -0,0 +1,30 @@
+mod common;
+
+use common::{convert, convert_file, convert_with, fixture, write_source};
+
+/// Convert `fixture` with `--factor-defaults` and return the emitted yaml.
+fn convert_factored(name: &str) -> String {
+    convert_with(&fixture(name), &["--factor-defaults"])
+}
+
+#[test]
+fn factored_tables_are_written_once() {
+    let factored = convert_factored("display-rebinding.yaml");
+    let (defaults, suites) = factored.split_once("---\n").unwrap();
+    assert_eq!(
+        defaults,
+        "suite_defaults:\n  display_table: second.dis\n  table: first.utb\n"
+    );
+    assert_eq!(suites.matches("table: second.utb").count(), 2);
+    assert!(!suites.contains("first.utb"));
+    assert!(!suites.contains("second.dis"));
+    // the first suite has no display table at all
+    assert!(suites.starts_with("- display_table: null\n"));
+}
+
+#[test]
+fn factored_defaults_are_applied_when_reading() {
+    let factored = convert_factored("display-rebinding.yaml");
+    let path = write_source("factored.yaml", factored);
+    assert_eq!(convert_file(&path), convert("display-rebinding.yaml"));
+}
@@ -0,0 +1,74 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::{command, fresh_dir, stderr};
+
+/// Write `files` into a fresh directory `name`.
+fn corpus(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let dir = fresh_dir(name);
+    for (name, contents) in files {
+        fs::write(dir.join(name), contents).unwrap();
+    }
+    dir
+}
+
+fn run(args: &[&str], dir: &PathBuf) -> (Option<i32>, String) {
+    let output = common::run(command(args).arg(dir));
+    (output.status.code(), stderr(&output))
+}
+
+const VALID: &str =
+    "- table: en-us-g1.ctb\n  mode: forward\n  tests:\n  - input: a\n    expected: ⠁\n";
+const INVALID: &str = "- table: en-us-g1.ctb\n  mode: sideways\n  tests: []\n";
+
+#[test]
+fn validate_checks_all_files_by_default() {
+    let dir = corpus(
+        "validate-all",
+        &[("a.yaml", INVALID), ("b.yaml", INVALID), ("c.yaml", VALID)],
+    );
+    let (code, stderr) = run(&["validate"], &dir);
+    assert_eq!(code, Some(2), "{}", stderr);
+    assert!(stderr.contains("a.yaml"), "{}", stderr);
+    assert!(stderr.contains("b.yaml"), "{}", stderr);
+    assert!(stderr.contains("2 invalid file(s) found\n"), "{}", stderr);
+}
+
+#[test]
+fn validate_stops_at_the_first_invalid_file() {
+    let dir = corpus(
+        "validate-fail-fast",
+        &[("a.yaml", VALID), ("b.yaml", INVALID), ("c.yaml", INVALID)],
+    );
+    let (code, stderr) = run(&["validate", "--fail-fast"], &dir);
+    assert_eq!(code, Some(2), "{}", stderr);
+    assert!(stderr.contains("b.yaml"), "{}", stderr);
+    assert!(!stderr.contains("c.yaml"), "{}", stderr);
+    assert!(
+        stderr.contains(
+            "1 invalid file(s) found, 1 remaining file(s) not checked because of --fail-fast"
+        ),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn lint_stops_at_the_first_file_with_errors() {
+    let broken = "table: en-us-g1.ctb\ntests: [\n";
+    let dir = corpus("lint-fail-fast", &[("a.yaml", broken), ("b.yaml", broken)]);
+    let (code, stderr) = run(&["lint"], &dir);
+    assert_eq!(code, Some(2), "{}", stderr);
+    assert!(stderr.contains("b.yaml"), "{}", stderr);
+
+    let (code, stderr) = run(&["lint", "--fail-fast"], &dir);
+    assert_eq!(code, Some(2), "{}", stderr);
+    assert!(stderr.contains("a.yaml"), "{}", stderr);
+    assert!(!stderr.contains("b.yaml"), "{}", stderr);
+    assert!(
+        stderr.contains("1 remaining file(s) not checked because of --fail-fast"),
+        "{}",
+        stderr
+    );
+}
@@ -0,0 +1,112 @@
+mod common;
+
+use std::path::PathBuf;
+
+use common::{convert_with, write_source};
+
+/// Two suites to select tests from, written for the test `name`.
+fn source(name: &str) -> PathBuf {
+    write_source(
+        &format!("filter-{}.yaml", name),
+        "\
+table: a.ctb
+tests:
+  - [a, ⠁]
+  - [b, ⠃, {xfail: true}]
+  - [ab, ⠁⠃]
+  - [c, ⠉]
+table: b.ctb
+flags: {testmode: backward}
+tests:
+  - [⠙, d]
+",
+    )
+}
+
+/// The inputs of the tests in the converted yaml, in order.
+fn inputs(yaml: &str) -> Vec<&str> {
+    yaml.lines()
+        .filter_map(|line| line.trim_start().strip_prefix("input: "))
+        .collect()
+}
+
+#[test]
+fn tests_are_included_by_pattern_and_xfail() {
+    let source = source("included");
+    assert_eq!(
+        inputs(&convert_with(&source, &["--grep", "b"])),
+        ["b", "ab"]
+    );
+    assert_eq!(
+        inputs(&convert_with(&source, &["--grep", "⠉"])),
+        ["c"],
+        "the pattern matches the expected output as well"
+    );
+    assert_eq!(inputs(&convert_with(&source, &["--only-xfail"])), ["b"]);
+    assert_eq!(
+        inputs(&convert_with(&source, &["--mode", "backward"])),
+        ["⠙"]
+    );
+}
+
+#[test]
+fn tests_are_excluded_by_xfail_and_limit() {
+    let source = source("excluded");
+    assert_eq!(
+        inputs(&convert_with(&source, &["--skip-xfail"])),
+        ["a", "ab", "c", "⠙"]
+    );
+    assert_eq!(
+        inputs(&convert_with(&source, &["--max-tests", "2"])),
+        ["a", "b", "⠙"]
+    );
+}
+
+#[test]
+fn suites_left_without_tests_are_dropped() {
+    let yaml = convert_with(&source("dropped"), &["--grep", "^a"]);
+    assert_eq!(inputs(&yaml), ["a", "ab"]);
+    assert!(!yaml.contains("b.ctb"), "{}", yaml);
+}
+
+#[test]
+fn the_same_seed_selects_the_same_sample() {
+    let tests: String = (0..20).map(|i| format!("  - [t{i}, ⠁]\n")).collect();
+    let source = write_source("sample.yaml", format!("table: a.ctb\ntests:\n{tests}"));
+    let sample = |seed| convert_with(&source, &["--sample", "5", "--seed", seed]);
+
+    let first = sample("7");
+    assert_eq!(first, sample("7"));
+    let selected = inputs(&first);
+    assert_eq!(selected.len(), 5, "{}", first);
+    let mut ordered = selected.clone();
+    ordered.sort_by_key(|input| input[1..].parse::<usize>().unwrap());
+    assert_eq!(selected, ordered, "the sample keeps the original order");
+
+    let others: Vec<String> = ["8", "9", "10"].into_iter().map(sample).collect();
+    assert!(
+        others.iter().any(|other| *other != first),
+        "other seeds select other samples"
+    );
+}
+
+#[test]
+fn tests_are_sorted_stably_by_code_point() {
+    let source = write_source(
+        "sort.yaml",
+        "table: a.ctb\ntests:\n  - [b, ⠁]\n  - [B, ⠃]\n  - [é, ⠁]\n  - [a, ⠃]\n  - [e, ⠁]\n",
+    );
+    assert_eq!(
+        inputs(&convert_with(&source, &["--sort", "input"])),
+        ["B", "a", "b", "e", "é"]
+    );
+    assert_eq!(
+        inputs(&convert_with(&source, &["--sort", "expected"])),
+        ["b", "é", "e", "B", "a"],
+        "tests with the same expected output keep their order"
+    );
+    assert_eq!(
+        inputs(&convert_with(&source, &["--sort", "none"])),
+        ["b", "B", "é", "a", "e"]
+    );
+}
@@ -0,0 +1,18 @@
+mod common;
+
+/// Convert `fixture` from `tests/fixtures` and parse the emitted yaml.
+fn convert(fixture: &str) -> serde_yaml::Value {
+    serde_yaml::from_str(&common::convert(fixture)).unwrap()
+}
+
+#[test]
+fn flow_style_converts_like_block_style() {
+    assert_eq!(convert("block-style.yaml"), convert("flow-style.yaml"));
+}
+
+#[test]
+fn mode_list_is_parsed() {
+    let yaml = serde_yaml::to_string(&convert("flow-style.yaml")).unwrap();
+    assert!(yaml.contains("noContractions"));
+    assert!(yaml.contains("dotsIO"));
+}
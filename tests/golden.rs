@@ -0,0 +1,42 @@
+//! Every legacy file in `tests/golden/legacy` is converted through the
+//! library and compared with the file of the same name in
+//! `tests/golden/normalized`. Run with `UPDATE_GOLDEN=1` to write the
+//! current output as the expected one instead.
+
+use std::{env, fs, path::Path};
+
+use louis_migrate_yaml::parse::{parse_file, ParseOptions};
+
+#[test]
+fn conversions_match_golden_files() {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let update = env::var_os("UPDATE_GOLDEN").is_some();
+    let mut mismatches = Vec::new();
+    for entry in fs::read_dir(golden.join("legacy")).unwrap() {
+        let path = entry.unwrap().path();
+        let (suites, _) = parse_file(&path, &ParseOptions::default())
+            .unwrap_or_else(|e| panic!("conversion of {} failed: {:#}", path.display(), e));
+        let actual = serde_yaml::to_string(&suites).unwrap();
+        let expected_path = golden.join("normalized").join(path.file_name().unwrap());
+        if update {
+            fs::write(&expected_path, &actual).unwrap();
+            continue;
+        }
+        let expected = fs::read_to_string(&expected_path).unwrap_or_else(|e| {
+            panic!(
+                "no golden file {} ({}), run with UPDATE_GOLDEN=1 to write it",
+                expected_path.display(),
+                e
+            )
+        });
+        if actual != expected {
+            mismatches.push(format!(
+                "{}:\n--- expected\n{}+++ actual\n{}",
+                path.display(),
+                expected,
+                actual
+            ));
+        }
+    }
+    assert!(mismatches.is_empty(), "{}", mismatches.join("\n"));
+}
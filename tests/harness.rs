@@ -0,0 +1,78 @@
+mod common;
+
+use common::{command, run, stderr, stdout, write_source};
+
+const HARNESS: &str = r#"# -*- coding: utf-8 -*-
+# old style harness file
+{
+    "tables": ["unicode.dis", "en-us-g1.ctb"],
+    "flags": {"testmode": "translate"},
+    "tests": [
+        {
+            "txt": u"foo # not a comment",
+            "brl": u"⠋⠕⠕",
+            "mode": ["noContractions", "dotsIO"], # a comment
+        },
+        {"txt": u"bar", "brl": u"⠃⠁⠗", "cursorPos": 1, "xfail": true, "typeform": [0, 0, 0]},
+    ],
+}
+"#;
+
+#[test]
+fn harness_files_are_converted() {
+    let path = write_source("en_harness.txt", HARNESS);
+    let output = run(command(["import-harness"]).arg(&path));
+    assert!(output.status.code().unwrap() <= 1);
+    assert_eq!(
+        stdout(&output),
+        "- table:
+  - unicode.dis
+  - en-us-g1.ctb
+  mode: forward
+  tests:
+  - input: 'foo # not a comment'
+    expected: ⠋⠕⠕
+    mode:
+    - noContractions
+    - dotsIO
+  - input: bar
+    expected: ⠃⠁⠗
+    xfail: true
+    cursor_pos: 1
+    extra:
+      typeform:
+      - 0
+      - 0
+      - 0
+"
+    );
+    assert!(stderr(&output).contains("unknown key typeform"));
+}
+
+#[test]
+fn python_literals_are_read() {
+    let path = write_source(
+        "python_harness.txt",
+        r#"{
+    'tables': ['en-us-g1.ctb'],
+    'tests': [
+        {'txt': u'it\'s "quoted"', 'brl': u'⠊⠞⠄⠎', 'xfail': True},
+        {'txt': 'foo', 'brl': '⠋⠕⠕', 'xfail': False, 'typeform': None},
+    ],
+}
+"#,
+    );
+    let output = run(command(["import-harness"]).arg(&path));
+    assert!(output.status.code().unwrap() <= 1, "{}", stderr(&output));
+    let yaml = stdout(&output);
+    assert!(
+        yaml.contains("  - input: it's \"quoted\"\n    expected: ⠊⠞⠄⠎\n    xfail: true\n"),
+        "{}",
+        yaml
+    );
+    assert!(
+        yaml.contains("  - input: foo\n    expected: ⠋⠕⠕\n    extra:\n      typeform: null\n"),
+        "{}",
+        yaml
+    );
+}
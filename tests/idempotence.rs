@@ -0,0 +1,15 @@
+mod common;
+
+use std::fs;
+
+use common::{convert, convert_file, fixture, write_source};
+
+#[test]
+fn converting_twice_is_a_no_op() {
+    for entry in fs::read_dir(fixture("")).unwrap() {
+        let name = entry.unwrap().file_name().into_string().unwrap();
+        let once = convert(&name);
+        let path = write_source(&name, &once);
+        assert_eq!(once, convert_file(&path), "{} changed", name);
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::{command, convert_file, run, stdout, write_source};
+
+/// Convert `yaml`, written to a file called `name`, with `args` and
+/// return the ids of the tests in their order.
+fn ids(name: &str, yaml: &str, args: &[&str]) -> Vec<String> {
+    let path = write_source(name, yaml);
+    let output = run(command(["--no-provenance"]).args(args).arg(&path));
+    stdout(&output)
+        .lines()
+        .filter_map(|line| line.trim_start().strip_prefix("- id: "))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn ids_do_not_depend_on_the_order_of_the_tests() {
+    let ordered = ids(
+        "ordered.yaml",
+        "table: a.ctb\ntests:\n  - [foo, bar]\n  - [abc, def]\n  - [foo, baz]\n",
+        &[],
+    );
+    assert_eq!(ordered.len(), 3, "{:?}", ordered);
+    assert_eq!(ordered[2], format!("{}-2", ordered[0]));
+
+    let reordered = ids(
+        "reordered.yaml",
+        "table: a.ctb\ntests:\n  - [abc, def]\n  - [foo, bar]\n",
+        &[],
+    );
+    assert_eq!(reordered, [ordered[1].clone(), ordered[0].clone()]);
+
+    let other_table = ids(
+        "other-table.yaml",
+        "table: b.ctb\ntests:\n  - [abc, def]\n",
+        &[],
+    );
+    assert_ne!(other_table[0], ordered[1]);
+}
+
+#[test]
+fn ids_can_be_left_out() {
+    let yaml = "table: a.ctb\ntests:\n  - [foo, bar]\n";
+    assert!(ids("no-ids.yaml", yaml, &["--no-ids"]).is_empty());
+}
+
+#[test]
+fn names_come_before_ids() {
+    let path = write_source(
+        "named.yaml",
+        "table: a.ctb\ntests:\n  - [foo, bar, {name: greeting}]\n",
+    );
+    let yaml = convert_file(&path);
+    assert!(yaml.contains("  - name: greeting\n    id: "), "{}", yaml);
+}
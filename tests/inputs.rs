@@ -0,0 +1,143 @@
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use common::{command, fresh_dir, run, stderr, stdout};
+
+/// Summarize the files given by `args`, run in the crate directory, and
+/// return the summary.
+fn summary(args: &[&str]) -> String {
+    let output = run(command(["summary"])
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR")));
+    stdout(&output)
+}
+
+#[test]
+fn glob_patterns_are_expanded() {
+    let summary = summary(&["tests/golden/**/xfail.yaml"]);
+    assert!(summary.contains("| tests/golden/legacy/xfail.yaml |"));
+    assert!(summary.contains("| tests/golden/normalized/xfail.yaml |"));
+    assert!(!summary.contains("translation.yaml"));
+}
+
+#[test]
+fn excluded_files_are_left_out() {
+    let summary = summary(&["tests/golden/legacy", "--exclude", "*/x*.yaml"]);
+    assert!(summary.contains("| tests/golden/legacy/translation.yaml |"));
+    assert!(!summary.contains("xfail.yaml"));
+}
+
+/// A directory with ignore files and the yaml files they apply to.
+fn ignoring_corpus() -> PathBuf {
+    let dir = fresh_dir("louisignore");
+    fs::create_dir_all(dir.join("helpers")).unwrap();
+    fs::create_dir_all(dir.join("sub")).unwrap();
+    let test = "table: en-us-g1.ctb\ntests:\n  - [a, ⠁]\n";
+    for name in [
+        "a.yaml",
+        "broken.yaml",
+        "helpers/h.yaml",
+        "sub/b.yaml",
+        "sub/broken-but-kept.yaml",
+    ] {
+        fs::write(dir.join(name), test).unwrap();
+    }
+    fs::write(
+        dir.join(".louisignore"),
+        "# not test files\nhelpers/\nbroken*.yaml\n",
+    )
+    .unwrap();
+    fs::write(dir.join("sub/.louisignore"), "!broken-but-kept.yaml\n").unwrap();
+    dir
+}
+
+/// The files listed in `summary`, relative to `dir`.
+fn summarized(summary: &str, dir: &Path) -> Vec<String> {
+    let prefix = format!("## {}/", dir.display());
+    summary
+        .lines()
+        .filter_map(|line| line.strip_prefix(&prefix))
+        .map(String::from)
+        .collect()
+}
+
+#[test]
+fn ignore_files_are_honored() {
+    let dir = ignoring_corpus();
+    let path = dir.to_str().unwrap();
+    assert_eq!(
+        summarized(&summary(&[path]), &dir),
+        ["a.yaml", "sub/b.yaml", "sub/broken-but-kept.yaml"]
+    );
+    let pattern = format!("{}/**/b*.yaml", path);
+    assert_eq!(
+        summarized(&summary(&[&pattern]), &dir),
+        ["sub/b.yaml", "sub/broken-but-kept.yaml"]
+    );
+
+    // unless they are overridden or the file is named explicitly
+    assert_eq!(summarized(&summary(&[path, "--no-ignore"]), &dir).len(), 5);
+    let broken = format!("{}/broken.yaml", path);
+    assert_eq!(summarized(&summary(&[&broken]), &dir), ["broken.yaml"]);
+}
+
+/// Summarize the files given by `args` and return the summary and the
+/// warnings.
+fn summary_with_warnings(args: &[&str]) -> (String, String) {
+    let output = run(command(["summary"]).args(args));
+    (stdout(&output), stderr(&output))
+}
+
+#[cfg(unix)]
+#[test]
+fn linked_directories_are_searched_once() {
+    let dir = fresh_dir("symlinks");
+    fs::create_dir_all(dir.join("real")).unwrap();
+    fs::write(
+        dir.join("real/a.yaml"),
+        "table: a.ctb\ntests:\n  - [a, ⠁]\n",
+    )
+    .unwrap();
+    std::os::unix::fs::symlink("real", dir.join("link")).unwrap();
+    // a link cycle
+    std::os::unix::fs::symlink("..", dir.join("real/up")).unwrap();
+    let path = dir.to_str().unwrap();
+
+    let (summary, warnings) = summary_with_warnings(&[path]);
+    assert_eq!(summarized(&summary, &dir), ["real/a.yaml"]);
+    assert!(warnings.is_empty(), "{}", warnings);
+
+    let (summary, warnings) = summary_with_warnings(&[path, "--follow-symlinks"]);
+    assert_eq!(summarized(&summary, &dir), ["link/a.yaml"]);
+    assert!(
+        warnings.contains("real/a.yaml: warning: the same file as"),
+        "{}",
+        warnings
+    );
+
+    let real = format!("{}/real/a.yaml", path);
+    let linked = format!("{}/link/a.yaml", path);
+    let (summary, _) = summary_with_warnings(&[&real, &linked]);
+    assert_eq!(summarized(&summary, &dir), ["link/a.yaml"]);
+}
+
+#[test]
+fn names_differing_in_case_are_warned_about() {
+    let dir = fresh_dir("case-variants");
+    let test = "table: a.ctb\ntests:\n  - [a, ⠁]\n";
+    fs::write(dir.join("de-g1.yaml"), test).unwrap();
+    fs::write(dir.join("DE-g1.yaml"), test).unwrap();
+    let (summary, warnings) = summary_with_warnings(&[dir.to_str().unwrap()]);
+    if summarized(&summary, &dir).len() == 2 {
+        // a case-sensitive file system
+        assert!(
+            warnings.contains("de-g1.yaml: warning: only differs in case from"),
+            "{}",
+            warnings
+        );
+    }
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use std::path::Path;
+
+use common::{command, fixture, run, stderr, test_file, write_source};
+
+/// Lint the file at `path` and return the exit code and the findings.
+fn lint(path: &Path) -> (Option<i32>, String) {
+    let output = run(command(["lint"]).arg(path));
+    (output.status.code(), stderr(&output))
+}
+
+#[test]
+fn partial_trans_is_only_allowed_in_forward_translation() {
+    let path = test_file("golden/legacy/flags-modes.yaml");
+    let (code, findings) = lint(&path);
+    assert_eq!(code, Some(2), "{}", findings);
+    let findings: Vec<_> = findings
+        .lines()
+        .filter(|line| line.contains("[partial-trans-direction]"))
+        .collect();
+    assert_eq!(findings.len(), 1, "{:?}", findings);
+    assert!(
+        findings[0].contains("flags-modes.yaml:11:3: error[partial-trans-direction]"),
+        "{}",
+        findings[0]
+    );
+}
+
+#[test]
+fn invisible_characters_are_listed() {
+    let path = fixture("invisible.yaml");
+    let (code, findings) = lint(&path);
+    assert_eq!(code, Some(1), "{}", findings);
+    let findings: Vec<_> = findings
+        .lines()
+        .filter(|line| line.contains("[invisible-characters]"))
+        .collect();
+    assert_eq!(findings.len(), 3, "{:?}", findings);
+    assert!(findings[0].contains("U+200F RIGHT-TO-LEFT MARK"), "{}", findings[0]);
+    assert!(findings[1].contains("U+202E RIGHT-TO-LEFT OVERRIDE"), "{}", findings[1]);
+    assert!(findings[2].contains("U+200D ZERO WIDTH JOINER"), "{}", findings[2]);
+}
+
+#[test]
+fn translation_options_are_rejected_in_display_and_hyphenation_suites() {
+    let path = write_source(
+        "inapplicable-options.yaml",
+        "\
+display: en-us-brf.dis
+table: en-us-g2.ctb
+flags: {testmode: display}
+tests:
+  - [ab, ⠁⠃, {cursorPos: 1}]
+  - [cd, ⠉⠙]
+flags: {testmode: hyphenate, mode: [noContractions]}
+defaults: {outputPos: [0, 1]}
+tests:
+  - [foobar, foo-bar, {maxOutputLength: 5}]
+flags: {testmode: forward}
+tests:
+  - [ab, ⠁⠃, {cursorPos: 1, outputPos: [0, 1]}]
+",
+    );
+    let (code, findings) = lint(&path);
+    assert_eq!(code, Some(2), "{}", findings);
+    let findings: Vec<_> = findings
+        .lines()
+        .filter(|line| line.contains("[inapplicable-option]"))
+        .collect();
+    assert_eq!(findings.len(), 4, "{:?}", findings);
+    assert!(
+        findings[0].contains("inapplicable-options.yaml:5:3: error[inapplicable-option]"),
+        "{}",
+        findings[0]
+    );
+    assert!(
+        findings[0].contains("test option \"cursorPos\" has no effect in testmode display"),
+        "{}",
+        findings[0]
+    );
+    assert!(
+        findings[1].contains("translation modes have no effect in testmode hyphenate"),
+        "{}",
+        findings[1]
+    );
+    assert!(findings[2].contains("\"outputPos\""), "{}", findings[2]);
+    assert!(findings[3].contains("\"maxOutputLength\""), "{}", findings[3]);
+}
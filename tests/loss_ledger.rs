@@ -0,0 +1,44 @@
+mod common;
+
+use std::fs;
+
+use serde_json::{json, Value};
+
+use common::{convert_with, tmp, write_source};
+
+/// Convert `source` with `--unknown-keys warn` and return the loss ledger.
+fn ledger(name: &str, source: &str) -> Value {
+    let path = tmp(&format!("{}.json", name));
+    let _ = fs::remove_file(&path);
+    convert_with(
+        &write_source(&format!("{}.yaml", name), source),
+        &[
+            "--unknown-keys",
+            "warn",
+            "--loss-ledger",
+            path.to_str().unwrap(),
+        ],
+    );
+    serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap()
+}
+
+#[test]
+fn dropped_values_are_written_to_the_ledger() {
+    let ledger = ledger(
+        "lossy",
+        "table: a.ctb\nfoo: [1, {bar: x}]\ntests:\n  - [a, ⠁, {frob: 2}]\n",
+    );
+    assert_eq!(
+        ledger,
+        json!([
+            {"key": "foo", "value": [1, {"bar": "x"}], "location": "foo"},
+            {"key": "frob", "value": 2, "location": "tests[0][2].frob"},
+        ])
+    );
+}
+
+#[test]
+fn a_lossless_conversion_writes_an_empty_ledger() {
+    let ledger = ledger("lossless", "table: a.ctb\ntests:\n  - [a, ⠁]\n");
+    assert_eq!(ledger, json!([]));
+}
@@ -0,0 +1,91 @@
+mod common;
+
+use common::{command, run, stderr, write_source};
+
+/// Convert `source` and return the exit status of the conversion together
+/// with what was written to stderr.
+fn convert_source(name: &str, source: &[u8]) -> (Option<i32>, String) {
+    let path = write_source(&format!("{}.yaml", name), source);
+    let output = run(command(["--no-provenance"]).arg(&path));
+    (output.status.code(), stderr(&output))
+}
+
+/// Assert that converting `source` fails with an error rather than a panic.
+fn assert_rejected(name: &str, source: &[u8]) {
+    let (status, stderr) = convert_source(name, source);
+    assert_eq!(status, Some(2), "{} was not rejected: {}", name, stderr);
+    assert!(
+        !stderr.contains("panicked"),
+        "{} panicked: {}",
+        name,
+        stderr
+    );
+}
+
+#[test]
+fn deep_nesting_is_rejected() {
+    let depth = 200;
+    let source = format!(
+        "table: foo.ctb\nextra: {}{}\n",
+        "[".repeat(depth),
+        "]".repeat(depth)
+    );
+    assert_rejected("deep-nesting", source.as_bytes());
+}
+
+#[test]
+fn syntax_errors_are_rejected() {
+    assert_rejected("unclosed-flow", b"table: foo.ctb\ntests:\n  - [abc, \"\n");
+    assert_rejected(
+        "bad-indentation",
+        b"table: foo.ctb\ntests:\n  - - abc\n - x\n",
+    );
+    assert_rejected("tab-indentation", b"table: foo.ctb\ntests:\n\t- [a, b]\n");
+}
+
+#[test]
+fn truncated_input_is_rejected() {
+    let source = b"table: foo.ctb\nflags: {testmode: forward}\ntests:\n  - [abc, def, {xfail: ";
+    for end in 1..source.len() {
+        let (_, stderr) = convert_source("truncated", &source[..end]);
+        assert!(
+            !stderr.contains("panicked"),
+            "panicked at {}: {}",
+            end,
+            stderr
+        );
+    }
+}
+
+#[test]
+fn invalid_encoding_is_rejected() {
+    assert_rejected(
+        "invalid-utf8",
+        b"table: foo.ctb\ntests:\n  - [\xff\xfe\xfd, x]\n",
+    );
+}
+
+#[test]
+fn parser_errors_are_reported_as_such() {
+    let (_, stderr) = convert_source("parser-error", b"table: foo.ctb\ntests:\n  - [abc, \"def\n");
+    assert!(
+        !stderr.contains("Expected"),
+        "parser error was swallowed: {}",
+        stderr
+    );
+}
+
+#[test]
+fn parser_errors_are_shown_in_the_source() {
+    let (status, stderr) = convert_source(
+        "bad-test-mode",
+        b"table: foo.ctb\nflags: {testmode: sideways}\ntests:\n  - [abc, def]\n",
+    );
+    assert_eq!(status, Some(2), "{}", stderr);
+    // at the value with positions from the parser, at its key without
+    assert!(stderr.contains("bad-test-mode.yaml:2:"), "{}", stderr);
+    assert!(stderr.contains("flags: {testmode: sideways}"), "{}", stderr);
+    // and only there
+    assert_eq!(stderr.matches("not supported").count(), 1, "{}", stderr);
+    assert!(!stderr.contains("at flags.testmode"), "{}", stderr);
+}
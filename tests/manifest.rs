@@ -0,0 +1,111 @@
+mod common;
+
+use std::{fs, path::Path, process::Output};
+
+use common::{command, fresh_dir, run, stderr, stdout, test_file, tmp};
+
+fn convert(args: &[&str], name: &str) -> Output {
+    run(command(["--no-provenance"])
+        .args(args)
+        .arg(test_file("golden/legacy").join(name)))
+}
+
+#[test]
+fn outputs_are_recorded() {
+    let dir = tmp("manifest");
+    let _ = fs::remove_dir_all(&dir);
+    let manifest = dir.join("manifest.json");
+    let args = [
+        "--output-dir",
+        dir.to_str().unwrap(),
+        "--manifest",
+        manifest.to_str().unwrap(),
+    ];
+    for name in ["xfail.yaml", "translation.yaml"] {
+        let output = convert(&args, name);
+        assert!(output.status.success(), "{:?}", output);
+    }
+    let output = convert(&[&args[..], &["--format", "rust"]].concat(), "xfail.yaml");
+    assert!(output.status.success(), "{:?}", output);
+
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest).unwrap()).unwrap();
+    let files = json["files"].as_object().unwrap();
+    let names: Vec<_> = files.keys().collect();
+    assert_eq!(names, ["translation.yaml", "xfail.rs", "xfail.yaml"]);
+    let entry = &files["xfail.yaml"];
+    let size = fs::metadata(dir.join("xfail.yaml")).unwrap().len();
+    assert_eq!(entry["size"], size);
+    assert_eq!(entry["sha256"].as_str().unwrap().len(), 64);
+    assert!(entry["source"]
+        .as_str()
+        .unwrap()
+        .ends_with("tests/golden/legacy/xfail.yaml"));
+
+    // converting again replaces the entry
+    let output = convert(&args, "xfail.yaml");
+    assert!(output.status.success(), "{:?}", output);
+    let again: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest).unwrap()).unwrap();
+    assert_eq!(again, json);
+}
+
+#[test]
+fn a_manifest_needs_an_output_file() {
+    let output = convert(&["--manifest", "unused.json"], "xfail.yaml");
+    assert_eq!(output.status.code(), Some(2), "{:?}", output);
+    assert!(!Path::new("unused.json").exists());
+}
+
+#[test]
+fn outputs_of_deleted_sources_are_pruned() {
+    let dir = fresh_dir("prune");
+    let sources = dir.join("legacy");
+    let outputs = dir.join("normalized");
+    fs::create_dir_all(&sources).unwrap();
+    let golden = test_file("golden/legacy");
+    for name in ["kept.yaml", "renamed.yaml", "edited.yaml"] {
+        fs::copy(golden.join("xfail.yaml"), sources.join(name)).unwrap();
+    }
+    let manifest = outputs.join("manifest.json");
+    let run = |args: &[&str], name: &str| {
+        common::run(
+            command(["--no-provenance", "--output-dir"])
+                .arg(&outputs)
+                .arg("--manifest")
+                .arg(&manifest)
+                .args(args)
+                .arg(sources.join(name)),
+        )
+    };
+    for name in ["kept.yaml", "renamed.yaml", "edited.yaml"] {
+        assert!(run(&[], name).status.success());
+    }
+    fs::rename(sources.join("renamed.yaml"), sources.join("new-name.yaml")).unwrap();
+    fs::remove_file(sources.join("edited.yaml")).unwrap();
+    fs::write(outputs.join("edited.yaml"), "edited by hand\n").unwrap();
+
+    let output = run(&["--prune", "--dry-run"], "new-name.yaml");
+    assert!(output.status.success(), "{:?}", output);
+    let stderr = stderr(&output);
+    assert!(
+        stderr.contains("edited.yaml: the source is gone"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("would be kept"), "{}", stderr);
+    let stdout = stdout(&output);
+    assert!(stdout.contains("would remove"), "{}", stdout);
+    assert!(stdout.contains("renamed.yaml"), "{}", stdout);
+    assert!(outputs.join("renamed.yaml").exists());
+
+    let output = run(&["--prune"], "new-name.yaml");
+    // exit status 1 for the warning about the edited output
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    assert!(!outputs.join("renamed.yaml").exists());
+    assert!(outputs.join("edited.yaml").exists());
+    let json: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest).unwrap()).unwrap();
+    let names: Vec<_> = json["files"].as_object().unwrap().keys().collect();
+    assert_eq!(names, ["kept.yaml", "new-name.yaml"]);
+}
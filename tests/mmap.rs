@@ -0,0 +1,51 @@
+mod common;
+
+use std::{fs, path::Path};
+
+use common::{
+    command, convert, convert_file, convert_with, fixture, run, stderr, stdout, write_source,
+};
+use sha2::{Digest, Sha256};
+
+/// Convert the file at `path` with `--mmap`, like [`convert_file`].
+fn convert_mapped(path: &Path) -> String {
+    convert_with(path, &["--mmap"])
+}
+
+#[test]
+fn mapped_files_convert_like_read_files() {
+    for entry in fs::read_dir(fixture("")).unwrap() {
+        let path = entry.unwrap().path();
+        let name = path.file_name().unwrap().to_str().unwrap();
+        assert_eq!(convert_mapped(&path), convert(name));
+    }
+}
+
+#[test]
+fn mapped_utf16_files_are_transcoded() {
+    let fixture = fixture("block-style.yaml");
+    let mut utf16 = vec![0xff, 0xfe];
+    let utf8 = fs::read_to_string(&fixture).unwrap();
+    utf16.extend(utf8.encode_utf16().flat_map(u16::to_le_bytes));
+    let path = write_source("mapped-utf16.yaml", utf16);
+    assert_eq!(convert_mapped(&path), convert_file(&fixture));
+}
+
+#[test]
+fn provenance_is_taken_from_the_mapped_or_read_file() {
+    let mut utf16 = vec![0xff, 0xfe];
+    utf16.extend(
+        "table: a.ctb\ntests:\n  - [a, b]\n"
+            .encode_utf16()
+            .flat_map(u16::to_le_bytes),
+    );
+    let sha256 = format!("{:x}", Sha256::digest(&utf16));
+    let path = write_source("provenance-utf16.yaml", utf16);
+    for args in [&[][..], &["--mmap"]] {
+        let output = run(command(args).arg(&path));
+        assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+        let yaml = stdout(&output);
+        assert!(yaml.contains(&format!("sha256: {}\n", sha256)), "{}", yaml);
+        assert!(yaml.contains("transcoded_from: UTF-16LE\n"), "{}", yaml);
+    }
+}
@@ -0,0 +1,67 @@
+mod common;
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Output,
+};
+
+use common::{command, run, stderr, test_file, tmp};
+
+/// Convert the golden legacy file `name` into `dir` with the extra `args`.
+fn convert_into(dir: &Path, name: &str, args: &[&str]) -> Output {
+    run(command(["--no-provenance", "--output-dir"])
+        .arg(dir)
+        .args(args)
+        .arg(test_file("golden/legacy").join(name)))
+}
+
+fn output_dir(name: &str) -> PathBuf {
+    let dir = tmp(name);
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+#[test]
+fn placeholders_are_filled_in() {
+    let dir = output_dir("template");
+    let template = ["--output-template", "{table_stem}/{mode}/{stem}.norm.{ext}"];
+    let output = convert_into(&dir, "xfail.yaml", &template);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(dir.join("en-us-g1/forward/xfail.norm.yaml").is_file());
+
+    let args = [&template[..], &["--format", "rust"]].concat();
+    let output = convert_into(&dir, "test-modes.yaml", &args);
+    assert!(output.status.success(), "{:?}", output);
+    assert!(dir.join("en-us-g1/mixed/test-modes.norm.rs").is_file());
+}
+
+#[test]
+fn suite_placeholders_are_rejected_when_streaming() {
+    let dir = output_dir("template-stream");
+    let output = convert_into(
+        &dir,
+        "xfail.yaml",
+        &["--stream", "--output-template", "{mode}.yaml"],
+    );
+    assert_eq!(output.status.code(), Some(2));
+    let output = convert_into(
+        &dir,
+        "xfail.yaml",
+        &["--stream", "--output-template", "{stem}.x.{ext}"],
+    );
+    assert!(output.status.success(), "{:?}", output);
+    assert!(dir.join("xfail.x.yaml").is_file());
+}
+
+#[test]
+fn unknown_placeholders_are_rejected() {
+    let output = convert_into(
+        &output_dir("template-unknown"),
+        "xfail.yaml",
+        &["--output-template", "{name}.yaml"],
+    );
+    assert!(!output.status.success());
+    let stderr = stderr(&output);
+    assert!(stderr.contains("unknown placeholder {name}"), "{}", stderr);
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{command, fixture, run, stderr, stdout};
+
+#[test]
+fn position_arrays_of_the_wrong_length_are_reported() {
+    let path = fixture("positions.yaml");
+    let output = run(command(["--no-provenance"]).arg(&path));
+    assert_eq!(output.status.code(), Some(1), "{:?}", output);
+    let stderr = stderr(&output);
+    // human-readable warnings leave out their code
+    let warnings: Vec<_> = stderr
+        .lines()
+        .filter(|line| line.contains(" entries but the "))
+        .collect();
+    assert_eq!(
+        warnings,
+        [
+            "positions.yaml:6:3: warning: suite 1, test 2: outputPos has 3 entries but the input has 2 characters",
+            "positions.yaml:6:3: warning: suite 1, test 2: inputPos has 1 entries but the expected output has 2 characters",
+        ]
+        .map(|warning| format!("{}/{}", path.parent().unwrap().display(), warning))
+    );
+
+    let stdout = stdout(&output);
+    assert!(
+        stdout.contains("output_pos:\n    - 0\n    - 1\n    - 1\n"),
+        "{}",
+        stdout
+    );
+}
+
+#[test]
+fn cursors_outside_their_string_are_reported() {
+    let path = fixture("positions.yaml");
+    let output = run(command(["--no-provenance"]).arg(&path));
+    let stderr = stderr(&output);
+    let warnings: Vec<_> = stderr
+        .lines()
+        .filter(|line| line.contains(" is outside the "))
+        .collect();
+    assert_eq!(warnings.len(), 2, "{}", stderr);
+    assert!(
+        warnings[0].ends_with(
+            "positions.yaml:11:3: warning: suite 1, test 4: cursorPos 3 is outside the input, \
+             which has 3 characters"
+        ),
+        "{}",
+        warnings[0]
+    );
+    assert!(
+        warnings[1].contains("brlCursorPos 2 is outside the expected output"),
+        "{}",
+        warnings[1]
+    );
+
+    let stdout = stdout(&output);
+    assert!(
+        stdout.contains("cursor_pos: 1\n    brl_cursor_pos: 1\n"),
+        "{}",
+        stdout
+    );
+}
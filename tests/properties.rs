@@ -0,0 +1,210 @@
+//! Properties of the conversion of random legacy files within the known
+//! grammar: it does not fail, its output parses again and the suites read
+//! back are the ones parsed from the legacy file, with no test lost.
+
+mod common;
+
+use std::{fmt::Write, path::Path};
+
+use common::{convert_file, write_source};
+
+use louis_migrate_yaml::parse::{parse_source, ParseOptions};
+use proptest::{collection::vec, option, prelude::*};
+
+/// A scalar of a legacy file, written plain or double quoted
+#[derive(Debug, Clone)]
+enum Scalar {
+    Plain(String),
+    Quoted(String),
+}
+
+impl Scalar {
+    fn value(&self) -> &str {
+        match self {
+            Scalar::Plain(value) | Scalar::Quoted(value) => value,
+        }
+    }
+
+    fn yaml(&self) -> String {
+        match self {
+            Scalar::Plain(value) => value.clone(),
+            // a json string is a double quoted yaml scalar
+            Scalar::Quoted(value) => serde_json::to_string(value).unwrap(),
+        }
+    }
+}
+
+fn scalar() -> impl Strategy<Value = Scalar> {
+    prop_oneof![
+        "[a-zA-Z][a-zA-Z0-9]{0,7}".prop_map(Scalar::Plain),
+        "[⠁-⣿]{1,8}".prop_map(Scalar::Plain),
+        // plain scalars that yaml 1.1 would resolve to something else
+        proptest::sample::select(vec![
+            "yes", "No", "off", "null", "~", "true", "1.50", "0o17"
+        ])
+        .prop_map(|value| Scalar::Plain(value.to_string())),
+        "[a-zA-Z0-9 ⠀-⣿äöüßéç'\"\\\\:#,\\-\\[\\]{}&*!|>%@`?]{1,10}".prop_map(Scalar::Quoted),
+    ]
+}
+
+fn table() -> impl Strategy<Value = String> {
+    let name = "[a-z]{1,6}(-g[12])?\\.(ctb|utb|dis)";
+    prop_oneof![
+        name.prop_map(String::from),
+        vec(name, 1..4).prop_map(|names| format!("[{}]", names.join(", "))),
+        ("[a-z]{2}", 1..3u8)
+            .prop_map(|(language, grade)| format!("{{language: {}, grade: {}}}", language, grade)),
+    ]
+}
+
+fn modes() -> impl Strategy<Value = Vec<&'static str>> {
+    proptest::sample::subsequence(vec!["noContractions", "dotsIO", "ucBrl"], 1..3)
+}
+
+/// A test: input, expected output and the options of the test
+#[derive(Debug, Clone)]
+struct LegacyTest {
+    input: Scalar,
+    expected: Scalar,
+    options: Vec<String>,
+}
+
+fn test() -> impl Strategy<Value = LegacyTest> {
+    let xfail = prop_oneof![
+        any::<bool>().prop_map(|xfail| xfail.to_string()),
+        "[a-z]{1,5}( [a-z]{1,5}){1,2}".prop_map(|reason| format!("{:?}", reason)),
+    ];
+    (scalar(), scalar(), option::of(xfail), option::of(modes())).prop_map(
+        |(input, expected, xfail, modes)| {
+            let mut options = Vec::new();
+            if let Some(xfail) = xfail {
+                options.push(format!("xfail: {}", xfail));
+            }
+            if let Some(modes) = modes {
+                options.push(format!("mode: [{}]", modes.join(", ")));
+            }
+            LegacyTest {
+                input,
+                expected,
+                options,
+            }
+        },
+    )
+}
+
+/// A `tests` block with the settings given before it
+#[derive(Debug, Clone)]
+struct Block {
+    display: Option<String>,
+    table: Option<String>,
+    flags: Option<(&'static str, Option<Vec<&'static str>>)>,
+    tests: Vec<LegacyTest>,
+}
+
+fn block() -> impl Strategy<Value = Block> {
+    let test_mode = prop_oneof![Just("forward"), Just("backward"), Just("bothDirections")];
+    (
+        option::of("[a-z]{1,6}\\.dis"),
+        option::of(table()),
+        option::of((test_mode, option::of(modes()))),
+        vec(test(), 1..6),
+    )
+        .prop_map(|(display, table, flags, tests)| Block {
+            display,
+            table,
+            flags,
+            tests,
+        })
+}
+
+/// A legacy file: its blocks, the first one with a table
+fn legacy_file() -> impl Strategy<Value = Vec<Block>> {
+    (table(), vec(block(), 1..4)).prop_map(|(table, mut blocks)| {
+        blocks[0].table.get_or_insert(table);
+        blocks
+    })
+}
+
+fn render(blocks: &[Block]) -> String {
+    let mut source = String::new();
+    for block in blocks {
+        if let Some(display) = &block.display {
+            writeln!(source, "display: {}", display).unwrap();
+        }
+        if let Some(table) = &block.table {
+            writeln!(source, "table: {}", table).unwrap();
+        }
+        if let Some((test_mode, modes)) = &block.flags {
+            match modes {
+                Some(modes) => writeln!(
+                    source,
+                    "flags: {{testmode: {}, mode: [{}]}}",
+                    test_mode,
+                    modes.join(", ")
+                ),
+                None => writeln!(source, "flags: {{testmode: {}}}", test_mode),
+            }
+            .unwrap();
+        }
+        source.push_str("tests:\n");
+        for test in &block.tests {
+            write!(
+                source,
+                "  - [{}, {}",
+                test.input.yaml(),
+                test.expected.yaml()
+            )
+            .unwrap();
+            if !test.options.is_empty() {
+                write!(source, ", {{{}}}", test.options.join(", ")).unwrap();
+            }
+            source.push_str("]\n");
+        }
+    }
+    source
+}
+
+proptest! {
+    // each case of the round trip runs the converter, and failures are
+    // not persisted as they could only be kept next to a lib.rs
+    #![proptest_config(ProptestConfig {
+        cases: 64,
+        failure_persistence: None,
+        ..ProptestConfig::default()
+    })]
+
+    #[test]
+    fn parsing_keeps_every_test(blocks in legacy_file()) {
+        let source = render(&blocks);
+        let (suites, _) = parse_source(Path::new("random.yaml"), &source, &ParseOptions::default())
+            .map_err(|e| TestCaseError::fail(format!("{:#}\n{}", e, source)))?;
+        prop_assert_eq!(suites.len(), blocks.len());
+        for (suite, block) in suites.iter().zip(&blocks) {
+            prop_assert_eq!(suite.tests.len(), block.tests.len());
+            for (test, legacy) in suite.tests.iter().zip(&block.tests) {
+                prop_assert_eq!(&test.input, legacy.input.value());
+                prop_assert_eq!(&test.expected, legacy.expected.value());
+            }
+        }
+    }
+
+    #[test]
+    fn converted_output_parses_back_to_the_same_suites(blocks in legacy_file()) {
+        let source = render(&blocks);
+        let output = convert_file(&write_source("random-legacy.yaml", &source));
+        let (expected, _) =
+            parse_source(Path::new("random.yaml"), &source, &ParseOptions::default()).unwrap();
+        let (mut suites, _) =
+            parse_source(Path::new("random.yaml"), &output, &ParseOptions::default())
+                .map_err(|e| TestCaseError::fail(format!("{:#}\n{}", e, output)))?;
+        // the conversion gives every test an id
+        for test in suites.iter_mut().flat_map(|suite| &mut suite.tests) {
+            prop_assert!(test.id.take().is_some());
+        }
+        prop_assert_eq!(
+            serde_yaml::to_string(&suites).unwrap(),
+            serde_yaml::to_string(&expected).unwrap(),
+            "{}", source
+        );
+    }
+}
@@ -0,0 +1,31 @@
+mod common;
+
+use common::{command, run, stderr, test_file};
+
+/// Convert the golden legacy file `name` verbosely and return what was
+/// logged.
+fn log(name: &str) -> String {
+    let path = test_file("golden/legacy").join(name);
+    let output = run(command(["-v", "--no-provenance"]).arg(&path));
+    assert!(output.status.success(), "{:?}", output);
+    stderr(&output)
+}
+
+#[test]
+fn regular_files_are_deserialized_directly() {
+    let log = log("regular.yaml");
+    assert!(
+        log.contains("regular.yaml: parsed as a regular file"),
+        "{}",
+        log
+    );
+}
+
+#[test]
+fn other_files_are_parsed_event_by_event() {
+    for name in ["test-modes.yaml", "xfail.yaml"] {
+        let log = log(name);
+        let expected = format!("{}: parsing event by event", name);
+        assert!(log.contains(&expected), "{}", log);
+    }
+}
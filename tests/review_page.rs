@@ -0,0 +1,23 @@
+mod common;
+
+use common::{command, run, stdout, write_source};
+
+#[test]
+fn braille_cells_show_their_dots() {
+    let path = write_source(
+        "review.yaml",
+        "table: foo.ctb\ntests:\n  - ['<a>', ⠁⠃, {xfail: bug}]\n",
+    );
+    let output = run(command(["--format", "html", "--no-ids"]).arg(&path));
+    assert!(output.status.success());
+    let html = stdout(&output);
+    assert!(
+        html.contains(
+            "<tr class=\"xfail\"><td>1</td><td></td><td class=\"braille\">&lt;a&gt;</td>\
+         <td class=\"braille\"><span title=\"dots 1\">⠁</span><span title=\"dots 12\">⠃</span></td>\
+         <td>yes: bug</td></tr>"
+        ),
+        "{}",
+        html
+    );
+}
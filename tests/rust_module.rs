@@ -0,0 +1,32 @@
+mod common;
+
+use std::{fs, process::Command};
+
+use common::{command, fixture, fresh_dir, run};
+
+/// The fixtures converted to Rust modules compile.
+#[test]
+fn rust_modules_compile() {
+    let dir = fresh_dir("rust-modules");
+    for entry in fs::read_dir(fixture("")).unwrap() {
+        let path = entry.unwrap().path();
+        let output = run(command(["--format", "rust", "--output-dir"])
+            .arg(&dir)
+            .arg(&path));
+        assert!(output.status.code().unwrap() <= 1, "{}", path.display());
+
+        let module = dir.join(path.with_extension("rs").file_name().unwrap());
+        let output = Command::new("rustc")
+            .args(["--crate-type", "lib", "--edition", "2021", "--out-dir"])
+            .arg(&dir)
+            .arg(&module)
+            .output()
+            .expect("failed to run rustc");
+        assert!(
+            output.status.success(),
+            "{}: {}",
+            module.display(),
+            String::from_utf8_lossy(&output.stderr)
+        );
+    }
+}
@@ -0,0 +1,56 @@
+mod common;
+
+use common::{convert, convert_with, fixture};
+
+/// Strings that would be read back as booleans, null, numbers or
+/// timestamps under yaml 1.1 or 1.2 if written unquoted
+const AMBIGUOUS: &[&str] = &[
+    "no",
+    "yes",
+    "on",
+    "OFF",
+    "y",
+    "N",
+    "1.0",
+    "1_000",
+    "~",
+    "null",
+    "0x1F",
+    "0o17",
+    "1:20",
+    "+.inf",
+    ".NaN",
+    "True",
+    "<<",
+    "=",
+    "2001-12-14t21:59:43.10-05:00",
+    "2001-12-14 21:59:43.10 -5",
+    "1e3",
+    "-.5",
+    "0b1010",
+    "Null",
+];
+
+fn assert_quoted(yaml: &str) {
+    for s in AMBIGUOUS {
+        let quoted = format!("'{}'", s);
+        assert!(yaml.contains(&quoted), "{} is not quoted:\n{}", s, yaml);
+    }
+}
+
+#[test]
+fn ambiguous_strings_are_quoted() {
+    assert_quoted(&convert("ambiguous-scalars.yaml"));
+
+    assert_quoted(&convert_with(
+        &fixture("ambiguous-scalars.yaml"),
+        &["--test-style", "flow"],
+    ));
+}
+
+#[test]
+fn other_strings_stay_plain() {
+    let yaml = convert("ambiguous-scalars.yaml");
+    assert!(yaml.contains("input: 123abc\n"), "{}", yaml);
+    assert!(yaml.contains("table: en-us-g1.ctb\n"), "{}", yaml);
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::{command, run, stdout, test_file, tmp};
+
+/// Shard the golden normalized files into `dir` and return what was
+/// printed.
+fn shard(dir: &PathBuf, args: &[&str]) -> String {
+    let output = run(command(["shard"])
+        .arg(test_file("golden/normalized"))
+        .arg("--output-dir")
+        .arg(dir)
+        .args(args));
+    assert!(output.status.success(), "{:?}", output);
+    stdout(&output)
+}
+
+#[test]
+fn shards_are_balanced_by_tests_and_deterministic() {
+    let dir = tmp("shards");
+    let _ = fs::remove_dir_all(&dir);
+    let summary = shard(&dir, &["--shards", "3"]);
+    let tests: Vec<usize> = summary
+        .lines()
+        .map(|line| {
+            let tests = line.split(", ").nth(1).unwrap();
+            tests.trim_end_matches(" test(s)").parse().unwrap()
+        })
+        .collect();
+    assert_eq!(tests.len(), 3, "{}", summary);
+    let (min, max) = (tests.iter().min().unwrap(), tests.iter().max().unwrap());
+    assert!(max - min <= 3, "{}", summary);
+
+    let manifests: Vec<String> = (1..=3)
+        .map(|i| fs::read_to_string(dir.join(format!("shard-{}.txt", i))).unwrap())
+        .collect();
+    let files = manifests
+        .iter()
+        .flat_map(|manifest| manifest.lines())
+        .count();
+    let golden = fs::read_dir(test_file("golden/normalized"))
+        .unwrap()
+        .count();
+    assert_eq!(files, golden);
+
+    assert_eq!(shard(&dir, &["--shards", "3"]), summary);
+    for (i, manifest) in manifests.iter().enumerate() {
+        let again = fs::read_to_string(dir.join(format!("shard-{}.txt", i + 1))).unwrap();
+        assert_eq!(&again, manifest);
+    }
+}
@@ -0,0 +1,62 @@
+mod common;
+
+use std::{fs, path::Path, process::Output};
+
+use common::{command, fresh_dir, run, stderr, stdout};
+
+/// Take a snapshot of `dir` with `args`, without a cache.
+fn snapshot(dir: &Path, args: &[&str]) -> Output {
+    run(command(["snapshot", "--no-cache"]).arg(dir).args(args))
+}
+
+#[test]
+fn an_unchanged_corpus_matches_its_snapshot() {
+    let dir = fresh_dir("snapshot");
+    let corpus = dir.join("corpus");
+    fs::create_dir_all(corpus.join("sub")).unwrap();
+    fs::write(corpus.join("a.yaml"), "table: a.ctb\ntests:\n  - [a, ⠁]\n").unwrap();
+    fs::write(
+        corpus.join("sub/b.yaml"),
+        "table: b.ctb\ntests:\n  - [b, ⠃]\n",
+    )
+    .unwrap();
+    let manifest = dir.join("manifest.json");
+
+    let output = snapshot(&corpus, &["--output", manifest.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    let written: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&manifest).unwrap()).unwrap();
+    let files: Vec<_> = written.as_object().unwrap().keys().collect();
+    assert_eq!(files, ["a.yaml", "sub/b.yaml"]);
+
+    let output = snapshot(&corpus, &["--compare", manifest.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert_eq!(stdout(&output), "");
+}
+
+#[test]
+fn changed_files_are_listed() {
+    let dir = fresh_dir("snapshot-changed");
+    let corpus = dir.join("corpus");
+    fs::create_dir_all(&corpus).unwrap();
+    fs::write(corpus.join("a.yaml"), "table: a.ctb\ntests:\n  - [a, ⠁]\n").unwrap();
+    fs::write(corpus.join("b.yaml"), "table: b.ctb\ntests:\n  - [b, ⠃]\n").unwrap();
+    let manifest = dir.join("manifest.json");
+    let output = snapshot(&corpus, &["--output", manifest.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+
+    fs::write(corpus.join("a.yaml"), "table: a.ctb\ntests:\n  - [a, ⠁⠁]\n").unwrap();
+    fs::remove_file(corpus.join("b.yaml")).unwrap();
+    fs::write(corpus.join("c.yaml"), "table: c.ctb\ntests:\n  - [c, ⠉]\n").unwrap();
+    let output = snapshot(&corpus, &["--compare", manifest.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    assert_eq!(
+        stdout(&output),
+        "changed: a.yaml\nadded: c.yaml\nremoved: b.yaml\n"
+    );
+    assert!(
+        stderr(&output).contains("the conversion of 3 file(s) changed"),
+        "{}",
+        stderr(&output)
+    );
+}
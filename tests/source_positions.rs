@@ -0,0 +1,46 @@
+mod common;
+
+use common::{command, fixture, stderr, stdout, write_source};
+
+/// Run louis-migrate-yaml with `args` and return what it printed to
+/// stdout and stderr.
+fn run(args: &[&str]) -> (String, String) {
+    let output = common::run(&mut command(args));
+    (stdout(&output), stderr(&output))
+}
+
+#[test]
+fn positions_are_only_written_on_request() {
+    let fixture = fixture("block-style.yaml");
+    let fixture = fixture.to_str().unwrap();
+    let (plain, _) = run(&["--no-provenance", fixture]);
+    assert!(!plain.contains("source:"), "{}", plain);
+
+    let (located, _) = run(&["--no-provenance", "--source-positions", fixture]);
+    assert!(
+        located.contains("mode: forward\n  source:\n    line: 7\n    column: 1\n"),
+        "{}",
+        located
+    );
+    assert!(
+        located.contains(
+            "input: abc\n    expected: ⠁⠃⠉\n    source:\n      line: 8\n      column: 3\n"
+        ),
+        "{}",
+        located
+    );
+}
+
+#[test]
+fn lint_findings_carry_their_position() {
+    let path = write_source(
+        "duplicate-test.yaml",
+        "table: a.ctb\ntests:\n  - [foo, bar]\n  - [foo, bar]\n",
+    );
+    let (_, findings) = run(&["lint", path.to_str().unwrap()]);
+    assert!(
+        findings.contains("duplicate-test.yaml:4:3: warning[duplicate-test]"),
+        "{}",
+        findings
+    );
+}
@@ -0,0 +1,17 @@
+mod common;
+
+use common::{convert_with, test_file};
+
+#[test]
+fn whitespace_separated_tables_become_a_list() {
+    let yaml = convert_with(
+        &test_file("golden/legacy/table-forms.yaml"),
+        &["--split-table-scalar"],
+    );
+    assert!(
+        yaml.contains("- table:\n  - unicode.dis\n  - en-us-g2.ctb\n"),
+        "{}",
+        yaml
+    );
+    assert!(yaml.contains("- table: en-us-g2.ctb\n"), "{}", yaml);
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use std::fs;
+
+use common::{command, run, stderr, stdout, test_file, tmp, write_source};
+
+/// Convert `source` with `--format csv` and the extra `args`.
+fn csv(name: &str, source: &str, args: &[&str]) -> String {
+    let path = write_source(&format!("{}.yaml", name), source);
+    let output = run(command(["--format", "csv"]).args(args).arg(&path));
+    assert!(output.status.success(), "{}", stderr(&output));
+    stdout(&output)
+}
+
+const SOURCE: &str = r#"table: [a.ctb, b.ctb]
+tests:
+  - ['a, "b"', x]
+  - [c, y, {xfail: not yet}]
+"#;
+
+#[test]
+fn fields_are_quoted() {
+    assert_eq!(
+        csv("csv-quoting", SOURCE, &[]),
+        "table,direction,input,expected,xfail,reason\n\
+         \"a.ctb,b.ctb\",forward,\"a, \"\"b\"\"\",x,false,\n\
+         \"a.ctb,b.ctb\",forward,c,y,true,not yet\n"
+    );
+}
+
+#[test]
+fn tabs_delimit_tsv() {
+    assert_eq!(
+        csv("tsv", SOURCE, &["--delimiter", "\t"]),
+        "table\tdirection\tinput\texpected\txfail\treason\n\
+         a.ctb,b.ctb\tforward\t\"a, \"\"b\"\"\"\tx\tfalse\t\n\
+         a.ctb,b.ctb\tforward\tc\ty\ttrue\tnot yet\n"
+    );
+}
+
+/// Tests exported as CSV and imported again are the same, as far as the
+/// columns go.
+#[test]
+fn import_reads_the_export_back() {
+    let legacy = test_file("golden/legacy/xfail.yaml");
+    let exported = tmp("xfail.csv");
+    let imported = tmp("xfail-imported.yaml");
+    let output = run(command(["--format", "csv", "-o"])
+        .arg(&exported)
+        .arg(&legacy));
+    assert!(output.status.success(), "{}", stderr(&output));
+    let output = run(command(["import-csv"])
+        .arg(&exported)
+        .arg("-o")
+        .arg(&imported));
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        fs::read_to_string(&imported).unwrap(),
+        fs::read_to_string(test_file("golden/normalized/xfail.yaml")).unwrap()
+    );
+}
@@ -0,0 +1,18 @@
+mod common;
+
+use std::fs;
+
+use common::{convert_file, convert_with, test_file};
+
+#[test]
+fn streaming_writes_the_same_output() {
+    for entry in fs::read_dir(test_file("fixtures")).unwrap() {
+        let path = entry.unwrap().path();
+        assert_eq!(
+            convert_with(&path, &["--stream"]),
+            convert_file(&path),
+            "{}",
+            path.display()
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! The streaming library API yields the same suites as parsing a file
+//! whole, one at a time.
+
+use std::{fs, path::Path};
+
+use louis_migrate_yaml::parse::{parse_file, ParseOptions, TestSuiteIter};
+
+#[test]
+fn suites_are_the_same_as_parsed_whole() {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    for dir in ["legacy", "normalized"] {
+        for entry in fs::read_dir(golden.join(dir)).unwrap() {
+            let path = entry.unwrap().path();
+            let (expected, warnings) = parse_file(&path, &ParseOptions::default()).unwrap();
+            let mut suites = TestSuiteIter::new(fs::File::open(&path).unwrap()).unwrap();
+            let actual: Vec<_> = suites.by_ref().map(Result::unwrap).collect();
+            assert_eq!(
+                serde_yaml::to_string(&actual).unwrap(),
+                serde_yaml::to_string(&expected).unwrap(),
+                "{}",
+                path.display()
+            );
+            assert_eq!(suites.warnings(), warnings, "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn suites_before_an_error_are_yielded() {
+    let source = "\
+table: a.ctb
+tests:
+  - [a, b]
+tests:
+  - [c]
+tests:
+  - [d, e]
+";
+    let mut suites = TestSuiteIter::new(source.as_bytes()).unwrap();
+    assert_eq!(suites.next().unwrap().unwrap().tests[0].input, "a");
+    let error = suites.next().unwrap().unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("without expected output"),
+        "{:#}",
+        error
+    );
+    assert!(suites.next().is_none());
+}
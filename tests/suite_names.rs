@@ -0,0 +1,49 @@
+mod common;
+
+use common::{command, run, stdout, test_file};
+
+/// Convert the golden legacy file `name` with `args` and return the
+/// emitted yaml.
+fn convert(name: &str, args: &[&str]) -> String {
+    let path = test_file("golden/legacy").join(name);
+    let output = run(command(["--no-provenance", "--name-suites"])
+        .args(args)
+        .arg(&path));
+    assert!(output.status.success(), "{:?}", output);
+    stdout(&output)
+}
+
+fn names(yaml: &str) -> Vec<&str> {
+    yaml.lines()
+        .filter_map(|line| line.strip_prefix("- name: "))
+        .collect()
+}
+
+#[test]
+fn suites_are_numbered_by_direction() {
+    let yaml = convert("test-modes.yaml", &[]);
+    assert_eq!(
+        names(&yaml),
+        [
+            "test-modes_backward#1",
+            "test-modes_bothDirections#1",
+            "test-modes_display#1",
+            "test-modes_hyphenate#1"
+        ]
+    );
+    let yaml = convert("table-forms.yaml", &[]);
+    let expected: Vec<_> = (1..=5)
+        .map(|i| format!("table-forms_forward#{}", i))
+        .collect();
+    assert_eq!(names(&yaml), expected);
+}
+
+#[test]
+fn names_do_not_depend_on_streaming_or_filtering() {
+    assert_eq!(
+        convert("table-forms.yaml", &[]),
+        convert("table-forms.yaml", &["--stream"])
+    );
+    let yaml = convert("test-modes.yaml", &["--mode", "display"]);
+    assert_eq!(names(&yaml), ["test-modes_display#1"]);
+}
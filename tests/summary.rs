@@ -0,0 +1,73 @@
+mod common;
+
+use std::{fs, path::PathBuf};
+
+use common::{command, fresh_dir, run, stdout};
+
+/// Write legacy files for three locales into a fresh directory.
+fn corpus() -> PathBuf {
+    let dir = fresh_dir("summary-locales");
+    let files = [
+        (
+            "de-g1.yaml",
+            "table: de-g1.ctb\ntests:\n  - [a, ⠁]\n  - [b, ⠃]\n",
+        ),
+        (
+            "de-g2.yaml",
+            "table: [unicode.dis, de-g2.ctb]\ntests:\n  - [a, ⠁, {xfail: true}]\n",
+        ),
+        (
+            "math.yaml",
+            "table: {language: nemeth}\ntests:\n  - [1, ⠂]\n",
+        ),
+        ("en-us-g1.yaml", "table: en-us-g1.ctb\ntests:\n  - [a]\n"),
+    ];
+    for (name, contents) in files {
+        fs::write(dir.join(name), contents).unwrap();
+    }
+    dir
+}
+
+fn summary(args: &[&str]) -> String {
+    let output = run(command(["summary"]).args(args).arg(corpus()));
+    assert!(output.status.success(), "{:?}", output);
+    stdout(&output)
+}
+
+#[test]
+fn files_are_grouped_by_locale() {
+    let md = summary(&["--group-by-locale"]);
+    let locales: Vec<_> = md
+        .lines()
+        .skip_while(|line| !line.starts_with("| Locale"))
+        .skip(2)
+        .take_while(|line| line.starts_with('|'))
+        .collect();
+    assert_eq!(
+        locales,
+        [
+            "| de | 2 | 2 | 3 | 1 | 0 |",
+            "| en | 1 | 0 | 0 | 0 | 1 |",
+            "| nemeth | 1 | 1 | 1 | 0 | 0 |"
+        ],
+        "{}",
+        md
+    );
+    let headings: Vec<_> = md
+        .lines()
+        .filter_map(|line| line.strip_prefix("## "))
+        .map(|heading| heading.rsplit('/').next().unwrap())
+        .collect();
+    assert_eq!(
+        headings,
+        ["de-g1.yaml", "de-g2.yaml", "en-us-g1.yaml", "math.yaml"]
+    );
+
+    let html = summary(&["--group-by-locale", "--format", "html"]);
+    assert!(html.contains("<tr><td>nemeth</td><td>1</td>"), "{}", html);
+}
+
+#[test]
+fn locales_are_left_out_by_default() {
+    assert!(!summary(&[]).contains("| Locale"));
+}
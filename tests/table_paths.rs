@@ -0,0 +1,63 @@
+mod common;
+
+use common::{command, convert_with, fixture, run, stderr, stdout};
+
+#[test]
+fn table_prefixes_are_rewritten() {
+    let yaml = convert_with(
+        &fixture("table-paths.yaml"),
+        &[
+            "--map-table-prefix",
+            "tables/de-g2.ctb=de.ctb",
+            "--map-table-prefix",
+            "tables=liblouis/tables",
+        ],
+    );
+    assert!(
+        yaml.starts_with(
+            "\
+- display_table: liblouis/tables/unicode.dis
+  table:
+  - liblouis/tables/en-ueb-g1.ctb
+  - tables-old/chardefs.uti
+"
+        ),
+        "prefixes match whole components only\n{}",
+        yaml
+    );
+    assert!(
+        yaml.contains("- display_table: liblouis/tables/unicode.dis\n  table: de.ctb\n"),
+        "the first matching prefix wins\n{}",
+        yaml
+    );
+}
+
+#[test]
+fn table_paths_are_canonicalized_and_duplicates_dropped() {
+    let output = run(command(["--no-provenance"]).arg(fixture("table-paths.yaml")));
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert!(
+        stderr(&output)
+            .contains("warning: suite 1: table tables/en-ueb-g1.ctb is listed more than once"),
+        "{}",
+        stderr(&output)
+    );
+    let yaml = stdout(&output);
+    assert!(
+        yaml.starts_with(
+            "\
+- display_table: tables/unicode.dis
+  table:
+  - tables/en-ueb-g1.ctb
+  - tables-old/chardefs.uti
+"
+        ),
+        "{}",
+        yaml
+    );
+    assert!(
+        yaml.contains("- display_table: tables/unicode.dis\n  table: tables/de-g2.ctb\n"),
+        "{}",
+        yaml
+    );
+}
@@ -0,0 +1,63 @@
+mod common;
+
+use common::{command, fixture, run, stdout};
+
+/// Convert the fixture `tagged.yaml` with `args` and return the emitted
+/// yaml.
+fn convert(args: &[&str]) -> String {
+    let output = run(command(["--no-provenance"])
+        .args(args)
+        .arg(fixture("tagged.yaml")));
+    assert!(output.status.success(), "{:?}", output);
+    stdout(&output)
+}
+
+#[test]
+fn tags_are_given_on_the_command_line() {
+    let yaml = convert(&["--tag", "ueb", "--tag", "g1", "--tag-file-name"]);
+    assert_eq!(
+        yaml.matches("tags:\n  - g1\n  - tagged\n  - ueb\n").count(),
+        2,
+        "{}",
+        yaml
+    );
+}
+
+#[test]
+fn tags_are_read_from_comments() {
+    let yaml = convert(&["--comment-tags"]);
+    assert!(
+        yaml.contains("- tags:\n  - ueb\n  table: en-ueb-g1.ctb"),
+        "{}",
+        yaml
+    );
+    assert!(
+        yaml.contains("tags:\n    - issue-1234\n    - regression\n    input: abc"),
+        "{}",
+        yaml
+    );
+    assert!(yaml.contains("tags:\n  - hyphenation\n"), "{}", yaml);
+    assert!(!yaml.contains("comments"), "{}", yaml);
+
+    let yaml = convert(&["--comment-tags", "--comments"]);
+    assert!(yaml.contains("comments:\n  - grade 1 words\n"), "{}", yaml);
+    assert!(!yaml.contains("# tags"), "{}", yaml);
+}
+
+#[test]
+fn tests_are_selected_by_tag() {
+    let yaml = convert(&[
+        "--comment-tags",
+        "--only-tag",
+        "regression",
+        "--only-tag",
+        "hyphenation",
+    ]);
+    assert!(yaml.contains("input: abc"), "{}", yaml);
+    assert!(!yaml.contains("input: foo\n"), "{}", yaml);
+    assert!(yaml.contains("input: foobar"), "{}", yaml);
+
+    let yaml = convert(&["--comment-tags", "--skip-tag", "ueb"]);
+    assert!(!yaml.contains("input: abc"), "{}", yaml);
+    assert!(yaml.contains("input: foobar"), "{}", yaml);
+}
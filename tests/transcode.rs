@@ -0,0 +1,38 @@
+mod common;
+
+use common::{command, convert, fixture, run, stderr, stdout};
+
+/// The fixtures with the tests of `block-style.yaml` in other encodings,
+/// and the encoding each is transcoded from.
+const ENCODED: [(&str, Option<&str>); 3] = [
+    // with a byte order mark
+    ("utf16le.yaml", Some("UTF-16LE")),
+    // without one, recognized by its null bytes
+    ("utf16be.yaml", Some("UTF-16BE")),
+    ("utf8-bom.yaml", None),
+];
+
+#[test]
+fn encoded_files_convert_like_utf8() {
+    let expected = convert("block-style.yaml");
+    for (name, _) in ENCODED {
+        assert_eq!(convert(name), expected, "{}", name);
+    }
+}
+
+#[test]
+fn provenance_names_the_original_encoding() {
+    for (name, encoding) in ENCODED {
+        let output = run(&mut command([fixture(name)]));
+        assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+        let yaml = stdout(&output);
+        match encoding {
+            Some(encoding) => assert!(
+                yaml.contains(&format!("transcoded_from: {}\n", encoding)),
+                "{}",
+                yaml
+            ),
+            None => assert!(!yaml.contains("transcoded_from"), "{}", yaml),
+        }
+    }
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use std::{path::PathBuf, process::Output};
+
+use common::{command, run, stderr, stdout, write_source};
+
+/// A source with an unknown test option, written for the test `name`.
+fn source(name: &str) -> PathBuf {
+    write_source(
+        &format!("unknown-keys-{}.yaml", name),
+        "table: a.ctb\ntests:\n  - [a, ⠁, {frob: 2, xfail: true}]\n",
+    )
+}
+
+fn convert(policy: &str) -> Output {
+    run(command(["--no-provenance", "--unknown-keys", policy]).arg(source(policy)))
+}
+
+#[test]
+fn unknown_options_fail_the_conversion_by_default() {
+    for output in [
+        run(command(["--no-provenance"]).arg(source("default"))),
+        convert("error"),
+    ] {
+        assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+        assert!(
+            stderr(&output).contains("Unknown test option \"frob\""),
+            "{}",
+            stderr(&output)
+        );
+        assert_eq!(stdout(&output), "");
+    }
+}
+
+#[test]
+fn unknown_options_are_dropped_with_a_warning() {
+    let output = convert("warn");
+    assert_eq!(output.status.code(), Some(1), "{}", stderr(&output));
+    assert!(
+        stderr(&output).contains("warning: ignoring unknown test option \"frob\""),
+        "{}",
+        stderr(&output)
+    );
+    let yaml = stdout(&output);
+    assert!(yaml.contains("    xfail: true\n"), "{}", yaml);
+    assert!(!yaml.contains("frob"), "{}", yaml);
+}
+
+#[test]
+fn unknown_options_are_preserved() {
+    let output = convert("preserve");
+    assert_eq!(output.status.code(), Some(0), "{}", stderr(&output));
+    assert!(
+        stdout(&output).contains("    xfail: true\n    extra:\n      frob: 2\n"),
+        "{}",
+        stdout(&output)
+    );
+}
@@ -0,0 +1,39 @@
+//! Needs liblouis with its tables, so it only runs with the `run-tests`
+//! feature.
+#![cfg(feature = "run-tests")]
+
+mod common;
+
+use common::{command, run, stderr, stdout, tmp, write_source};
+
+#[test]
+fn xfail_values_read_differently_by_the_harness_are_mismatches() {
+    let legacy = write_source(
+        "equivalence.yaml",
+        "\
+table: [unicode.dis, en-us-g1.ctb]
+tests:
+  - [a, ⠁]
+  - [b, ⠁, {xfail: no}]
+",
+    );
+    let normalized = tmp("equivalence-normalized.yaml");
+    let output = run(command(["--no-provenance", "-o"])
+        .arg(&normalized)
+        .arg(&legacy));
+    assert!(output.status.code().unwrap() <= 1, "{}", stderr(&output));
+
+    let output = run(command(["verify-equivalence"])
+        .arg(&legacy)
+        .arg(&normalized));
+    assert_eq!(output.status.code(), Some(2), "{}", stderr(&output));
+    let report: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    assert_eq!(report["equivalent"], false);
+    assert_eq!(report["tests"], 2);
+    let mismatches = report["mismatches"].as_array().unwrap();
+    assert_eq!(mismatches.len(), 1, "{:#}", report);
+    // the harness expects `xfail: no` to fail, the conversion does not
+    assert_eq!(mismatches[0]["test"], 2);
+    assert_eq!(mismatches[0]["legacy"]["forward"], "expected-fail");
+    assert_eq!(mismatches[0]["normalized"]["forward"], "fail");
+}
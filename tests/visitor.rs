@@ -0,0 +1,127 @@
+//! The visitor API sees the same suites and tests as parsing a file whole,
+//! in the order of the file.
+
+use std::{fs, path::Path};
+
+use anyhow::{bail, Result};
+use louis_migrate_yaml::{
+    normalized::{Table, Test, TestSuite},
+    parse::{parse_file, visit_file, visit_source, Flags, ParseOptions},
+    visit::{SuiteBuilder, TestFileVisitor},
+};
+
+/// Records the callbacks it gets, one line each
+#[derive(Default)]
+struct Recorder {
+    calls: Vec<String>,
+}
+
+impl TestFileVisitor for Recorder {
+    fn on_table(&mut self, table: &Table) -> Result<()> {
+        self.calls.push(format!("table {:?}", table));
+        Ok(())
+    }
+
+    fn on_flags(&mut self, flags: &Flags) -> Result<()> {
+        self.calls.push(format!("flags {:?}", flags.test_mode));
+        Ok(())
+    }
+
+    fn on_suite_start(&mut self, suite: &TestSuite) -> Result<()> {
+        assert!(suite.tests.is_empty());
+        self.calls.push("suite".to_string());
+        Ok(())
+    }
+
+    fn on_test(&mut self, test: Test) -> Result<()> {
+        self.calls.push(format!("test {}", test.input));
+        Ok(())
+    }
+
+    fn on_suite_end(&mut self) -> Result<()> {
+        self.calls.push("end".to_string());
+        Ok(())
+    }
+}
+
+#[test]
+fn suite_builder_builds_the_parsed_suites() {
+    let golden = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    for dir in ["legacy", "normalized"] {
+        for entry in fs::read_dir(golden.join(dir)).unwrap() {
+            let path = entry.unwrap().path();
+            let (expected, expected_warnings) =
+                parse_file(&path, &ParseOptions::default()).unwrap();
+            let mut builder = SuiteBuilder::default();
+            let warnings = visit_file(&path, &ParseOptions::default(), &mut builder).unwrap();
+            assert_eq!(
+                serde_yaml::to_string(&builder.into_suites()).unwrap(),
+                serde_yaml::to_string(&expected).unwrap(),
+                "{}",
+                path.display()
+            );
+            assert_eq!(warnings, expected_warnings, "{}", path.display());
+        }
+    }
+}
+
+#[test]
+fn callbacks_follow_the_file() {
+    let source = "\
+table: a.ctb
+flags: {testmode: backward}
+tests:
+  - [a, b]
+  - [c, d]
+tests: []
+table: b.ctb
+tests:
+  - [e, f]
+";
+    let mut recorder = Recorder::default();
+    let warnings = visit_source(
+        Path::new("a.yaml"),
+        source,
+        &ParseOptions::default(),
+        &mut recorder,
+    )
+    .unwrap();
+    assert_eq!(
+        recorder.calls,
+        [
+            "table Single(\"a.ctb\")",
+            "flags Backward",
+            "suite",
+            "test a",
+            "test c",
+            "end",
+            "table Single(\"b.ctb\")",
+            "suite",
+            "test e",
+            "end",
+        ]
+    );
+    assert!(warnings.iter().any(|w| w.code == "empty-tests"));
+}
+
+#[test]
+fn errors_of_callbacks_stop_parsing() {
+    struct Stop(usize);
+    impl TestFileVisitor for Stop {
+        fn on_test(&mut self, _test: Test) -> Result<()> {
+            self.0 += 1;
+            bail!("enough")
+        }
+    }
+    let source = "table: a.ctb\ntests:\n  - [a, b]\n  - [c, d]\n";
+    let mut stop = Stop(0);
+    let error = visit_source(
+        Path::new("a.yaml"),
+        source,
+        &ParseOptions::default(),
+        &mut stop,
+    )
+    .unwrap_err();
+    assert!(format!("{:#}", error).contains("enough"), "{:#}", error);
+    assert_eq!(stop.0, 1);
+}
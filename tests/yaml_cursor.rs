@@ -0,0 +1,95 @@
+//! The cursor skips whole nodes, however they are nested, and gives up on
+//! collections nested too deeply.
+
+use louis_migrate_yaml::yaml_events::{Event, YamlCursor, MAX_DEPTH};
+
+/// Read the start of `source`, a mapping, up to its first value.
+fn cursor_at_first_value(source: &str) -> YamlCursor<'_> {
+    let mut cursor = YamlCursor::new(source).unwrap();
+    cursor.read_stream_start().unwrap();
+    cursor.read_document_start().unwrap();
+    cursor.read_mapping_start().unwrap();
+    assert_eq!(cursor.read_scalar().unwrap(), "skipped");
+    cursor
+}
+
+/// Check that the cursor is at the key `next`, followed by the end of the
+/// stream.
+fn assert_at_last_key(cursor: &mut YamlCursor) {
+    assert_eq!(cursor.read_scalar().unwrap(), "next");
+    assert_eq!(cursor.read_scalar().unwrap(), "value");
+    cursor.read_mapping_end().unwrap();
+    cursor.read_document_end().unwrap();
+    cursor.read_stream_end().unwrap();
+}
+
+#[test]
+fn nested_flow_collections_are_skipped() {
+    let source = "skipped: [a, {b: [c, d], e: {}}, [[f]], []]\nnext: value\n";
+    let mut cursor = cursor_at_first_value(source);
+    cursor.skip_value().unwrap();
+    assert_at_last_key(&mut cursor);
+}
+
+#[test]
+fn nested_block_collections_are_skipped() {
+    let source = "\
+skipped:
+  - a
+  - b:
+      - c
+      - d: {e: f}
+    g: h
+  -
+    - - i
+next: value
+";
+    let mut cursor = cursor_at_first_value(source);
+    cursor.skip_value().unwrap();
+    assert_at_last_key(&mut cursor);
+}
+
+#[test]
+fn the_rest_of_a_collection_is_skipped() {
+    let source = "skipped: {a: [b, {c: d}], e: f}\nnext: value\n";
+    let mut cursor = cursor_at_first_value(source);
+    let first = cursor.next_event().unwrap().unwrap();
+    assert_eq!(first, Event::MappingStart);
+    cursor.skip_subtree(&first).unwrap();
+    assert_at_last_key(&mut cursor);
+
+    let mut cursor = cursor_at_first_value("skipped: scalar\nnext: value\n");
+    let first = cursor.next_event().unwrap().unwrap();
+    cursor.skip_subtree(&first).unwrap();
+    assert_at_last_key(&mut cursor);
+}
+
+#[test]
+fn collections_nested_too_deeply_are_an_error() {
+    let nested = |depth| {
+        format!(
+            "skipped: {}{}\nnext: value\n",
+            "[".repeat(depth),
+            "]".repeat(depth)
+        )
+    };
+
+    // the mapping around the value is one level already
+    let source = nested(MAX_DEPTH - 1);
+    let mut cursor = cursor_at_first_value(&source);
+    cursor.skip_value().unwrap();
+    assert_at_last_key(&mut cursor);
+
+    let source = nested(MAX_DEPTH);
+    let mut cursor = cursor_at_first_value(&source);
+    let error = cursor.skip_value().unwrap_err();
+    assert!(
+        format!("{:#}", error).contains(&format!("nested deeper than {} levels", MAX_DEPTH)),
+        "{:#}",
+        error
+    );
+    assert!(
+        cursor.peek().is_none(),
+        "the cursor yields nothing after an error"
+    );
+}